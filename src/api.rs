@@ -0,0 +1,206 @@
+use bitcoin::consensus::encode::serialize_hex;
+use bitcoin::{psbt, Network};
+use wasm_bindgen::prelude::*;
+use yew::{Callback, ComponentLink};
+
+use crate::app::{Psbt, PsbtProps};
+use crate::finalize;
+use crate::json::psbt_to_json;
+use crate::model::{parse_network, parse_psbt, PsbtMessage};
+use crate::units::Unit;
+
+fn js_error<E: std::fmt::Display>(e: E) -> JsValue {
+    js_sys::Error::new(&e.to_string()).into()
+}
+
+fn parse(psbt: &str) -> Result<psbt::Psbt, JsValue> {
+    parse_psbt(psbt).map_err(js_error)
+}
+
+fn network_or_default(network: Option<String>) -> Result<Network, JsValue> {
+    network
+        .map(|n| parse_network(&n).map_err(js_error))
+        .unwrap_or(Ok(Network::Testnet))
+}
+
+/// Decode a PSBT in base64 or hex to an object in the same format as Bitcoin Core's `decodepsbt`
+#[wasm_bindgen]
+pub fn decode(psbt: &str, network: Option<String>) -> Result<JsValue, JsValue> {
+    let json = psbt_to_json(&parse(psbt)?, network_or_default(network)?);
+    js_sys::JSON::parse(&json.to_string())
+}
+
+fn edit(
+    psbt: &str,
+    kind: &str,
+    index: usize,
+    field: &str,
+    values: Vec<JsValue>,
+    remove: bool,
+) -> Result<String, JsValue> {
+    let values = values
+        .iter()
+        .map(|v| {
+            v.as_string()
+                .ok_or_else(|| js_error("values must be strings"))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let values = values.iter().map(String::as_str).collect::<Vec<_>>();
+
+    let psbt = parse(psbt)?;
+    let msg =
+        PsbtMessage::from_strings(&psbt, kind, index, field, &values, remove).map_err(js_error)?;
+    let mut psbt = Some(psbt);
+    msg.apply_to(&mut psbt);
+
+    Ok(psbt.unwrap().to_string())
+}
+
+/// Set a field of an input or output. Values use the same formats as the fields of the editor,
+/// maps take the key followed by the value. Returns the edited PSBT in base64
+#[wasm_bindgen]
+pub fn apply_edit(
+    psbt: &str,
+    kind: &str,
+    index: usize,
+    field: &str,
+    values: Vec<JsValue>,
+) -> Result<String, JsValue> {
+    edit(psbt, kind, index, field, values, false)
+}
+
+/// Clear a field of an input or output, or remove the entry with the key in `key` from a map.
+/// Returns the edited PSBT in base64
+#[wasm_bindgen]
+pub fn remove_field(
+    psbt: &str,
+    kind: &str,
+    index: usize,
+    field: &str,
+    key: Vec<JsValue>,
+) -> Result<String, JsValue> {
+    edit(psbt, kind, index, field, key, true)
+}
+
+/// Combine all the PSBTs, which must have the same unsigned transaction. Returns the result in
+/// base64
+#[wasm_bindgen]
+pub fn combine(psbts: Vec<JsValue>) -> Result<String, JsValue> {
+    let mut psbts = psbts.iter().map(|psbt| {
+        psbt.as_string()
+            .ok_or_else(|| js_error("PSBTs must be strings"))
+            .and_then(|psbt| parse(&psbt))
+    });
+
+    let mut combined = psbts
+        .next()
+        .ok_or_else(|| js_error("nothing to combine"))??;
+    for psbt in psbts {
+        combined.combine(psbt?).map_err(js_error)?;
+    }
+
+    Ok(combined.to_string())
+}
+
+/// Finalize the inputs that use standard scripts. Returns the finalized PSBT in base64, or throws
+/// listing the inputs that couldn't be finalized
+#[wasm_bindgen]
+pub fn finalize(psbt: &str) -> Result<String, JsValue> {
+    let mut psbt = parse(psbt)?;
+    finalize::finalize(&mut psbt).map_err(|errors| js_error(errors.join("\n")))?;
+
+    Ok(psbt.to_string())
+}
+
+/// Extract the hex of the signed transaction from a finalized PSBT
+#[wasm_bindgen]
+pub fn extract(psbt: &str) -> Result<String, JsValue> {
+    let tx = finalize::extract(parse(psbt)?).map_err(js_error)?;
+
+    Ok(serialize_hex(&tx))
+}
+
+/// Fee paid by the transaction in satoshis, it requires the spent outputs of every input
+#[wasm_bindgen]
+pub fn fee(psbt: &str) -> Result<f64, JsValue> {
+    let fee = parse(psbt)?.fee().map_err(js_error)?;
+
+    Ok(fee.to_sat() as f64)
+}
+
+/// Editor mounted in a page by [`mount_editor`]
+#[wasm_bindgen]
+pub struct Editor {
+    link: ComponentLink<Psbt>,
+}
+
+#[wasm_bindgen]
+impl Editor {
+    /// Replace the PSBT shown in the editor, or clear it with `null`. The change is added to
+    /// the history like any other edit
+    pub fn set_psbt(&self, psbt: Option<String>) -> Result<(), JsValue> {
+        let psbt = psbt.map(|psbt| parse(&psbt)).transpose()?;
+        self.link.send_message(PsbtMessage::Labelled(
+            "Set by the page".into(),
+            Box::new(PsbtMessage::ChangePsbt(psbt)),
+        ));
+
+        Ok(())
+    }
+
+    pub fn undo(&self) {
+        self.link.send_message(PsbtMessage::Undo);
+    }
+
+    pub fn redo(&self) {
+        self.link.send_message(PsbtMessage::Redo);
+    }
+}
+
+/// Mount the editor into `element`, replacing its content. `onchange` is called with the PSBT in
+/// base64 after every edit, or with `null` when it's cleared. With `read_only` the values are shown
+/// as text that can't be edited. Amounts are shown in `unit`: BTC, mBTC, bits or sats. The page
+/// is expected to load the Bootstrap styles, the editor doesn't touch the URL or the local storage
+#[wasm_bindgen]
+pub fn mount_editor(
+    element: web_sys::Element,
+    psbt: Option<String>,
+    network: Option<String>,
+    onchange: Option<js_sys::Function>,
+    read_only: Option<bool>,
+    unit: Option<String>,
+) -> Result<Editor, JsValue> {
+    let initial = psbt.map(|psbt| parse(&psbt)).transpose()?;
+    let unit = match unit {
+        Some(unit) => unit.parse::<Unit>().map_err(js_error)?,
+        None => Unit::default(),
+    };
+    let onchange = match onchange {
+        Some(function) => Callback::from(move |psbt: Option<psbt::Psbt>| {
+            let value = psbt
+                .map(|psbt| JsValue::from(psbt.to_string()))
+                .unwrap_or(JsValue::NULL);
+            if let Err(e) = function.call1(&JsValue::NULL, &value) {
+                log::warn!("onchange callback failed: {:?}", e);
+            }
+        }),
+        None => Callback::noop(),
+    };
+
+    let props = PsbtProps {
+        network: network_or_default(network)?,
+        unit,
+        hardened_style: Default::default(),
+        persist: false,
+        active: false,
+        initial,
+        initial_history: Default::default(),
+        onchange,
+        diff: None,
+        read_only: read_only.unwrap_or_default(),
+        self_link: Default::default(),
+    };
+    let link = yew::App::<Psbt>::new().mount_with_props(element, props);
+
+    Ok(Editor { link })
+}
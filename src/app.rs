@@ -1,6 +1,5 @@
 use std::cell::RefCell;
 use std::collections::BTreeMap;
-use std::convert::TryInto;
 use std::ops::Deref;
 use std::rc::Rc;
 use std::str::FromStr;
@@ -8,26 +7,36 @@ use std::str::FromStr;
 #[allow(unused_imports)]
 use log::*;
 use yew::prelude::*;
+use yew::services::keyboard::{KeyListenerHandle, KeyboardService};
+use yew::services::reader::{File, FileData, ReaderService, ReaderTask};
 
 use bitcoin::{
     bip32,
-    ecdsa::Signature,
     psbt::{self, PsbtSighashType},
-    secp256k1,
     sighash::EcdsaSighashType,
-    Address, Network, ScriptBuf, Transaction, TxIn, TxOut, Witness,
+    Address, Network, Transaction, TxIn, TxOut,
 };
 
 use crate::bootstrap::*;
+use crate::derivation::{self, HardenedStyle};
+use crate::diff::*;
+use crate::export::*;
 use crate::fields::*;
 use crate::history::*;
+use crate::json::*;
+use crate::lint::{self, Finding, Location};
+use crate::model::*;
 use crate::navbar::*;
-
-const EXAMPLE_PSBTS: [(&str, &str); 3] = [
-    ("One P2PKH input, outputs are empty", "cHNidP8BAHUCAAAAASaBcTce3/KF6Tet7qSze3gADAVmy7OtZGQXE8pCFxv2AAAAAAD+////AtPf9QUAAAAAGXapFNDFmQPFusKGh2DpD9UhpGZap2UgiKwA4fUFAAAAABepFDVF5uM7gyxHBQ8k0+65PJwDlIvHh7MuEwAAAQD9pQEBAAAAAAECiaPHHqtNIOA3G7ukzGmPopXJRjr6Ljl/hTPMti+VZ+UBAAAAFxYAFL4Y0VKpsBIDna89p95PUzSe7LmF/////4b4qkOnHf8USIk6UwpyN+9rRgi7st0tAXHmOuxqSJC0AQAAABcWABT+Pp7xp0XpdNkCxDVZQ6vLNL1TU/////8CAMLrCwAAAAAZdqkUhc/xCX/Z4Ai7NK9wnGIZeziXikiIrHL++E4sAAAAF6kUM5cluiHv1irHU6m80GfWx6ajnQWHAkcwRAIgJxK+IuAnDzlPVoMR3HyppolwuAJf3TskAinwf4pfOiQCIAGLONfc0xTnNMkna9b7QPZzMlvEuqFEyADS8vAtsnZcASED0uFWdJQbrUqZY3LLh+GFbTZSYG2YVi/jnF6efkE/IQUCSDBFAiEA0SuFLYXc2WHS9fSrZgZU327tzHlMDDPOXMMJ/7X85Y0CIGczio4OFyXBl/saiK9Z9R5E5CVbIBZ8hoQDHAXR8lkqASECI7cr7vCWXRC+B3jv7NYfysb3mk6haTkzgHNEZPhPKrMAAAAAAAAA"),
-    ("One P2SH-P2WSH input of a 2-of-2, with metadata", "cHNidP8BAFUCAAAAASeaIyOl37UfxF8iD6WLD8E+HjNCeSqF1+Ns1jM7XLw5AAAAAAD/////AaBa6gsAAAAAGXapFP/pwAYQl8w7Y28ssEYPpPxCfStFiKwAAAAAAAEBIJVe6gsAAAAAF6kUY0UgD2jRieGtwN8cTRbqjxTA2+uHIgIDsTQcy6doO2r08SOM1ul+cWfVafrEfx5I1HVBhENVvUZGMEMCIAQktY7/qqaU4VWepck7v9SokGQiQFXN8HC2dxRpRC0HAh9cjrD+plFtYLisszrWTt5g6Hhb+zqpS5m9+GFR25qaAQEEIgAgdx/RitRZZm3Unz1WTj28QvTIR3TjYK2haBao7UiNVoEBBUdSIQOxNBzLp2g7avTxI4zW6X5xZ9Vp+sR/HkjUdUGEQ1W9RiED3lXR4drIBeP4pYwfv5uUwC89uq/hJ/78pJlfJvggg71SriIGA7E0HMunaDtq9PEjjNbpfnFn1Wn6xH8eSNR1QYRDVb1GELSmumcAAACAAAAAgAQAAIAiBgPeVdHh2sgF4/iljB+/m5TALz26r+En/vykmV8m+CCDvRC0prpnAAAAgAAAAIAFAACAAAA="),
-    ("Revault Unvault TX", "cHNidP8BAIkCAAAAAV+HumeWIAtm1c9hvTgUme25aogn3EvF1+vV7KYKKKdYAAAAAAD9////AkANAwAAAAAAIgAgXA0s+qynDjinXOmpJ/Qhuj87xEB7YcLEVdz7OX5B+l8wdQAAAAAAACIAIKj/nBsC9abIRvrVxbaHRVSZNtMZjsOSosgybAbmDAtwAAAAAAABASuIlAMAAAAAACIAIKI1Ly2kCXvsF5kWmgyAGmH2th23XwgbIDHRo7sHndheAQMEAQAAAAEFR1IhAtk/sjHYB5gv7nUSr0k25UlmeCn+7ztrilD5aKBYhOZ/IQI+TfqYOB5AvGLZO2C3OWNepPtB2MXltlovJy9aNEUezFKuIgYCPk36mDgeQLxi2TtgtzljXqT7QdjF5bZaLycvWjRFHswIeMYQoQoAAAAiBgLZP7Ix2AeYL+51Eq9JNuVJZngp/u87a4pQ+WigWITmfwgbQV1zCgAAAAAAAA=="),
-];
+use crate::network::network_mismatch;
+use crate::persistence;
+use crate::qr::{QrExport, QrImport};
+use crate::share::*;
+use crate::shortcuts::{self, Shortcut};
+use crate::tabs::*;
+use crate::template::{spend_type, Template};
+use crate::units::Unit;
+use crate::upgrade::*;
+use crate::xpub::XpubConverter;
 
 pub trait ParentMessage<T, X = ()> {
     fn build_message(data: T, tag: Option<X>) -> Self;
@@ -36,31 +45,231 @@ pub trait ParentMessage<T, X = ()> {
 #[derive(Clone)]
 pub struct WeakComponentLink<COMP: Component>(Rc<RefCell<Option<ComponentLink<COMP>>>>);
 
+impl<COMP: Component> Default for WeakComponentLink<COMP> {
+    fn default() -> Self {
+        WeakComponentLink(Rc::new(RefCell::new(None)))
+    }
+}
+
+impl<COMP: Component> WeakComponentLink<COMP> {
+    pub fn send_message(&self, msg: COMP::Message) {
+        if let Some(link) = self.0.borrow().deref() {
+            link.send_message(msg);
+        }
+    }
+}
+
+/// A PSBT open in its own tab, with its own history and network
+struct Tab {
+    id: usize,
+    name: String,
+    network: Network,
+
+    link: WeakComponentLink<Psbt>,
+    // Copy of the PSBT held by the component, kept in sync for the cross-tab actions
+    psbt: Option<psbt::Psbt>,
+    initial_history: History,
+}
+
+impl Tab {
+    fn new(id: usize, network: Network, psbt: Option<psbt::Psbt>, history: History) -> Self {
+        Tab {
+            id,
+            name: format!("PSBT {}", id + 1),
+            network,
+            link: Default::default(),
+            psbt,
+            initial_history: history,
+        }
+    }
+}
+
 pub struct App {
     link: ComponentLink<Self>,
-    network: Network,
 
-    psbt: WeakComponentLink<Psbt>,
+    tabs: Vec<Tab>,
+    active: usize,
+    next_tab_id: usize,
+    other_tab: Option<usize>,
+    tab_error: Option<String>,
+    diff_with: Option<usize>,
+    diff: Option<PsbtDiff>,
+    persist: bool,
+    /// Set by the `readonly` URL parameter, hides everything that can change the PSBT
+    read_only: bool,
+    unit: Unit,
+    hardened_style: HardenedStyle,
+
+    reader_task: Option<ReaderTask>,
+    load_error: Option<String>,
+    load_warning: Option<String>,
+
+    _key_listener: KeyListenerHandle,
 }
 
 #[derive(Debug)]
 pub enum AppMsg {
     SetNetwork(Network),
-    SetPsbt(&'static str),
-
+    SetUnit(Unit),
+    SetHardenedStyle(HardenedStyle),
+    SetPsbt(&'static str, &'static str),
+    LoadFile(Option<File>),
+    FileLoaded(FileData),
+    QrImported(psbt::Psbt),
+    SetPersist(bool),
+    ClearSavedData,
+
+    NewTab,
+    SelectTab(usize),
+    CloseTab(usize),
+    RenameTab(String),
+    TabChanged(usize, Option<psbt::Psbt>),
+    SelectOtherTab(Option<usize>),
+    CombineTab,
+    DiffTab,
+    CloseDiff,
+
+    Shortcut(Shortcut),
     Undo,
     Redo,
 }
 
+impl App {
+    fn active_tab(&self) -> &Tab {
+        self.tabs.iter().find(|tab| tab.id == self.active).unwrap()
+    }
+
+    fn active_tab_mut(&mut self) -> &mut Tab {
+        let active = self.active;
+        self.tabs.iter_mut().find(|tab| tab.id == active).unwrap()
+    }
+
+    fn other_tab(&self) -> Option<&Tab> {
+        self.tabs.iter().find(|tab| Some(tab.id) == self.other_tab)
+    }
+
+    /// Compare the active tab with the one selected for the diff, keeping the result up to date
+    /// while either of them is edited
+    fn refresh_diff(&mut self) {
+        let other = match self.tabs.iter().find(|tab| Some(tab.id) == self.diff_with) {
+            Some(other) => other,
+            None => return,
+        };
+
+        match (&self.active_tab().psbt, &other.psbt) {
+            (Some(ours), Some(theirs)) => {
+                self.diff = Some(PsbtDiff::new(ours, theirs));
+                self.tab_error = None;
+            }
+            _ => {
+                self.diff = None;
+                self.tab_error = Some("Both tabs must contain a PSBT".into());
+            }
+        }
+    }
+
+    fn close_diff(&mut self) {
+        self.diff_with = None;
+        self.diff = None;
+    }
+
+    fn view_diff(&self) -> Html {
+        let (diff, other) = match (
+            &self.diff,
+            self.tabs.iter().find(|tab| Some(tab.id) == self.diff_with),
+        ) {
+            (Some(diff), Some(other)) => (diff, other),
+            _ => return html! {},
+        };
+
+        let summary = diff.summary();
+        html! {
+            <>
+            {
+                if diff.unsigned_tx {
+                    html! {
+                        <div class="alert alert-danger fw-bold">
+                            <i class="bi bi-exclamation-octagon-fill"></i>
+                            { " The unsigned transaction differs from the one in " }{ &other.name }{ ": these PSBTs don't spend the same inputs to the same outputs!" }
+                        </div>
+                    }
+                } else {
+                    html! {}
+                }
+            }
+            <div class="alert alert-info">
+                <button type="button" class="btn-close float-end" aria-label="Close" onclick=self.link.callback(|_| AppMsg::CloseDiff)></button>
+                {
+                    if diff.is_empty() {
+                        html! { <>{ "Identical to " }<strong>{ &other.name }</strong></> }
+                    } else {
+                        html! {
+                            <>
+                            { "Changes compared to " }<strong>{ &other.name }</strong>{ ":" }
+                            <ul class="mb-0">{ for summary.iter().map(|s| html! { <li><code>{ s }</code></li> }) }</ul>
+                            </>
+                        }
+                    }
+                }
+            </div>
+            </>
+        }
+    }
+}
+
 impl Component for App {
     type Message = AppMsg;
     type Properties = ();
 
     fn create(_: Self::Properties, link: ComponentLink<Self>) -> Self {
+        let persist = persistence::is_enabled();
+        let saved = match persist {
+            true => persistence::load(),
+            false => Default::default(),
+        };
+
+        // A PSBT in the URL takes precedence over whatever was saved locally
+        let (initial_psbt, initial_history, network) = match read_location() {
+            (Some(psbt), network) => (Some(psbt), History::default(), network),
+            (None, network) => (saved.psbt, saved.history, network),
+        };
+        let network = network.or(saved.network).unwrap_or(Network::Testnet);
+
+        let key_listener = KeyboardService::register_key_down(
+            &yew::utils::window(),
+            link.batch_callback(|e: KeyboardEvent| {
+                let shortcut = shortcuts::shortcut_for(
+                    &e.key(),
+                    e.ctrl_key() || e.meta_key(),
+                    e.shift_key(),
+                    e.alt_key(),
+                    shortcuts::is_editing(&e),
+                )?;
+                e.prevent_default();
+                Some(AppMsg::Shortcut(shortcut))
+            }),
+        );
+
         App {
             link,
-            network: Network::Testnet,
-            psbt: WeakComponentLink(Rc::new(RefCell::new(None))),
+
+            tabs: vec![Tab::new(0, network, initial_psbt, initial_history)],
+            active: 0,
+            next_tab_id: 1,
+            other_tab: None,
+            tab_error: None,
+            diff_with: None,
+            diff: None,
+            persist,
+            read_only: read_only_requested(),
+            unit: persistence::load_unit().unwrap_or_default(),
+            hardened_style: persistence::load_hardened_style().unwrap_or_default(),
+
+            reader_task: None,
+            load_error: None,
+            load_warning: None,
+
+            _key_listener: key_listener,
         }
     }
 
@@ -69,48 +278,284 @@ impl Component for App {
     }
 
     fn update(&mut self, msg: Self::Message) -> ShouldRender {
-        let send_psbt_message = |m| {
-            if let Some(link) = self.psbt.0.borrow().deref() {
-                link.send_message(m);
+        match msg {
+            AppMsg::SetNetwork(network) => {
+                self.active_tab_mut().network = network;
+                if self.persist {
+                    persistence::save_network(network);
+                }
             }
-        };
+            AppMsg::SetUnit(unit) => {
+                self.unit = unit;
+                persistence::save_unit(unit);
+            }
+            AppMsg::SetHardenedStyle(style) => {
+                self.hardened_style = style;
+                persistence::save_hardened_style(style);
+            }
+            AppMsg::SetPersist(true) => {
+                persistence::enable();
+                persistence::save_network(self.active_tab().network);
+                self.persist = true;
+            }
+            AppMsg::SetPersist(false) => {
+                persistence::clear();
+                self.persist = false;
+            }
+            AppMsg::ClearSavedData => {
+                persistence::clear();
+                self.persist = false;
+                // The PSBT is also in the fragment of the URL, which ends up in the browser history
+                update_location(None, self.active_tab().network);
+            }
+            AppMsg::SetPsbt(label, psbt) => {
+                self.active_tab().link.send_message(PsbtMessage::Labelled(
+                    format!("Loaded example \"{}\"", label),
+                    Box::new(PsbtMessage::ChangePsbt(psbt::Psbt::from_str(psbt).ok())),
+                ))
+            }
+            AppMsg::LoadFile(Some(file)) => {
+                match ReaderService::read_file(file, self.link.callback(AppMsg::FileLoaded)) {
+                    Ok(task) => self.reader_task = Some(task),
+                    Err(e) => self.load_error = Some(e.to_string()),
+                }
+            }
+            AppMsg::LoadFile(None) => return false,
+            AppMsg::FileLoaded(data) => {
+                self.reader_task = None;
+
+                let mut skipped = vec![];
+                let result = match data.content.first() {
+                    Some(b'{') => serde_json::from_slice(&data.content)
+                        .map_err(ParseError::from)
+                        .and_then(|json| psbt_from_json(&json))
+                        .map(|import| {
+                            skipped = import.skipped;
+                            import.psbt
+                        }),
+                    _ => parse_psbt_bytes(&data.content),
+                };
+                self.load_warning = match skipped.is_empty() {
+                    true => None,
+                    false => Some(format!(
+                        "{}: the following fields couldn't be imported: {}",
+                        data.name,
+                        skipped.join(", ")
+                    )),
+                };
+                match result {
+                    Ok(psbt) => {
+                        self.load_error = None;
+                        self.active_tab().link.send_message(PsbtMessage::Labelled(
+                            format!("Loaded {}", data.name),
+                            Box::new(PsbtMessage::ChangePsbt(Some(psbt))),
+                        ));
+                    }
+                    Err(e) => self.load_error = Some(format!("{}: {}", data.name, e)),
+                }
+            }
+            AppMsg::QrImported(psbt) => {
+                self.load_error = None;
+                self.active_tab().link.send_message(PsbtMessage::Labelled(
+                    "Imported from QR code".to_string(),
+                    Box::new(PsbtMessage::ChangePsbt(Some(psbt))),
+                ));
+            }
+            AppMsg::NewTab => {
+                let network = self.active_tab().network;
+                self.tabs.push(Tab::new(
+                    self.next_tab_id,
+                    network,
+                    None,
+                    History::default(),
+                ));
+                self.active = self.next_tab_id;
+                self.next_tab_id += 1;
+                self.other_tab = None;
+                self.close_diff();
+            }
+            AppMsg::SelectTab(id) => {
+                if id == self.active {
+                    return false;
+                }
+                self.active = id;
+                if self.other_tab == Some(id) {
+                    self.other_tab = None;
+                }
+                self.tab_error = None;
+                self.close_diff();
+                if self.persist {
+                    persistence::save_network(self.active_tab().network);
+                }
+            }
+            AppMsg::CloseTab(id) => {
+                let index = match self.tabs.iter().position(|tab| tab.id == id) {
+                    Some(index) if self.tabs.len() > 1 => index,
+                    _ => return false,
+                };
+                self.tabs.remove(index);
+                if self.active == id {
+                    self.active = self.tabs[index.saturating_sub(1)].id;
+                }
+                if self.other_tab == Some(id) {
+                    self.other_tab = None;
+                }
+                self.close_diff();
+            }
+            AppMsg::RenameTab(name) => match name.trim() {
+                "" => {}
+                name => self.active_tab_mut().name = name.to_string(),
+            },
+            AppMsg::TabChanged(id, psbt) => {
+                if let Some(tab) = self.tabs.iter_mut().find(|tab| tab.id == id) {
+                    tab.psbt = psbt;
+                }
+                self.refresh_diff();
+            }
+            AppMsg::SelectOtherTab(id) => {
+                self.other_tab = id;
+                self.tab_error = None;
+                self.close_diff();
+            }
+            AppMsg::CombineTab => {
+                let (other, other_name) = match self
+                    .other_tab()
+                    .and_then(|tab| Some((tab.psbt.clone()?, tab.name.clone())))
+                {
+                    Some(other) => other,
+                    None => {
+                        self.tab_error = Some("The other tab doesn't contain a PSBT".into());
+                        return true;
+                    }
+                };
 
-        match msg {
-            AppMsg::SetNetwork(network) => self.network = network,
-            AppMsg::SetPsbt(psbt) => {
-                send_psbt_message(PsbtMessage::ChangePsbt(psbt::Psbt::from_str(psbt).ok()))
+                let active = self.active_tab();
+                let combined = match active.psbt.clone() {
+                    Some(mut psbt) => psbt.combine(other).map(|_| psbt),
+                    None => Ok(other),
+                };
+                self.tab_error = match combined {
+                    Ok(psbt) => {
+                        active.link.send_message(PsbtMessage::Labelled(
+                            format!("Combined with {}", other_name),
+                            Box::new(PsbtMessage::ChangePsbt(Some(psbt))),
+                        ));
+                        None
+                    }
+                    Err(e) => Some(format!("Can't combine the PSBTs: {}", e)),
+                };
+            }
+            AppMsg::DiffTab => {
+                self.diff_with = self.other_tab;
+                self.refresh_diff();
             }
-            AppMsg::Undo => send_psbt_message(PsbtMessage::Undo),
-            AppMsg::Redo => send_psbt_message(PsbtMessage::Redo),
+            AppMsg::CloseDiff => self.close_diff(),
+            AppMsg::Shortcut(shortcut) => {
+                match shortcut {
+                    Shortcut::Undo | Shortcut::Redo if self.read_only => {}
+                    Shortcut::Undo => self.active_tab().link.send_message(PsbtMessage::Undo),
+                    Shortcut::Redo => self.active_tab().link.send_message(PsbtMessage::Redo),
+                    Shortcut::NextCard => shortcuts::focus_card(1),
+                    Shortcut::PreviousCard => shortcuts::focus_card(-1),
+                    Shortcut::FirstInput => shortcuts::focus_first(".psbt-input"),
+                    Shortcut::FirstOutput => shortcuts::focus_first(".psbt-output"),
+                    Shortcut::FocusPsbt => shortcuts::focus_first(".psbt-field textarea"),
+                    Shortcut::Blur => shortcuts::blur(),
+                }
+                return false;
+            }
+            AppMsg::Undo => self.active_tab().link.send_message(PsbtMessage::Undo),
+            AppMsg::Redo => self.active_tab().link.send_message(PsbtMessage::Redo),
         }
 
         true
     }
 
     fn view(&self) -> Html {
+        let read_only = self.read_only;
+        let ondrop = self.link.callback(move |e: DragEvent| {
+            e.prevent_default();
+            AppMsg::LoadFile(
+                e.data_transfer()
+                    .and_then(|dt| dt.files())
+                    .and_then(|files| files.get(0))
+                    .filter(|_| !read_only),
+            )
+        });
+        let onfile = self.link.callback(|data| match data {
+            ChangeData::Files(files) => AppMsg::LoadFile(files.get(0)),
+            _ => AppMsg::LoadFile(None),
+        });
+        let tab_infos = self
+            .tabs
+            .iter()
+            .map(|tab| TabInfo {
+                id: tab.id,
+                name: tab.name.clone(),
+            })
+            .collect::<Vec<_>>();
+
         html! {
             <ContainerFluid>
-                <Navbar network=self.network parent=self.link.clone() />
-
-                <Container>
-                    <div class="d-flex justify-content-between align-items-center">
-                        <h2 class="my-3">{ "Bitcoin PSBT Explorer" }</h2>
-                        <div class="dropdown">
-                            <button class="btn btn-outline-secondary dropdown-toggle" type="button" id="examplesDropdown" data-bs-toggle="dropdown" aria-expanded="false">
-                                { "Examples "}
-                            </button>
-                                <ul class="dropdown-menu dropdown-menu-end" aria-labelledby="examplesDropdown">
-                                    {
-                                        for EXAMPLE_PSBTS.iter().map(|(label, psbt)| html! {
-                                                <li><a class="dropdown-item" onclick=self.link.callback(move |_| AppMsg::SetPsbt(psbt))>{ label }</a></li>
-                                        })
-                                    }
-                                </ul>
+                <Navbar network=self.active_tab().network unit=self.unit hardened_style=self.hardened_style persist=self.persist read_only=self.read_only parent=self.link.clone() />
+
+                <div ondragover=Callback::from(|e: DragEvent| e.prevent_default()) ondrop=ondrop>
+                    <Container>
+                        <div class="d-flex justify-content-between align-items-center">
+                            <h2 class="my-3">{ "Bitcoin PSBT Explorer" }</h2>
+                            <div class=classes!("d-flex", self.read_only.then_some("d-none"))>
+                                <QrImport onload=self.link.callback(AppMsg::QrImported) />
+                                <XpubConverter network=self.active_tab().network />
+                                <label class="btn btn-outline-secondary me-2">
+                                    <i class="bi bi-folder2-open"></i>{ " Open file" }
+                                    <input type="file" class="d-none" accept=".psbt,.txt,.json" onchange=onfile />
+                                </label>
+                                <div class="dropdown">
+                                    <button class="btn btn-outline-secondary dropdown-toggle" type="button" id="examplesDropdown" data-bs-toggle="dropdown" aria-expanded="false">
+                                        { "Examples "}
+                                    </button>
+                                        <ul class="dropdown-menu dropdown-menu-end" aria-labelledby="examplesDropdown">
+                                            {
+                                                for EXAMPLE_PSBTS.iter().map(|(label, psbt)| html! {
+                                                        <li><a class="dropdown-item" onclick=self.link.callback(move |_| AppMsg::SetPsbt(label, psbt))>{ label }</a></li>
+                                                })
+                                            }
+                                        </ul>
+                                </div>
+                            </div>
                         </div>
-                    </div>
 
-                    <Psbt network=self.network self_link=WeakComponentLink(Rc::clone(&self.psbt.0)) />
-                </Container>
+                        {
+                            self.load_error.as_ref().map(|e| html! { <div class="alert alert-danger">{ e }</div> }).unwrap_or_default()
+                        }
+                        {
+                            self.load_warning.as_ref().map(|w| html! { <div class="alert alert-warning">{ w }</div> }).unwrap_or_default()
+                        }
+
+                        {
+                            match self.read_only {
+                                true => html! {},
+                                false => html! { <TabBar tabs=tab_infos active=self.active other=self.other_tab parent=self.link.clone() /> },
+                            }
+                        }
+                        {
+                            self.tab_error.as_ref().map(|e| html! { <div class="alert alert-danger">{ e }</div> }).unwrap_or_default()
+                        }
+                        { self.view_diff() }
+
+                        {
+                            for self.tabs.iter().map(|tab| {
+                                let id = tab.id;
+                                let active = id == self.active;
+                                html! {
+                                    <div key=id.to_string() class=classes!(if active { "active-tab" } else { "d-none" })>
+                                        <Psbt network=tab.network unit=self.unit hardened_style=self.hardened_style persist=self.persist active=active read_only=self.read_only diff=if active { self.diff.clone() } else { None } initial=tab.psbt.clone() initial_history=tab.initial_history.clone() onchange=self.link.callback(move |psbt| AppMsg::TabChanged(id, psbt)) self_link=tab.link.clone() />
+                                    </div>
+                                }
+                            })
+                        }
+                    </Container>
+                </div>
             </ContainerFluid>
         }
     }
@@ -128,67 +573,65 @@ pub struct Psbt {
 #[derive(Clone, Properties)]
 pub struct PsbtProps {
     pub network: Network,
+    #[prop_or_default]
+    pub unit: Unit,
+    #[prop_or_default]
+    pub hardened_style: HardenedStyle,
+    #[prop_or_default]
+    pub persist: bool,
+    /// Only the PSBT in the active tab is reflected in the URL and in the local storage
+    #[prop_or(true)]
+    pub active: bool,
+    #[prop_or_default]
+    pub initial: Option<psbt::Psbt>,
+    #[prop_or_default]
+    pub initial_history: History,
+    #[prop_or_default]
+    pub onchange: Callback<Option<psbt::Psbt>>,
+    /// Differences with the PSBT in another tab, highlighted in the cards
+    #[prop_or_default]
+    pub diff: Option<PsbtDiff>,
+    /// Show the PSBT without allowing edits
+    #[prop_or_default]
+    pub read_only: bool,
     pub self_link: WeakComponentLink<Psbt>,
 }
 
-#[derive(Clone, Debug)]
-pub enum PsbtMessage {
-    ChangePsbt(Option<psbt::Psbt>),
-    ChangeInput(usize, PsbtInputMsg),
-    ChangeOutput(usize, PsbtOutputMsg),
-
-    Undo,
-    Redo,
-
-    None,
-}
-
 impl ParentMessage<Option<psbt::Psbt>> for PsbtMessage {
     fn build_message(data: Option<psbt::Psbt>, _tag: Option<()>) -> Self {
         PsbtMessage::ChangePsbt(data)
     }
 }
 
-impl PsbtMessage {
-    pub fn apply_to(self, psbt: &mut Option<psbt::Psbt>) -> PsbtMessage {
-        match self {
-            PsbtMessage::ChangePsbt(new_psbt) => {
-                let old = psbt.take();
-                *psbt = new_psbt;
-
-                PsbtMessage::ChangePsbt(old)
-            }
-            PsbtMessage::ChangeInput(index, msg) => psbt
-                .as_mut()
-                .map(|psbt| PsbtMessage::ChangeInput(index, msg.apply_to(&mut psbt.inputs[index])))
-                .unwrap_or(PsbtMessage::None),
-            PsbtMessage::ChangeOutput(index, msg) => psbt
-                .as_mut()
-                .map(|psbt| {
-                    PsbtMessage::ChangeOutput(index, msg.apply_to(&mut psbt.outputs[index]))
-                })
-                .unwrap_or(PsbtMessage::None),
-            _ => PsbtMessage::None,
-        }
-    }
-}
-
 impl Component for Psbt {
     type Message = PsbtMessage;
     type Properties = PsbtProps;
 
     fn create(props: Self::Properties, link: ComponentLink<Self>) -> Self {
         *props.self_link.0.borrow_mut() = Some(link.clone());
+        if props.active {
+            update_location(props.initial.as_ref(), props.network);
+        }
 
         Psbt {
             link,
-            psbt: None,
-            history: Default::default(),
+            psbt: props.initial.clone(),
+            history: props.initial_history.clone(),
             props,
         }
     }
 
     fn change(&mut self, props: Self::Properties) -> ShouldRender {
+        let activated = props.active && !self.props.active;
+        if props.active && props.persist && (activated || !self.props.persist) {
+            persistence::save_psbt(self.psbt.as_ref(), &self.history);
+        }
+        // Only sync the URL when it would change, so that a cleared fragment stays clear until the
+        // next edit
+        if props.active && (activated || props.network != self.props.network) {
+            update_location(self.psbt.as_ref(), props.network);
+        }
+
         self.props = props;
         *self.props.self_link.0.borrow_mut() = Some(self.link.clone());
 
@@ -196,26 +639,85 @@ impl Component for Psbt {
     }
 
     fn update(&mut self, msg: Self::Message) -> ShouldRender {
-        match msg {
+        let changed = match msg {
             PsbtMessage::Undo => self.history.undo(&mut self.psbt),
             PsbtMessage::Redo => self.history.redo(&mut self.psbt),
+            PsbtMessage::JumpTo(position) => self.history.jump_to(position, &mut self.psbt),
+            PsbtMessage::CopyLink => {
+                copy_to_clipboard(&share_link(self.psbt.as_ref(), self.props.network));
+                return false;
+            }
+            PsbtMessage::Batch(msgs) => {
+                self.history
+                    .begin(PsbtMessage::Batch(msgs.clone()).describe());
+                for msg in msgs {
+                    let opposite = msg.apply_to(&mut self.psbt);
+                    self.history.add(opposite, String::new());
+                }
+                self.history.commit();
+
+                true
+            }
             msg => {
+                let description = msg.describe();
+                let field = msg.field_key();
                 let opposite = msg.apply_to(&mut self.psbt);
-                self.history.add(opposite);
+                match field {
+                    Some(field) => {
+                        self.history
+                            .add_edit(opposite, description, field, js_sys::Date::now())
+                    }
+                    None => self.history.add(opposite, description),
+                }
 
                 true
             }
+        };
+
+        if changed {
+            self.props.onchange.emit(self.psbt.clone());
+            if self.props.active {
+                update_location(self.psbt.as_ref(), self.props.network);
+                if self.props.persist {
+                    persistence::save_psbt(self.psbt.as_ref(), &self.history);
+                }
+            }
         }
+        changed
     }
 
     fn view(&self) -> Html {
         type SingleFieldPsbt = SingleField<Option<psbt::Psbt>, Psbt, (), 1>;
 
+        let mut findings = self.psbt.as_ref().map(lint::lint).unwrap_or_default();
+        findings.extend(
+            self.psbt
+                .as_ref()
+                .and_then(|psbt| network_mismatch(psbt, self.props.network)),
+        );
+
         html! {
             <Container class="p-0">
+                { self.view_history() }
                 <Row>
                     <Column xs=12>
-                       <SingleFieldPsbt value=self.psbt.clone() label="PSBT" parent=self.link.clone() />
+                       {
+                           self.psbt.as_ref().map(|psbt| html! {
+                               <div class="d-flex justify-content-end mb-2">
+                                   <button type="button" class="btn btn-outline-secondary me-2" onclick=self.link.callback(|_| PsbtMessage::CopyLink)><i class="bi bi-link-45deg"></i>{ " Copy link" }</button>
+                                   <QrExport psbt=psbt.clone() />
+                                   <ExportMenu psbt=psbt.clone() network=self.props.network />
+                               </div>
+                           }).unwrap_or_default()
+                       }
+                       <div class="psbt-field">
+                           <SingleFieldPsbt value=self.psbt.clone() label="PSBT" read_only=self.props.read_only parent=self.link.clone() />
+                       </div>
+                    </Column>
+
+                    <Column xs=12>
+                        { view_findings(&findings) }
+                        { self.psbt.as_ref().filter(|_| !self.props.read_only).map(|psbt| html! { <NonWitnessUtxoUpgrade psbt=psbt.clone() parent=self.link.clone() /> }).unwrap_or_default() }
                     </Column>
 
                     {
@@ -225,7 +727,7 @@ impl Component for Psbt {
                                 <Column xs=12 md=6 class="order-first">
                                     <h2 class="my-3">{ "Inputs" }</h2>
                                     {
-                                        self.psbt.as_ref().map(|psbt| html! { for psbt.inputs.iter().zip(psbt.unsigned_tx.input.iter()).enumerate().map(|(index, (psbt_input, input))| html!{ <PsbtInput index=index input=input.clone() psbt_input=psbt_input.clone() network=self.props.network parent=self.link.clone() /> }) }).unwrap_or_default()
+                                        self.psbt.as_ref().map(|psbt| html! { for psbt.inputs.iter().zip(psbt.unsigned_tx.input.iter()).enumerate().map(|(index, (psbt_input, input))| html!{ <PsbtInput index=index input=input.clone() psbt_input=psbt_input.clone() network=self.props.network unit=self.props.unit hardened_style=self.props.hardened_style findings=findings_for(&findings, Location::Input(index)) diff=self.props.diff.as_ref().and_then(|d| d.inputs.get(index).cloned()).unwrap_or_default() read_only=self.props.read_only parent=self.link.clone() /> }) }).unwrap_or_default()
                                     }
                                 </Column>
 
@@ -241,7 +743,12 @@ impl Component for Psbt {
                                 <Column xs=12 md=5 class="order-last">
                                     <h2 class="my-3">{ "Outputs" }</h2>
                                     {
-                                        self.psbt.as_ref().map(|psbt | html! { for psbt.outputs.iter().zip(psbt.unsigned_tx.output.iter()).enumerate().map(|(index, (psbt_output, output))| html!{ <PsbtOutput index=index output=output.clone() psbt_output=psbt_output.clone() network=self.props.network parent=self.link.clone() /> }) }).unwrap_or_default()
+                                        self.psbt.as_ref().map(|psbt | html! { for psbt.outputs.iter().zip(psbt.unsigned_tx.output.iter()).enumerate().map(|(index, (psbt_output, output))| html!{ <PsbtOutput index=index output=output.clone() psbt_output=psbt_output.clone() network=self.props.network unit=self.props.unit hardened_style=self.props.hardened_style findings=findings_for(&findings, Location::Output(index)) diff=self.props.diff.as_ref().and_then(|d| d.outputs.get(index).cloned()).unwrap_or_default() read_only=self.props.read_only parent=self.link.clone() /> }) }).unwrap_or_default()
+                                    }
+                                    {
+                                        self.psbt.as_ref().and_then(|psbt| psbt.fee().ok()).map(|fee| html! {
+                                            <p class="text-end">{ "Fee: " }<strong>{ self.props.unit.format(fee) }</strong></p>
+                                        }).unwrap_or_default()
                                     }
                                 </Column>
                             </div>
@@ -253,196 +760,202 @@ impl Component for Psbt {
     }
 }
 
-pub trait Field<const N: usize>: Clone + Sized + std::fmt::Debug {
-    type DeserializeError: std::fmt::Debug;
-
-    fn bip174_serialize(&self) -> [String; N];
-    fn bip174_deserialize(s: [&str; N]) -> Result<Self, Self::DeserializeError>;
-}
-
-impl<T: Field<N>, const N: usize> Field<N> for Option<T> {
-    type DeserializeError = <T as Field<N>>::DeserializeError;
-
-    fn bip174_serialize(&self) -> [String; N] {
-        match self {
-            None => {
-                let v = vec![String::new(); N];
-                v.try_into().unwrap()
-            }
-            Some(s) => s.bip174_serialize(),
+impl Psbt {
+    /// Sidebar listing the history entries, only rendered for the active tab so that its id is
+    /// unique in the page. There's nothing to undo in read-only mode
+    fn view_history(&self) -> Html {
+        if !self.props.active || self.props.read_only {
+            return html! {};
         }
-    }
-    fn bip174_deserialize(s: [&str; N]) -> Result<Self, Self::DeserializeError> {
-        if s.iter().any(|s| s.is_empty()) {
-            Ok(None)
-        } else {
-            Ok(Some(T::bip174_deserialize(s)?))
-        }
-    }
-}
 
-macro_rules! impl_hex_serialize_field {
-    ($type:ty) => {
-        impl Field<1> for $type {
-            type DeserializeError = ParseError;
-
-            fn bip174_deserialize(s: [&str; 1]) -> Result<Self, Self::DeserializeError> {
-                use bitcoin::consensus::encode::deserialize;
-                use bitcoin::hashes::hex::FromHex;
-
-                let data = Vec::<u8>::from_hex(s[0])?;
-                Ok(deserialize(&data)?)
+        let position = self.history.position();
+        let entry = |index: usize, description: &str| {
+            let class = match index.cmp(&position) {
+                std::cmp::Ordering::Equal => Some("active"),
+                std::cmp::Ordering::Greater => Some("text-muted"),
+                std::cmp::Ordering::Less => None,
+            };
+            html! {
+                <button type="button" class=classes!("list-group-item", "list-group-item-action", class) onclick=self.link.callback(move |_| PsbtMessage::JumpTo(index))>
+                    <span class="fw-light me-2">{ index }</span>{ description }
+                </button>
             }
+        };
 
-            fn bip174_serialize(&self) -> [String; 1] {
-                [bitcoin::consensus::encode::serialize_hex(&self)]
-            }
+        html! {
+            <div class="offcanvas offcanvas-end" tabindex="-1" id="historySidebar" aria-labelledby="historySidebarLabel">
+                <div class="offcanvas-header">
+                    <h5 class="offcanvas-title" id="historySidebarLabel">{ "History" }</h5>
+                    <button type="button" class="btn-close text-reset" data-bs-dismiss="offcanvas" aria-label="Close"></button>
+                </div>
+                <div class="offcanvas-body">
+                    <div class="list-group list-group-flush">
+                        { entry(0, "Initial state") }
+                        { for self.history.entries().iter().enumerate().map(|(i, e)| entry(i + 1, &e.description)) }
+                    </div>
+                </div>
+            </div>
         }
-    };
-}
-impl_hex_serialize_field!(TxOut);
-impl_hex_serialize_field!(Transaction);
-impl_hex_serialize_field!(Vec<Vec<u8>>);
-impl_hex_serialize_field!(Vec<u8>);
-impl_hex_serialize_field!(Witness);
-
-impl Field<1> for bitcoin::PublicKey {
-    type DeserializeError = ParseError;
-
-    fn bip174_deserialize(s: [&str; 1]) -> Result<Self, Self::DeserializeError> {
-        use bitcoin::hashes::hex::FromHex;
-        let data = Vec::<u8>::from_hex(s[0])?;
-        Ok(bitcoin::PublicKey::from_slice(&data)?)
-    }
-
-    fn bip174_serialize(&self) -> [String; 1] {
-        [bitcoin::consensus::encode::serialize_hex(&self.to_bytes())]
     }
 }
 
-impl Field<1> for secp256k1::PublicKey {
-    type DeserializeError = ParseError;
-
-    fn bip174_deserialize(s: [&str; 1]) -> Result<Self, Self::DeserializeError> {
-        use bitcoin::hashes::hex::FromHex;
-        let data = Vec::<u8>::from_hex(s[0])?;
-        Ok(secp256k1::PublicKey::from_slice(&data)?)
-    }
-
-    fn bip174_serialize(&self) -> [String; 1] {
-        [format!("{:x}", self)]
+fn build_row(item: Html) -> Html {
+    html! {
+        <div class="card-body py-2 d-flex">
+            <Column xs=12>
+                { item }
+            </Column>
+        </div>
     }
 }
 
-impl Field<1> for ScriptBuf {
-    type DeserializeError = ParseError;
-
-    fn bip174_deserialize(s: [&str; 1]) -> Result<Self, Self::DeserializeError> {
-        Ok(ScriptBuf::from_hex(s[0])?)
-    }
+/// Summary of the output of the non_witness_utxo spent by `input`
+fn view_referenced_txout(tx: &Transaction, input: &TxIn, network: Network, unit: Unit) -> Html {
+    let vout = input.previous_output.vout;
+    let txout = match tx.output.get(vout as usize) {
+        Some(txout) => txout,
+        None => return html! {},
+    };
+    let destination = Address::from_script(&txout.script_pubkey, network)
+        .map(|a| a.to_string())
+        .unwrap_or_else(|_| core_script_asm(&txout.script_pubkey, false));
 
-    fn bip174_serialize(&self) -> [String; 1] {
-        [self.as_script().to_hex_string()]
+    html! {
+        <div class="card-body py-1 small text-muted d-flex flex-wrap">
+            <span class="me-2">{ format!("Spends output #{}:", vout) }</span>
+            <span class="me-2 fw-bold">{ unit.format(txout.value) }</span>
+            <span class="me-2">{ "to" }</span>
+            <span class="me-2 font-monospace text-break">{ destination }</span>
+            <span class="badge bg-light text-dark border">{ Template::classify(&txout.script_pubkey).name() }</span>
+        </div>
     }
 }
 
-impl Field<1> for Signature {
-    type DeserializeError = ParseError;
-
-    fn bip174_deserialize(s: [&str; 1]) -> Result<Self, Self::DeserializeError> {
-        use bitcoin::hashes::hex::FromHex;
-        let data = Vec::<u8>::from_hex(s[0])?;
-        Ok(Signature::from_slice(&data)?)
-    }
+/// Badge with the template of a script, followed by the parameters it commits to
+fn view_template(script: Option<&bitcoin::Script>) -> Html {
+    let template = match script {
+        Some(script) => Template::classify(script),
+        None => return html! {},
+    };
 
-    fn bip174_serialize(&self) -> [String; 1] {
-        [format!("{:x}", &self.serialize())]
+    html! {
+        <div class="card-body py-1 small text-muted d-flex flex-wrap align-items-center">
+            <span class="badge bg-light text-dark border me-2">{ template.name() }</span>
+            {
+                for template.parameters().into_iter().map(|(label, value)| html! {
+                    <span class="me-3 text-break">{ format!("{}: ", label) }<span class="font-monospace">{ value }</span></span>
+                })
+            }
+        </div>
     }
 }
 
-impl Field<1> for psbt::Psbt {
-    type DeserializeError = psbt::PsbtParseError;
-
-    fn bip174_deserialize(s: [&str; 1]) -> Result<Self, Self::DeserializeError> {
-        psbt::Psbt::from_str(s[0])
-    }
+/// Explanation of the standard derivation paths, one row per path
+fn view_derivations<'a>(
+    sources: impl Iterator<Item = &'a bip32::KeySource>,
+    network: Network,
+    style: HardenedStyle,
+) -> Html {
+    let view_path = |path: &bip32::DerivationPath| {
+        let info = derivation::explain_path(path, network)?;
+        Some(html! {
+            <div class="card-body py-1 small text-muted d-flex flex-wrap align-items-center">
+                <span class="badge bg-light text-dark border me-2">{ format!("{} {}", info.scheme.name(), info.scheme.script_type()) }</span>
+                <span class="me-3 font-monospace">{ derivation::format_path(path, style) }</span>
+                {
+                    for info.levels.iter().map(|level| html! {
+                        <span class=classes!("me-3", if level.unexpected { Some("text-warning") } else { None })>
+                            { format!("{}: ", level.kind.label()) }<span class="font-monospace">{ derivation::format_step(level.step, style) }</span>{ format!(" ({})", level.meaning) }
+                        </span>
+                    })
+                }
+                {
+                    info.coin_type_warning.map(|warning| html! {
+                        <span class="badge bg-warning text-dark"><i class="bi bi-exclamation-triangle"></i>{ format!(" {}", warning) }</span>
+                    }).unwrap_or_default()
+                }
+            </div>
+        })
+    };
 
-    fn bip174_serialize(&self) -> [String; 1] {
-        [self.to_string()]
+    html! {
+        { for sources.filter_map(|(_, path)| view_path(path)) }
     }
 }
 
-impl Field<2> for bitcoin::bip32::KeySource {
-    type DeserializeError = ParseError;
-
-    fn bip174_deserialize(s: [&str; 2]) -> Result<Self, Self::DeserializeError> {
-        use bitcoin::hashes::hex::FromHex;
-
-        let fingerprint = FromHex::from_hex(s[0])?;
-        let path = FromStr::from_str(s[1])?;
+fn findings_for(findings: &[Finding], location: Location) -> Vec<Finding> {
+    findings
+        .iter()
+        .filter(|f| f.location == location)
+        .cloned()
+        .collect()
+}
 
-        Ok((fingerprint, path))
+/// Panel listing everything the linter found in the PSBT
+fn view_findings(findings: &[Finding]) -> Html {
+    if findings.is_empty() {
+        return html! {};
     }
 
-    fn bip174_serialize(&self) -> [String; 2] {
-        [format!("{:x}", self.0), self.1.to_string()]
+    let worst = findings.iter().map(|f| f.severity).max().unwrap();
+    html! {
+        <div class=classes!("alert", format!("alert-{}", worst.class()), "mt-3")>
+            <h6 class="alert-heading"><i class="bi bi-exclamation-triangle"></i>{ format!(" {} possible problem(s)", findings.len()) }</h6>
+            <ul class="mb-0">
+                {
+                    for findings.iter().map(|f| html! {
+                        <li><strong>{ f.location.to_string() }</strong>{ ": " }{ &f.message }</li>
+                    })
+                }
+            </ul>
+        </div>
     }
 }
 
-macro_rules! declare_ty_wrapper {
-    ($name:ident, $wrap:ty $(, with_ord $tt:tt)?) => {
-        #[derive(Debug, Clone, PartialEq, Eq $(, PartialOrd, Ord $tt )? )]
-        pub struct $name($wrap);
-
-        impl std::ops::Deref for $name {
-            type Target = $wrap;
-
-            fn deref(&self) -> &Self::Target {
-                &self.0
-            }
-        }
-        impl From<$wrap> for $name {
-            fn from(t: $wrap) -> Self {
-                $name(t)
-            }
-        }
-        impl From<$name> for $wrap {
-            fn from(t: $name) -> Self {
-                t.0
-            }
-        }
-
-        impl Field<1> for $name {
-            type DeserializeError = ParseError;
-
-            fn bip174_serialize(&self) -> [String; 1] {
-                self.0.bip174_serialize()
-            }
-            fn bip174_deserialize(s: [&str; 1]) -> Result<Self, Self::DeserializeError> {
-                Ok(<$wrap>::bip174_deserialize(s)?.into())
-            }
+/// Badges shown in the input and output cards
+fn build_finding_badges(findings: &[Finding]) -> Html {
+    html! {
+        {
+            for findings.iter().map(|f| html! {
+                <div class="card-body py-1">
+                    <span class=classes!("badge", "text-wrap", "text-start", format!("bg-{}", f.severity.class()), if f.severity == lint::Severity::Warning { Some("text-dark") } else { None })>
+                        <i class="bi bi-exclamation-triangle"></i>{ " " }{ &f.message }
+                    </span>
+                </div>
+            })
         }
-    };
+    }
 }
 
-declare_ty_wrapper!(WitnessUtxo, Option<TxOut>);
-declare_ty_wrapper!(NonWitnessUtxo, Option<Transaction>);
-declare_ty_wrapper!(SecpPublicKeyWrapper, secp256k1::PublicKey, with_ord,);
-declare_ty_wrapper!(PublicKeyWrapper, bitcoin::PublicKey, with_ord,);
-declare_ty_wrapper!(BytesWrapper, Vec<u8>);
-declare_ty_wrapper!(RedeemScript, Option<ScriptBuf>);
-declare_ty_wrapper!(WitnessScript, Option<ScriptBuf>);
-declare_ty_wrapper!(FinalScript, Option<ScriptBuf>);
-declare_ty_wrapper!(FinalWitness, Option<Witness>);
-declare_ty_wrapper!(FinalSignature, Signature);
+fn build_diff_row(item: Html, change: Option<Change>) -> Html {
+    match change {
+        None => build_row(item),
+        Some(change) => html! {
+            <div class=classes!("card-body", "py-2", "d-flex", "position-relative", change.class())>
+                <span class="badge bg-secondary position-absolute top-0 end-0 me-3 mt-1">{ change.label() }</span>
+                <Column xs=12>
+                    { item }
+                </Column>
+            </div>
+        },
+    }
+}
 
-fn build_row(item: Html) -> Html {
-    html! {
-        <div class="card-body py-2 d-flex">
-            <Column xs=12>
-                { item }
-            </Column>
-        </div>
+/// Badge in the card header listing the changed fields that don't have their own row
+fn build_hidden_changes(fields: &BTreeMap<&'static str, Change>, shown: &[&str]) -> Html {
+    let hidden = fields
+        .iter()
+        .filter(|(name, _)| !shown.contains(name))
+        .map(|(name, change)| format!("{} {}", name, change.label()))
+        .collect::<Vec<_>>();
+
+    match hidden.is_empty() {
+        true => html! {},
+        false => html! {
+            <span class="badge bg-warning text-dark ms-auto" title=hidden.join(", ")>
+                { format!("{} more changed", hidden.len()) }
+            </span>
+        },
     }
 }
 
@@ -453,22 +966,19 @@ pub struct PsbtInputProps {
     input: TxIn,
 
     network: Network,
+    #[prop_or_default]
+    unit: Unit,
+    #[prop_or_default]
+    hardened_style: HardenedStyle,
+    #[prop_or_default]
+    findings: Vec<Finding>,
+    #[prop_or_default]
+    diff: InputDiff,
+    #[prop_or_default]
+    read_only: bool,
     parent: ComponentLink<Psbt>,
 }
 
-#[derive(Debug, Clone)]
-#[allow(clippy::enum_variant_names)]
-pub enum PsbtInputMsg {
-    ChangeSigHash(Option<PsbtSighashType>),
-    ChangeWitnessUtxo(WitnessUtxo),
-    ChangeNonWitnessUtxo(NonWitnessUtxo),
-    ChangeRedeemScript(RedeemScript),
-    ChangeWitnessScript(WitnessScript),
-    ChangeFinalScript(FinalScript),
-    ChangeFinalWitness(FinalWitness),
-    ChangePartialSigs(MapUpdate<PublicKeyWrapper, FinalSignature>),
-    ChangeBIP32Derivation(MapUpdate<SecpPublicKeyWrapper, bip32::KeySource>),
-}
 macro_rules! impl_parent_message {
     ($enum:ident, $variant:ident, $type:ty) => {
         impl ParentMessage<$type> for $enum {
@@ -488,73 +998,6 @@ impl_parent_message!(PsbtInputMsg, ChangeFinalWitness, FinalWitness);
 impl_parent_message!(PsbtInputMsg, ChangePartialSigs, MapUpdate<PublicKeyWrapper, FinalSignature>);
 impl_parent_message!(PsbtInputMsg, ChangeBIP32Derivation, MapUpdate<SecpPublicKeyWrapper, bip32::KeySource>);
 
-macro_rules! set_and_return {
-    ($field:expr, $val:expr) => {{
-        let old = $field.clone();
-        $field = $val;
-        old.into()
-    }};
-}
-
-impl PsbtInputMsg {
-    pub fn apply_to(self, psbt_input: &mut psbt::Input) -> PsbtInputMsg {
-        match self {
-            PsbtInputMsg::ChangeSigHash(sighash) => {
-                PsbtInputMsg::ChangeSigHash(set_and_return!(psbt_input.sighash_type, sighash))
-            }
-            PsbtInputMsg::ChangeWitnessUtxo(witness_utxo) => PsbtInputMsg::ChangeWitnessUtxo(
-                set_and_return!(psbt_input.witness_utxo, witness_utxo.0),
-            ),
-            PsbtInputMsg::ChangeNonWitnessUtxo(non_witness_utxo) => {
-                PsbtInputMsg::ChangeNonWitnessUtxo(set_and_return!(
-                    psbt_input.non_witness_utxo,
-                    non_witness_utxo.0
-                ))
-            }
-            PsbtInputMsg::ChangeRedeemScript(redeem_script) => PsbtInputMsg::ChangeRedeemScript(
-                set_and_return!(psbt_input.redeem_script, redeem_script.0),
-            ),
-            PsbtInputMsg::ChangeWitnessScript(witness_script) => PsbtInputMsg::ChangeWitnessScript(
-                set_and_return!(psbt_input.witness_script, witness_script.0),
-            ),
-            PsbtInputMsg::ChangeFinalScript(final_script_sig) => PsbtInputMsg::ChangeFinalScript(
-                set_and_return!(psbt_input.final_script_sig, final_script_sig.0),
-            ),
-            PsbtInputMsg::ChangeFinalWitness(final_script_witness) => {
-                PsbtInputMsg::ChangeFinalWitness(set_and_return!(
-                    psbt_input.final_script_witness,
-                    final_script_witness.0
-                ))
-            }
-            PsbtInputMsg::ChangePartialSigs(c) => {
-                let prev = match c {
-                    MapUpdate::Set(k, v) => (
-                        k.clone(),
-                        psbt_input.partial_sigs.insert(k.into(), v.into()),
-                    ),
-                    MapUpdate::Remove(k) => (k.clone(), psbt_input.partial_sigs.remove(&k)),
-                };
-                match prev {
-                    (k, Some(v)) => PsbtInputMsg::ChangePartialSigs(MapUpdate::Set(k, v.into())),
-                    (k, None) => PsbtInputMsg::ChangePartialSigs(MapUpdate::Remove(k)),
-                }
-            }
-            PsbtInputMsg::ChangeBIP32Derivation(c) => {
-                let prev = match c {
-                    MapUpdate::Set(k, v) => {
-                        (k.clone(), psbt_input.bip32_derivation.insert(k.into(), v))
-                    }
-                    MapUpdate::Remove(k) => (k.clone(), psbt_input.bip32_derivation.remove(&k)),
-                };
-                match prev {
-                    (k, Some(v)) => PsbtInputMsg::ChangeBIP32Derivation(MapUpdate::Set(k, v)),
-                    (k, None) => PsbtInputMsg::ChangeBIP32Derivation(MapUpdate::Remove(k)),
-                }
-            }
-        }
-    }
-}
-
 #[derive(Clone)]
 pub struct PsbtInput {
     link: ComponentLink<Self>,
@@ -608,29 +1051,73 @@ impl Component for PsbtInput {
             .iter()
             .map(|(k, v)| ((*k).into(), v.clone()))
             .collect::<BTreeMap<_, _>>();
+        let partial_sigs_diff = self
+            .props
+            .diff
+            .partial_sigs
+            .iter()
+            .map(|(k, c)| ((*k).into(), *c))
+            .collect::<BTreeMap<_, _>>();
+        let bip32_derivation_diff = self
+            .props
+            .diff
+            .bip32_derivation
+            .iter()
+            .map(|(k, c)| ((*k).into(), *c))
+            .collect::<BTreeMap<_, _>>();
+        let spent = lint::spent_output(&self.props.psbt_input, &self.props.input);
 
         html! {
-            <div class="card mb-3 pb-2 position-relative">
+            <div class="card mb-3 pb-2 position-relative psbt-card psbt-input" tabindex="-1">
                 <div class="card-header mb-2 d-flex flex-wrap">
                     <span class="col-1 fw-light">{ format!("#{}", self.props.index) }</span>
-                    <span class="col-11">{ self.props.input.previous_output.to_string() }</span>
-                    // <span class="offset-1 col-11 offset-md-0 col-md-3 text-end">{ "??? BTC" }</span>
+                    <span class="col-11 col-md-8 text-break">
+                        { self.props.input.previous_output.to_string() }
+                        { spend_type(&self.props.psbt_input, &self.props.input).map(|t| html! { <span class="badge bg-light text-dark border ms-2">{ t }</span> }).unwrap_or_default() }
+                    </span>
+                    { build_hidden_changes(&self.props.diff.fields, &["witness_utxo", "non_witness_utxo", "partial_sigs", "bip32_derivation", "sighash_type", "final_script_sig", "final_script_witness", "redeem_script", "witness_script"]) }
+                    <span class="offset-1 col-11 offset-md-0 col-md-3 text-md-end fw-bold">{ spent.map(|txout| self.props.unit.format(txout.value)).unwrap_or_default() }</span>
+                    { spent.and_then(|txout| Address::from_script(&txout.script_pubkey, self.props.network).ok()).map(|address| html! { <span class="offset-1 col-11 small text-muted font-monospace text-break">{ address.to_string() }</span> }).unwrap_or_default() }
                 </div>
-
-                { build_row(html! { <SingleFieldWitnessUtxo label="Witness UTXO" value=WitnessUtxo(self.props.psbt_input.witness_utxo.clone()) parent=self.link.clone() /> }) }
-                { build_row(html! { <SingleFieldNonWitnessUtxo label="Non Witness UTXO" value=NonWitnessUtxo(self.props.psbt_input.non_witness_utxo.clone()) parent=self.link.clone() /> }) }
-                { build_row(html! { <MapFieldPartialSigs label="Partial Signatures" key_label="Public Key" value_label="Signature" map=partial_sigs parent=self.link.clone() /> }) }
-                { build_row(html! { <MapFieldBIP32Derivation label="BIP32 Derivation" key_label="Public Key" value_label=["Fingerprint", "Path"] map=bip32_derivation parent=self.link.clone() /> }) }
-                { build_row(html! { <SelectFieldSigHash label="Sighash Type".to_string() allow_empty=true selected=self.props.psbt_input.sighash_type values=vec![EcdsaSighashType::All.into(), EcdsaSighashType::None.into(), EcdsaSighashType::Single.into(), EcdsaSighashType::AllPlusAnyoneCanPay.into(), EcdsaSighashType::NonePlusAnyoneCanPay.into(), EcdsaSighashType::SinglePlusAnyoneCanPay.into()] parent=self.link.clone() /> }) }
-                { build_row(html! { <SingleFieldFinalScript label="Final Script Sig" value=FinalScript(self.props.psbt_input.final_script_sig.clone()) parent=self.link.clone() /> }) }
-                { build_row(html! { <SingleFieldFinalWitness label="Final Script Witness" value=FinalWitness(self.props.psbt_input.final_script_witness.clone()) parent=self.link.clone() /> }) }
-                { build_row(html! { <SingleFieldRedeemScript label="Redeem Script" value=RedeemScript(self.props.psbt_input.redeem_script.clone()) parent=self.link.clone() /> }) }
-                { build_row(html! { <SingleFieldWitnessScript label="Witness Script" value=WitnessScript(self.props.psbt_input.witness_script.clone()) parent=self.link.clone() /> }) }
+                { build_finding_badges(&self.props.findings) }
+
+                { build_diff_row(html! { <SingleFieldWitnessUtxo label="Witness UTXO" value=WitnessUtxo(self.props.psbt_input.witness_utxo.clone()) read_only=self.props.read_only parent=self.link.clone() /> }, self.props.diff.field("witness_utxo")) }
+                { self.props.psbt_input.witness_utxo.as_ref().map(|txout| self.view_witness_utxo_amount(txout)).unwrap_or_default() }
+                { view_template(self.props.psbt_input.witness_utxo.as_ref().map(|txout| txout.script_pubkey.as_script())) }
+                { build_diff_row(html! { <SingleFieldNonWitnessUtxo label="Non Witness UTXO" value=NonWitnessUtxo(self.props.psbt_input.non_witness_utxo.clone()) read_only=self.props.read_only parent=self.link.clone() /> }, self.props.diff.field("non_witness_utxo")) }
+                { self.props.psbt_input.non_witness_utxo.as_ref().map(|tx| view_referenced_txout(tx, &self.props.input, self.props.network, self.props.unit)).unwrap_or_default() }
+                { build_diff_row(html! { <MapFieldPartialSigs label="Partial Signatures" key_label="Public Key" value_label="Signature" map=partial_sigs highlight=partial_sigs_diff read_only=self.props.read_only parent=self.link.clone() /> }, self.props.diff.field("partial_sigs")) }
+                { build_diff_row(html! { <MapFieldBIP32Derivation label="BIP32 Derivation" key_label="Public Key" value_label=["Fingerprint", "Path"] map=bip32_derivation highlight=bip32_derivation_diff read_only=self.props.read_only hardened_style=self.props.hardened_style parent=self.link.clone() /> }, self.props.diff.field("bip32_derivation")) }
+                { view_derivations(self.props.psbt_input.bip32_derivation.values(), self.props.network, self.props.hardened_style) }
+                { build_diff_row(html! { <SelectFieldSigHash label="Sighash Type".to_string() allow_empty=true selected=self.props.psbt_input.sighash_type values=vec![EcdsaSighashType::All.into(), EcdsaSighashType::None.into(), EcdsaSighashType::Single.into(), EcdsaSighashType::AllPlusAnyoneCanPay.into(), EcdsaSighashType::NonePlusAnyoneCanPay.into(), EcdsaSighashType::SinglePlusAnyoneCanPay.into()] read_only=self.props.read_only parent=self.link.clone() /> }, self.props.diff.field("sighash_type")) }
+                { build_diff_row(html! { <SingleFieldFinalScript label="Final Script Sig" value=FinalScript(self.props.psbt_input.final_script_sig.clone()) read_only=self.props.read_only parent=self.link.clone() /> }, self.props.diff.field("final_script_sig")) }
+                { build_diff_row(html! { <SingleFieldFinalWitness label="Final Script Witness" value=FinalWitness(self.props.psbt_input.final_script_witness.clone()) read_only=self.props.read_only parent=self.link.clone() /> }, self.props.diff.field("final_script_witness")) }
+                { build_diff_row(html! { <SingleFieldRedeemScript label="Redeem Script" value=RedeemScript(self.props.psbt_input.redeem_script.clone()) read_only=self.props.read_only parent=self.link.clone() /> }, self.props.diff.field("redeem_script")) }
+                { view_template(self.props.psbt_input.redeem_script.as_deref()) }
+                { build_diff_row(html! { <SingleFieldWitnessScript label="Witness Script" value=WitnessScript(self.props.psbt_input.witness_script.clone()) read_only=self.props.read_only parent=self.link.clone() /> }, self.props.diff.field("witness_script")) }
+                { view_template(self.props.psbt_input.witness_script.as_deref()) }
             </div>
         }
     }
 }
 
+impl PsbtInput {
+    /// Edit the amount of the witness UTXO without touching its script
+    fn view_witness_utxo_amount(&self, txout: &TxOut) -> Html {
+        let script_pubkey = txout.script_pubkey.clone();
+        let onchange = self.link.callback(move |value| {
+            PsbtInputMsg::ChangeWitnessUtxo(WitnessUtxo(Some(TxOut {
+                value,
+                script_pubkey: script_pubkey.clone(),
+            })))
+        });
+
+        build_row(html! {
+            <AmountField label="Witness UTXO amount" value=txout.value unit=self.props.unit read_only=self.props.read_only onchange=onchange />
+        })
+    }
+}
+
 #[derive(Clone, Properties)]
 pub struct PsbtOutputProps {
     index: usize,
@@ -638,48 +1125,23 @@ pub struct PsbtOutputProps {
     output: TxOut,
 
     network: Network,
+    #[prop_or_default]
+    unit: Unit,
+    #[prop_or_default]
+    hardened_style: HardenedStyle,
+    #[prop_or_default]
+    findings: Vec<Finding>,
+    #[prop_or_default]
+    diff: OutputDiff,
+    #[prop_or_default]
+    read_only: bool,
     parent: ComponentLink<Psbt>,
 }
 
-#[derive(Debug, Clone)]
-#[allow(clippy::enum_variant_names)]
-pub enum PsbtOutputMsg {
-    ChangeRedeemScript(RedeemScript),
-    ChangeWitnessScript(WitnessScript),
-    ChangeBIP32Derivation(MapUpdate<SecpPublicKeyWrapper, bip32::KeySource>),
-}
 impl_parent_message!(PsbtOutputMsg, ChangeRedeemScript, RedeemScript);
 impl_parent_message!(PsbtOutputMsg, ChangeWitnessScript, WitnessScript);
 impl_parent_message!(PsbtOutputMsg, ChangeBIP32Derivation, MapUpdate<SecpPublicKeyWrapper, bip32::KeySource>);
 
-impl PsbtOutputMsg {
-    fn apply_to(self, psbt_output: &mut psbt::Output) -> PsbtOutputMsg {
-        match self {
-            PsbtOutputMsg::ChangeRedeemScript(redeem_script) => PsbtOutputMsg::ChangeRedeemScript(
-                set_and_return!(psbt_output.redeem_script, redeem_script.0),
-            ),
-            PsbtOutputMsg::ChangeWitnessScript(witness_script) => {
-                PsbtOutputMsg::ChangeWitnessScript(set_and_return!(
-                    psbt_output.witness_script,
-                    witness_script.0
-                ))
-            }
-            PsbtOutputMsg::ChangeBIP32Derivation(c) => {
-                let prev = match c {
-                    MapUpdate::Set(k, v) => {
-                        (k.clone(), psbt_output.bip32_derivation.insert(k.into(), v))
-                    }
-                    MapUpdate::Remove(k) => (k.clone(), psbt_output.bip32_derivation.remove(&k)),
-                };
-                match prev {
-                    (k, Some(v)) => PsbtOutputMsg::ChangeBIP32Derivation(MapUpdate::Set(k, v)),
-                    (k, None) => PsbtOutputMsg::ChangeBIP32Derivation(MapUpdate::Remove(k)),
-                }
-            }
-        }
-    }
-}
-
 #[derive(Clone)]
 pub struct PsbtOutput {
     link: ComponentLink<Self>,
@@ -719,69 +1181,31 @@ impl Component for PsbtOutput {
             .iter()
             .map(|(k, v)| ((*k).into(), v.clone()))
             .collect::<BTreeMap<_, _>>();
+        let bip32_derivation_diff = self
+            .props
+            .diff
+            .bip32_derivation
+            .iter()
+            .map(|(k, c)| ((*k).into(), *c))
+            .collect::<BTreeMap<_, _>>();
 
         html! {
-            <div class="card mb-3 pb-2 position-relative">
+            <div class="card mb-3 pb-2 position-relative psbt-card psbt-output" tabindex="-1">
                 <div class="card-header mb-2 d-flex flex-wrap">
                     <span class="col-1 fw-light">{ format!("#{}", self.props.index) }</span>
-                    <span class="col-11">{ Address::from_script(&self.props.output.script_pubkey, self.props.network).map(|a| a.to_string()).unwrap_or_else(|_| self.props.output.script_pubkey.to_string()) }</span>
-                    // <span class="offset-1 col-11 offset-md-0 col-md-3 text-end">{ "??? BTC" }</span>
+                    <span class="col-11 col-md-8 text-break">{ Address::from_script(&self.props.output.script_pubkey, self.props.network).map(|a| a.to_string()).unwrap_or_else(|_| self.props.output.script_pubkey.to_string()) }</span>
+                    { build_hidden_changes(&self.props.diff.fields, &["bip32_derivation", "redeem_script", "witness_script"]) }
+                    <span class="offset-1 col-11 offset-md-0 col-md-3 text-md-end fw-bold">{ self.props.unit.format(self.props.output.value) }</span>
                 </div>
-
-                { build_row(html! { <MapFieldBIP32Derivation label="BIP32 Derivation" key_label="Public Key" value_label=["Fingerprint", "Path"] map=bip32_derivation parent=self.link.clone() /> }) }
-                { build_row(html! { <SingleFieldRedeemScript label="Redeem Script" value=RedeemScript(self.props.psbt_output.redeem_script.clone()) parent=self.link.clone() /> }) }
-                { build_row(html! { <SingleFieldWitnessScript label="Witness Script" value=WitnessScript(self.props.psbt_output.witness_script.clone()) parent=self.link.clone() /> }) }
+                { build_finding_badges(&self.props.findings) }
+
+                { build_diff_row(html! { <MapFieldBIP32Derivation label="BIP32 Derivation" key_label="Public Key" value_label=["Fingerprint", "Path"] map=bip32_derivation highlight=bip32_derivation_diff read_only=self.props.read_only hardened_style=self.props.hardened_style parent=self.link.clone() /> }, self.props.diff.field("bip32_derivation")) }
+                { view_derivations(self.props.psbt_output.bip32_derivation.values(), self.props.network, self.props.hardened_style) }
+                { build_diff_row(html! { <SingleFieldRedeemScript label="Redeem Script" value=RedeemScript(self.props.psbt_output.redeem_script.clone()) read_only=self.props.read_only parent=self.link.clone() /> }, self.props.diff.field("redeem_script")) }
+                { view_template(self.props.psbt_output.redeem_script.as_deref()) }
+                { build_diff_row(html! { <SingleFieldWitnessScript label="Witness Script" value=WitnessScript(self.props.psbt_output.witness_script.clone()) read_only=self.props.read_only parent=self.link.clone() /> }, self.props.diff.field("witness_script")) }
+                { view_template(self.props.psbt_output.witness_script.as_deref()) }
             </div>
         }
     }
 }
-
-#[derive(Debug)]
-pub enum ParseError {
-    Hex(HexError),
-    Encode(bitcoin::consensus::encode::Error),
-    Key(bitcoin::key::Error),
-    Secp(secp256k1::Error),
-    BIP32(bitcoin::bip32::Error),
-    Ecdsa(bitcoin::ecdsa::Error),
-}
-#[derive(Debug)]
-pub enum HexError {
-    Bytes(bitcoin::hex::HexToBytesError),
-    Array(bitcoin::hex::HexToArrayError),
-}
-impl From<bitcoin::hashes::hex::HexToArrayError> for ParseError {
-    fn from(e: bitcoin::hashes::hex::HexToArrayError) -> Self {
-        ParseError::Hex(HexError::Array(e))
-    }
-}
-impl From<bitcoin::hashes::hex::HexToBytesError> for ParseError {
-    fn from(e: bitcoin::hashes::hex::HexToBytesError) -> Self {
-        ParseError::Hex(HexError::Bytes(e))
-    }
-}
-impl From<bitcoin::consensus::encode::Error> for ParseError {
-    fn from(e: bitcoin::consensus::encode::Error) -> Self {
-        ParseError::Encode(e)
-    }
-}
-impl From<bitcoin::key::Error> for ParseError {
-    fn from(e: bitcoin::key::Error) -> Self {
-        ParseError::Key(e)
-    }
-}
-impl From<secp256k1::Error> for ParseError {
-    fn from(e: secp256k1::Error) -> Self {
-        ParseError::Secp(e)
-    }
-}
-impl From<bitcoin::bip32::Error> for ParseError {
-    fn from(e: bitcoin::bip32::Error) -> Self {
-        ParseError::BIP32(e)
-    }
-}
-impl From<bitcoin::ecdsa::Error> for ParseError {
-    fn from(e: bitcoin::ecdsa::Error) -> Self {
-        ParseError::Ecdsa(e)
-    }
-}
@@ -0,0 +1,218 @@
+use bitcoin::hashes::hex::FromHex;
+
+const BASE32_ALPHABET: &[u8; 32] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+
+/// Most parts that fit in the two base36 digits of the header
+const MAX_PARTS: usize = 1295;
+const HEADER_LEN: usize = 8;
+
+/// RFC 4648 base32 without padding, the encoding `2` of BBQr
+pub fn base32_encode(data: &[u8]) -> String {
+    let mut out = String::with_capacity(data.len().div_ceil(5) * 8);
+    for chunk in data.chunks(5) {
+        let mut buffer = [0; 5];
+        buffer[..chunk.len()].copy_from_slice(chunk);
+        let bits = buffer.iter().fold(0u64, |acc, b| acc << 8 | *b as u64);
+
+        let chars = (chunk.len() * 8).div_ceil(5);
+        for i in 0..chars {
+            let index = (bits >> (35 - i * 5)) & 0x1f;
+            out.push(BASE32_ALPHABET[index as usize] as char);
+        }
+    }
+    out
+}
+
+/// Decode RFC 4648 base32 without padding
+pub fn base32_decode(data: &str) -> Result<Vec<u8>, String> {
+    let mut out = Vec::with_capacity(data.len() * 5 / 8);
+    let (mut bits, mut count) = (0u32, 0);
+    for c in data.bytes() {
+        let value = BASE32_ALPHABET
+            .iter()
+            .position(|a| *a == c)
+            .ok_or_else(|| format!("invalid base32 character '{}'", c as char))?;
+        bits = (bits << 5 | value as u32) & 0xffff;
+        count += 5;
+        if count >= 8 {
+            count -= 8;
+            out.push((bits >> count) as u8);
+        }
+    }
+    Ok(out)
+}
+
+fn base36(value: usize) -> String {
+    const DIGITS: &[u8; 36] = b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZ";
+    format!(
+        "{}{}",
+        DIGITS[value / 36] as char,
+        DIGITS[value % 36] as char
+    )
+}
+
+fn parse_base36(s: &str) -> Result<usize, String> {
+    usize::from_str_radix(s, 36).map_err(|_| format!("invalid base36 number \"{}\"", s))
+}
+
+/// Split `data` in BBQr parts of at most `max_chars` characters each. `file_type` is `P` for a
+/// PSBT and `T` for a transaction. The data is base32 encoded without compression, which every
+/// BBQr reader supports
+pub fn encode(data: &[u8], file_type: char, max_chars: usize) -> Vec<String> {
+    let encoded = base32_encode(data);
+
+    // Every part except the last one must contain a whole number of base32 groups
+    let max_part_len = ((max_chars.saturating_sub(HEADER_LEN)) / 8 * 8).max(8);
+    let count = encoded.len().div_ceil(max_part_len).clamp(1, MAX_PARTS);
+    // Empty data still makes a single, empty part
+    let part_len = (encoded.len().div_ceil(count).div_ceil(8) * 8).max(8);
+    let count = encoded.len().div_ceil(part_len).max(1);
+
+    (0..count)
+        .map(|i| {
+            let chunk = &encoded
+                [(i * part_len).min(encoded.len())..((i + 1) * part_len).min(encoded.len())];
+            format!("B$2{}{}{}{}", file_type, base36(count), base36(i), chunk)
+        })
+        .collect()
+}
+
+/// Collects the parts of a BBQr, in any order, until the file can be decoded
+#[derive(Clone, Debug, Default)]
+pub struct BbqrDecoder {
+    /// Encoding and file type of the parts received so far
+    kind: Option<(char, char)>,
+    parts: Vec<Option<String>>,
+    message: Option<Vec<u8>>,
+}
+
+impl BbqrDecoder {
+    /// Add a part, starting with the `B$` header
+    pub fn receive(&mut self, part: &str) -> Result<(), String> {
+        let part = part.trim();
+        if !part.is_ascii() || part.len() < HEADER_LEN || !part.starts_with("B$") {
+            return Err("missing the \"B$\" header".to_string());
+        }
+
+        let mut header = part[2..4].chars();
+        let kind = (header.next().unwrap(), header.next().unwrap());
+        if !matches!(kind.0, '2' | 'H' | 'Z') {
+            return Err(format!("unsupported encoding '{}'", kind.0));
+        }
+        let count = parse_base36(&part[4..6])?;
+        let index = parse_base36(&part[6..8])?;
+        if index >= count {
+            return Err(format!("part {} of {} is out of range", index + 1, count));
+        }
+
+        match self.kind {
+            Some(expected) if expected != kind || self.parts.len() != count => {
+                return Err("the part belongs to a different file".to_string())
+            }
+            Some(_) => {}
+            None => {
+                self.kind = Some(kind);
+                self.parts = vec![None; count];
+            }
+        }
+        self.parts[index] = Some(part[HEADER_LEN..].to_string());
+
+        if self.parts.iter().all(Option::is_some) && self.message.is_none() {
+            let data = self.parts.iter().flatten().cloned().collect::<String>();
+            self.message = Some(match kind.0 {
+                'H' => Vec::<u8>::from_hex(&data).map_err(|e| e.to_string())?,
+                '2' => base32_decode(&data)?,
+                _ => miniz_oxide::inflate::decompress_to_vec(&base32_decode(&data)?)
+                    .map_err(|e| format!("invalid compressed data: {}", e))?,
+            });
+        }
+        Ok(())
+    }
+
+    /// File type of the parts, `P` for a PSBT
+    pub fn file_type(&self) -> Option<char> {
+        self.kind.map(|(_, file_type)| file_type)
+    }
+
+    pub fn count(&self) -> Option<usize> {
+        self.kind.map(|_| self.parts.len())
+    }
+
+    /// Parts that haven't been received yet, starting from 1
+    pub fn missing(&self) -> Vec<usize> {
+        self.parts
+            .iter()
+            .enumerate()
+            .filter(|(_, p)| p.is_none())
+            .map(|(i, _)| i + 1)
+            .collect()
+    }
+
+    pub fn message(&self) -> Option<&[u8]> {
+        self.message.as_deref()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_base32() {
+        assert_eq!(base32_encode(b""), "");
+        assert_eq!(base32_encode(b"f"), "MY");
+        assert_eq!(base32_encode(b"foob"), "MZXW6YQ");
+        assert_eq!(base32_encode(b"foobar"), "MZXW6YTBOI");
+        assert_eq!(base32_decode("MZXW6YQ").unwrap(), b"foob");
+        assert_eq!(base32_decode("MZXW6YTBOI").unwrap(), b"foobar");
+        assert!(base32_decode("mzxw").is_err());
+    }
+
+    #[test]
+    fn test_encode() {
+        assert_eq!(encode(b"foobar", 'P', 100), vec!["B$2P0100MZXW6YTBOI"]);
+        assert_eq!(encode(b"", 'P', 100), vec!["B$2P0100"]);
+
+        let data = (0..=255).collect::<Vec<u8>>();
+        let parts = encode(&data, 'P', 100);
+        assert_eq!(parts.len(), 5);
+        assert!(parts.iter().all(|p| p.len() <= 100));
+        assert!(parts[0].starts_with("B$2P0500"));
+        assert!(parts[4].starts_with("B$2P0504"));
+        assert_eq!(
+            parts.iter().map(|p| &p[HEADER_LEN..]).collect::<String>(),
+            base32_encode(&data)
+        );
+        assert_eq!(base36(1295), "ZZ");
+    }
+
+    #[test]
+    fn test_decoder() {
+        let data = (0..=255).collect::<Vec<u8>>();
+        let parts = encode(&data, 'P', 100);
+
+        let mut decoder = BbqrDecoder::default();
+        for part in parts.iter().rev().skip(1) {
+            decoder.receive(part).unwrap();
+        }
+        assert_eq!(decoder.missing(), vec![5]);
+        assert_eq!(decoder.message(), None);
+        decoder.receive(&parts[4]).unwrap();
+        assert_eq!(decoder.message(), Some(&data[..]));
+        assert_eq!(decoder.file_type(), Some('P'));
+        assert!(decoder.receive("B$2P0100MZXW6YTBOI").is_err());
+
+        let mut decoder = BbqrDecoder::default();
+        decoder.receive("B$HP0200deadbe").unwrap();
+        decoder.receive("B$HP0201ef").unwrap();
+        assert_eq!(decoder.message(), Some(&[0xde, 0xad, 0xbe, 0xef][..]));
+
+        // "foobar" compressed with zlib.compressobj(wbits=-10)
+        let mut decoder = BbqrDecoder::default();
+        decoder.receive("B$ZP0100JPF46T2KFQBAA").unwrap();
+        assert_eq!(decoder.message(), Some(&b"foobar"[..]));
+
+        assert!(BbqrDecoder::default().receive("B$2P0102").is_err());
+        assert!(BbqrDecoder::default().receive("ur:crypto-psbt").is_err());
+    }
+}
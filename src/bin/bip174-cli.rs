@@ -0,0 +1,171 @@
+use std::io::{self, Read, Write};
+use std::process;
+use std::str::FromStr;
+
+use bitcoin::consensus::encode::serialize_hex;
+use bitcoin::{psbt, Network};
+
+use bip174::finalize::{extract, finalize};
+use bip174::json::psbt_to_json;
+use bip174::model::{parse_network, parse_psbt_bytes, PsbtMessage};
+
+const USAGE: &str = "\
+Usage: bip174-cli [--network <network>] [--output base64|hex|binary] <command> [<args>...]
+
+The PSBT is read from stdin as base64, hex or binary and the result is written to stdout.
+
+Commands:
+    decode                                        Print the PSBT as Bitcoin Core's decodepsbt does
+    set <input|output> <index> <field> <value>... Set a field, maps take the key followed by the value
+    remove <input|output> <index> <field> [<key>] Remove a field, or one key of a map
+    combine <file>...                             Combine with the PSBTs stored in the files
+    finalize                                      Finalize the inputs that use standard scripts
+    extract                                       Print the hex of the finalized transaction
+
+Input fields: sighash_type, witness_utxo, non_witness_utxo, redeem_script, witness_script,
+final_script_sig, final_script_witness, partial_sigs, bip32_derivation
+
+Output fields: redeem_script, witness_script, bip32_derivation
+
+Values use the same formats as the fields of the editor.
+
+When some inputs can't be finalized the others are finalized anyway: the PSBT is still written to
+stdout, while the errors are reported on stderr with a non-zero exit status.";
+
+#[derive(Clone, Copy)]
+enum Format {
+    Base64,
+    Hex,
+    Binary,
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "base64" => Ok(Format::Base64),
+            "hex" => Ok(Format::Hex),
+            "binary" => Ok(Format::Binary),
+            _ => Err(format!("unknown output format \"{}\"", s)),
+        }
+    }
+}
+
+enum Output {
+    Psbt(psbt::Psbt, Format),
+    /// A PSBT that could only be partially processed, with the reasons
+    Partial(psbt::Psbt, Format, Vec<String>),
+    Text(String),
+}
+
+fn read_psbt(mut reader: impl Read, name: &str) -> Result<psbt::Psbt, String> {
+    let mut data = vec![];
+    reader
+        .read_to_end(&mut data)
+        .map_err(|e| format!("{}: {}", name, e))?;
+    parse_psbt_bytes(&data).map_err(|e| format!("{}: {}", name, e))
+}
+
+fn edit(psbt: psbt::Psbt, set: bool, args: &[String]) -> Result<psbt::Psbt, String> {
+    let (kind, index, field, values) = match args {
+        [kind, index, field, values @ ..] => (kind.as_str(), index, field, values),
+        _ => return Err("expected <input|output> <index> <field>".to_string()),
+    };
+    let index = index
+        .parse::<usize>()
+        .map_err(|_| format!("invalid index \"{}\"", index))?;
+    let values = values.iter().map(String::as_str).collect::<Vec<_>>();
+    let msg = PsbtMessage::from_strings(&psbt, kind, index, field, &values, !set)?;
+
+    let mut psbt = Some(psbt);
+    msg.apply_to(&mut psbt);
+    Ok(psbt.unwrap())
+}
+
+fn run(args: &[String]) -> Result<Output, String> {
+    let mut network = Network::Bitcoin;
+    let mut format = Format::Base64;
+    let mut args = args;
+    loop {
+        match args {
+            [flag, value, rest @ ..] if flag == "--network" => {
+                network = parse_network(value)?;
+                args = rest;
+            }
+            [flag, value, rest @ ..] if flag == "--output" => {
+                format = value.parse()?;
+                args = rest;
+            }
+            _ => break,
+        }
+    }
+
+    let (command, args) = match args {
+        [command, args @ ..] => (command.as_str(), args),
+        [] => return Err(USAGE.to_string()),
+    };
+    if command == "help" || command == "--help" || command == "-h" {
+        return Ok(Output::Text(USAGE.to_string()));
+    }
+
+    let psbt = read_psbt(io::stdin(), "stdin")?;
+    match command {
+        "decode" => Ok(Output::Text(
+            serde_json::to_string_pretty(&psbt_to_json(&psbt, network)).unwrap(),
+        )),
+        "set" => edit(psbt, true, args).map(|psbt| Output::Psbt(psbt, format)),
+        "remove" => edit(psbt, false, args).map(|psbt| Output::Psbt(psbt, format)),
+        "combine" => {
+            let mut psbt = psbt;
+            for path in args {
+                let file = std::fs::File::open(path).map_err(|e| format!("{}: {}", path, e))?;
+                psbt.combine(read_psbt(file, path)?)
+                    .map_err(|e| format!("{}: {}", path, e))?;
+            }
+            Ok(Output::Psbt(psbt, format))
+        }
+        "finalize" => {
+            let mut psbt = psbt;
+            match finalize(&mut psbt) {
+                Ok(()) => Ok(Output::Psbt(psbt, format)),
+                Err(errors) => Ok(Output::Partial(psbt, format, errors)),
+            }
+        }
+        "extract" => extract(psbt).map(|tx| Output::Text(serialize_hex(&tx))),
+        _ => Err(format!("unknown command \"{}\"\n\n{}", command, USAGE)),
+    }
+}
+
+fn write_psbt(stdout: &mut impl Write, psbt: &psbt::Psbt, format: Format) -> io::Result<()> {
+    match format {
+        Format::Base64 => writeln!(stdout, "{}", psbt),
+        Format::Hex => writeln!(stdout, "{}", psbt.serialize_hex()),
+        Format::Binary => stdout.write_all(&psbt.serialize()),
+    }
+}
+
+fn main() {
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+
+    let mut stdout = io::stdout();
+    let (result, errors) = match run(&args) {
+        Ok(Output::Text(text)) => (writeln!(stdout, "{}", text), vec![]),
+        Ok(Output::Psbt(psbt, format)) => (write_psbt(&mut stdout, &psbt, format), vec![]),
+        Ok(Output::Partial(psbt, format, errors)) => {
+            (write_psbt(&mut stdout, &psbt, format), errors)
+        }
+        Err(e) => {
+            eprintln!("{}", e);
+            process::exit(1);
+        }
+    };
+    if let Err(e) = result {
+        eprintln!("{}", e);
+        process::exit(1);
+    }
+    if !errors.is_empty() {
+        eprintln!("{}", errors.join("\n"));
+        process::exit(1);
+    }
+}
@@ -0,0 +1,321 @@
+use std::fmt;
+use std::str::FromStr;
+
+use bitcoin::bip32::{self, ChildNumber, DerivationPath};
+use bitcoin::Network;
+
+use crate::network::NetworkKind;
+
+/// How hardened steps are written in derivation paths
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum HardenedStyle {
+    /// `m/84'/0'/0'`
+    #[default]
+    Apostrophe,
+    /// `m/84h/0h/0h`, which doesn't need escaping in shells and descriptors
+    H,
+}
+
+pub const ALL_HARDENED_STYLES: [HardenedStyle; 2] = [HardenedStyle::Apostrophe, HardenedStyle::H];
+
+impl fmt::Display for HardenedStyle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HardenedStyle::Apostrophe => write!(f, "'"),
+            HardenedStyle::H => write!(f, "h"),
+        }
+    }
+}
+
+impl FromStr for HardenedStyle {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "'" => Ok(HardenedStyle::Apostrophe),
+            "h" => Ok(HardenedStyle::H),
+            _ => Err(format!("unknown hardened notation \"{}\"", s)),
+        }
+    }
+}
+
+/// Write `path` with the `style` hardened notation
+pub fn format_path(path: &DerivationPath, style: HardenedStyle) -> String {
+    match style {
+        HardenedStyle::Apostrophe => path.to_string(),
+        HardenedStyle::H => format!("{:#}", path),
+    }
+}
+
+/// Write a single step of a path with the `style` hardened notation
+pub fn format_step(step: ChildNumber, style: HardenedStyle) -> String {
+    match style {
+        HardenedStyle::Apostrophe => step.to_string(),
+        HardenedStyle::H => format!("{:#}", step),
+    }
+}
+
+/// Parse a derivation path written with either `'`, `h` or `H` for the hardened steps, with or
+/// without the leading `m/`
+pub fn parse_path(s: &str) -> Result<DerivationPath, bip32::Error> {
+    let s = s.trim().replace('H', "h");
+    let steps = match s.strip_prefix('m').or_else(|| s.strip_prefix('M')) {
+        Some("") => "",
+        Some(steps) => steps
+            .strip_prefix('/')
+            .ok_or(bip32::Error::InvalidDerivationPathFormat)?,
+        None => &s,
+    };
+    match steps.is_empty() {
+        true => Ok(DerivationPath::master()),
+        false => DerivationPath::from_str(&format!("m/{}", steps)),
+    }
+}
+
+/// Standard derivation schemes, identified by the purpose at the first level of the path
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Scheme {
+    Bip44,
+    Bip48,
+    Bip49,
+    Bip84,
+    Bip86,
+}
+
+impl Scheme {
+    pub fn from_purpose(purpose: u32) -> Option<Self> {
+        match purpose {
+            44 => Some(Scheme::Bip44),
+            48 => Some(Scheme::Bip48),
+            49 => Some(Scheme::Bip49),
+            84 => Some(Scheme::Bip84),
+            86 => Some(Scheme::Bip86),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Scheme::Bip44 => "BIP44",
+            Scheme::Bip48 => "BIP48",
+            Scheme::Bip49 => "BIP49",
+            Scheme::Bip84 => "BIP84",
+            Scheme::Bip86 => "BIP86",
+        }
+    }
+
+    pub fn script_type(&self) -> &'static str {
+        match self {
+            Scheme::Bip44 => "P2PKH",
+            Scheme::Bip48 => "multisig",
+            Scheme::Bip49 => "P2SH-P2WPKH",
+            Scheme::Bip84 => "P2WPKH",
+            Scheme::Bip86 => "P2TR",
+        }
+    }
+
+    /// The levels after the purpose
+    fn levels(&self) -> &'static [LevelKind] {
+        use LevelKind::*;
+
+        match self {
+            Scheme::Bip48 => &[CoinType, Account, ScriptType, Chain, Index],
+            _ => &[CoinType, Account, Chain, Index],
+        }
+    }
+}
+
+/// The levels of the standard derivation paths
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LevelKind {
+    CoinType,
+    Account,
+    /// Only in BIP48 paths
+    ScriptType,
+    Chain,
+    Index,
+}
+
+impl LevelKind {
+    pub fn label(&self) -> &'static str {
+        match self {
+            LevelKind::CoinType => "Coin type",
+            LevelKind::Account => "Account",
+            LevelKind::ScriptType => "Script type",
+            LevelKind::Chain => "Chain",
+            LevelKind::Index => "Index",
+        }
+    }
+
+    /// What `step` means at this level, and whether it departs from the standard
+    fn explain(&self, step: ChildNumber) -> (String, bool) {
+        let (index, hardened) = match step {
+            ChildNumber::Hardened { index } => (index, true),
+            ChildNumber::Normal { index } => (index, false),
+        };
+
+        match self {
+            LevelKind::CoinType => match NetworkKind::from_coin_type(index) {
+                Some(NetworkKind::Main) => ("Bitcoin".to_string(), !hardened),
+                Some(NetworkKind::Test) => ("testnet, signet and regtest".to_string(), !hardened),
+                None => ("another coin".to_string(), true),
+            },
+            LevelKind::Account => (format!("account #{}", index), !hardened),
+            LevelKind::ScriptType => match index {
+                1 => ("P2SH-P2WSH".to_string(), !hardened),
+                2 => ("P2WSH".to_string(), !hardened),
+                3 => ("P2TR".to_string(), !hardened),
+                _ => ("unknown script type".to_string(), true),
+            },
+            LevelKind::Chain => match index {
+                0 => ("receive".to_string(), hardened),
+                1 => ("change".to_string(), hardened),
+                _ => ("non-standard chain".to_string(), true),
+            },
+            LevelKind::Index => (format!("address #{}", index), hardened),
+        }
+    }
+}
+
+/// A level of a standard derivation path and what it means
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Level {
+    pub kind: LevelKind,
+    pub step: ChildNumber,
+    pub meaning: String,
+    /// Whether the step departs from the standard
+    pub unexpected: bool,
+}
+
+/// Explanation of a path that follows one of the standard schemes
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PathInfo {
+    pub scheme: Scheme,
+    pub levels: Vec<Level>,
+    /// Set when the coin type isn't the one of the selected network
+    pub coin_type_warning: Option<String>,
+}
+
+/// Explain each level of `path` when it follows one of the standard schemes, flagging the coin
+/// types that don't belong to `network`
+pub fn explain_path(path: &DerivationPath, network: Network) -> Option<PathInfo> {
+    let (purpose, rest) = path.as_ref().split_first()?;
+    let scheme = match purpose {
+        ChildNumber::Hardened { index } => Scheme::from_purpose(*index)?,
+        ChildNumber::Normal { .. } => return None,
+    };
+
+    let levels = scheme
+        .levels()
+        .iter()
+        .zip(rest)
+        .map(|(kind, step)| {
+            let (meaning, unexpected) = kind.explain(*step);
+            Level {
+                kind: *kind,
+                step: *step,
+                meaning,
+                unexpected,
+            }
+        })
+        .collect::<Vec<_>>();
+
+    let coin_type_warning = rest.first().and_then(|coin_type| {
+        let index = match coin_type {
+            ChildNumber::Hardened { index } | ChildNumber::Normal { index } => *index,
+        };
+        match NetworkKind::from_coin_type(index) {
+            Some(kind) if kind == NetworkKind::of(network) => None,
+            Some(kind) => Some(format!(
+                "coin type {} is for {}, but {} is selected",
+                index,
+                kind.name(),
+                network
+            )),
+            None => Some(format!("coin type {} isn't Bitcoin", index)),
+        }
+    });
+
+    Some(PathInfo {
+        scheme,
+        levels,
+        coin_type_warning,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_path() {
+        let path = DerivationPath::from_str("m/84'/0'/0'/1/5").unwrap();
+        assert_eq!(parse_path("m/84'/0'/0'/1/5").unwrap(), path);
+        assert_eq!(parse_path(" m/84h/0h/0H/1/5 ").unwrap(), path);
+        assert_eq!(parse_path("84h/0h/0h/1/5").unwrap(), path);
+        assert_eq!(parse_path("m").unwrap(), DerivationPath::master());
+        assert_eq!(parse_path("").unwrap(), DerivationPath::master());
+        assert!(parse_path("m/84x").is_err());
+        assert_eq!(parse_path("M/84h/0h/0h/1/5").unwrap(), path);
+        for malformed in ["mm/84h", "mmm//84h/0h/0h", "m//84h", "//84h", "m84h"].iter() {
+            assert!(parse_path(malformed).is_err(), "{}", malformed);
+        }
+
+        assert_eq!(
+            format_path(&path, HardenedStyle::Apostrophe),
+            "m/84'/0'/0'/1/5"
+        );
+        assert_eq!(format_path(&path, HardenedStyle::H), "m/84h/0h/0h/1/5");
+        assert_eq!(format_step(path[0], HardenedStyle::H), "84h");
+        assert_eq!(format_step(path[4], HardenedStyle::H), "5");
+
+        for style in ALL_HARDENED_STYLES.iter() {
+            assert_eq!(style.to_string().parse::<HardenedStyle>(), Ok(*style));
+        }
+    }
+
+    #[test]
+    fn test_explain_path() {
+        let path = parse_path("m/84h/1h/0h/1/5").unwrap();
+        let info = explain_path(&path, Network::Testnet).unwrap();
+        assert_eq!(info.scheme, Scheme::Bip84);
+        assert_eq!(info.coin_type_warning, None);
+        assert_eq!(
+            info.levels
+                .iter()
+                .map(|l| (l.kind, l.meaning.as_str(), l.unexpected))
+                .collect::<Vec<_>>(),
+            vec![
+                (LevelKind::CoinType, "testnet, signet and regtest", false),
+                (LevelKind::Account, "account #0", false),
+                (LevelKind::Chain, "change", false),
+                (LevelKind::Index, "address #5", false),
+            ]
+        );
+
+        let info = explain_path(&path, Network::Bitcoin).unwrap();
+        assert_eq!(
+            info.coin_type_warning.unwrap(),
+            "coin type 1 is for a test network, but bitcoin is selected"
+        );
+
+        // Account level path of a P2WSH multisig
+        let info = explain_path(&parse_path("m/48'/0'/3'/2'").unwrap(), Network::Bitcoin).unwrap();
+        assert_eq!(info.scheme, Scheme::Bip48);
+        assert_eq!(info.levels.len(), 3);
+        assert_eq!(info.levels[2].meaning, "P2WSH");
+
+        let info = explain_path(&parse_path("m/44'/2'/0/0/0").unwrap(), Network::Bitcoin).unwrap();
+        assert_eq!(info.coin_type_warning.unwrap(), "coin type 2 isn't Bitcoin");
+        assert!(info.levels[1].unexpected);
+
+        assert_eq!(
+            explain_path(&parse_path("m/0'/0'/4'").unwrap(), Network::Bitcoin),
+            None
+        );
+        assert_eq!(
+            explain_path(&parse_path("m/84/0/0").unwrap(), Network::Bitcoin),
+            None
+        );
+    }
+}
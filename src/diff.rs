@@ -0,0 +1,342 @@
+use std::collections::BTreeMap;
+use std::fmt::Display;
+
+use bitcoin::{bip32, psbt, secp256k1};
+
+/// How a field or a map key changed, going from the other PSBT to the one being displayed
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Change {
+    Added,
+    Removed,
+    Changed,
+}
+
+impl Change {
+    fn of<T: PartialEq + Presence>(ours: &T, theirs: &T) -> Option<Change> {
+        match (ours.is_set(), theirs.is_set()) {
+            (true, false) => Some(Change::Added),
+            (false, true) => Some(Change::Removed),
+            (true, true) if ours != theirs => Some(Change::Changed),
+            _ => None,
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Change::Added => "added",
+            Change::Removed => "removed",
+            Change::Changed => "changed",
+        }
+    }
+
+    pub fn class(&self) -> &'static str {
+        match self {
+            Change::Added => "diff-added",
+            Change::Removed => "diff-removed",
+            Change::Changed => "diff-changed",
+        }
+    }
+}
+
+trait Presence {
+    fn is_set(&self) -> bool;
+}
+
+impl<T> Presence for Option<T> {
+    fn is_set(&self) -> bool {
+        self.is_some()
+    }
+}
+
+impl<K, V> Presence for BTreeMap<K, V> {
+    fn is_set(&self) -> bool {
+        !self.is_empty()
+    }
+}
+
+impl Presence for u32 {
+    fn is_set(&self) -> bool {
+        true
+    }
+}
+
+fn diff_map<K: Ord + Clone, V: PartialEq>(
+    ours: &BTreeMap<K, V>,
+    theirs: &BTreeMap<K, V>,
+) -> BTreeMap<K, Change> {
+    let mut changes = BTreeMap::new();
+    for (k, v) in ours {
+        match theirs.get(k) {
+            None => changes.insert(k.clone(), Change::Added),
+            Some(other) if other != v => changes.insert(k.clone(), Change::Changed),
+            _ => None,
+        };
+    }
+    for k in theirs.keys().filter(|k| !ours.contains_key(k)) {
+        changes.insert(k.clone(), Change::Removed);
+    }
+
+    changes
+}
+
+macro_rules! diff_fields {
+    ($ours:expr, $theirs:expr, [ $($field:ident),* ]) => {{
+        let mut fields = BTreeMap::new();
+        $(
+            if let Some(change) = Change::of(&$ours.$field, &$theirs.$field) {
+                fields.insert(stringify!($field), change);
+            }
+        )*
+        fields
+    }};
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct InputDiff {
+    pub fields: BTreeMap<&'static str, Change>,
+    pub partial_sigs: BTreeMap<bitcoin::PublicKey, Change>,
+    pub bip32_derivation: BTreeMap<secp256k1::PublicKey, Change>,
+}
+
+impl InputDiff {
+    fn new(ours: &psbt::Input, theirs: &psbt::Input) -> Self {
+        InputDiff {
+            fields: diff_fields!(
+                ours,
+                theirs,
+                [
+                    non_witness_utxo,
+                    witness_utxo,
+                    partial_sigs,
+                    sighash_type,
+                    redeem_script,
+                    witness_script,
+                    bip32_derivation,
+                    final_script_sig,
+                    final_script_witness,
+                    ripemd160_preimages,
+                    sha256_preimages,
+                    hash160_preimages,
+                    hash256_preimages,
+                    tap_key_sig,
+                    tap_script_sigs,
+                    tap_scripts,
+                    tap_key_origins,
+                    tap_internal_key,
+                    tap_merkle_root,
+                    proprietary,
+                    unknown
+                ]
+            ),
+            partial_sigs: diff_map(&ours.partial_sigs, &theirs.partial_sigs),
+            bip32_derivation: diff_map(&ours.bip32_derivation, &theirs.bip32_derivation),
+        }
+    }
+
+    pub fn field(&self, name: &str) -> Option<Change> {
+        self.fields.get(name).copied()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.fields.is_empty()
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct OutputDiff {
+    pub fields: BTreeMap<&'static str, Change>,
+    pub bip32_derivation: BTreeMap<secp256k1::PublicKey, Change>,
+}
+
+impl OutputDiff {
+    fn new(ours: &psbt::Output, theirs: &psbt::Output) -> Self {
+        OutputDiff {
+            fields: diff_fields!(
+                ours,
+                theirs,
+                [
+                    redeem_script,
+                    witness_script,
+                    bip32_derivation,
+                    tap_internal_key,
+                    tap_tree,
+                    tap_key_origins,
+                    proprietary,
+                    unknown
+                ]
+            ),
+            bip32_derivation: diff_map(&ours.bip32_derivation, &theirs.bip32_derivation),
+        }
+    }
+
+    pub fn field(&self, name: &str) -> Option<Change> {
+        self.fields.get(name).copied()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.fields.is_empty()
+    }
+}
+
+/// Structural differences between the displayed PSBT and another one
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PsbtDiff {
+    /// When this is set the two PSBTs don't even describe the same transaction
+    pub unsigned_tx: bool,
+    pub global: BTreeMap<&'static str, Change>,
+    pub xpub: BTreeMap<bip32::Xpub, Change>,
+    pub inputs: Vec<InputDiff>,
+    pub outputs: Vec<OutputDiff>,
+}
+
+impl PsbtDiff {
+    pub fn new(ours: &psbt::Psbt, theirs: &psbt::Psbt) -> Self {
+        // Inputs and outputs that don't exist in the other PSBT are compared against empty ones
+        let empty_input = psbt::Input::default();
+        let empty_output = psbt::Output::default();
+
+        PsbtDiff {
+            unsigned_tx: ours.unsigned_tx != theirs.unsigned_tx,
+            global: diff_fields!(ours, theirs, [version, xpub, proprietary, unknown]),
+            xpub: diff_map(&ours.xpub, &theirs.xpub),
+            inputs: ours
+                .inputs
+                .iter()
+                .enumerate()
+                .map(|(i, input)| {
+                    InputDiff::new(input, theirs.inputs.get(i).unwrap_or(&empty_input))
+                })
+                .collect(),
+            outputs: ours
+                .outputs
+                .iter()
+                .enumerate()
+                .map(|(i, output)| {
+                    OutputDiff::new(output, theirs.outputs.get(i).unwrap_or(&empty_output))
+                })
+                .collect(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        !self.unsigned_tx
+            && self.global.is_empty()
+            && self.inputs.iter().all(InputDiff::is_empty)
+            && self.outputs.iter().all(OutputDiff::is_empty)
+    }
+
+    /// One line per changed field, with the individual keys for the maps that we display
+    pub fn summary(&self) -> Vec<String> {
+        fn describe(
+            lines: &mut Vec<String>,
+            section: &str,
+            fields: &BTreeMap<&'static str, Change>,
+            keys: &[(&str, Vec<(String, Change)>)],
+        ) {
+            for (field, change) in fields {
+                match keys.iter().find(|(name, _)| name == field) {
+                    Some((_, keys)) => lines.extend(keys.iter().map(|(k, change)| {
+                        format!("{}: {} {} {}", section, field, k, change.label())
+                    })),
+                    None => lines.push(format!("{}: {} {}", section, field, change.label())),
+                }
+            }
+        }
+        fn keys<K: Display>(map: &BTreeMap<K, Change>) -> Vec<(String, Change)> {
+            map.iter().map(|(k, c)| (k.to_string(), *c)).collect()
+        }
+
+        let mut lines = vec![];
+        if self.unsigned_tx {
+            lines.push("global: unsigned_tx changed".to_string());
+        }
+        describe(
+            &mut lines,
+            "global",
+            &self.global,
+            &[("xpub", keys(&self.xpub))],
+        );
+        for (i, input) in self.inputs.iter().enumerate() {
+            describe(
+                &mut lines,
+                &format!("input #{}", i),
+                &input.fields,
+                &[
+                    ("partial_sigs", keys(&input.partial_sigs)),
+                    ("bip32_derivation", keys(&input.bip32_derivation)),
+                ],
+            );
+        }
+        for (i, output) in self.outputs.iter().enumerate() {
+            describe(
+                &mut lines,
+                &format!("output #{}", i),
+                &output.fields,
+                &[("bip32_derivation", keys(&output.bip32_derivation))],
+            );
+        }
+
+        lines
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::model::{parse_psbt, EXAMPLE_PSBTS};
+
+    use super::*;
+
+    #[test]
+    fn test_psbt_diff() {
+        let a = parse_psbt(EXAMPLE_PSBTS[1].1).unwrap();
+        assert!(PsbtDiff::new(&a, &a).is_empty());
+
+        let mut b = a.clone();
+        let sig_key = *b.inputs[0].partial_sigs.keys().next().unwrap();
+        b.inputs[0].partial_sigs.clear();
+        b.inputs[0].witness_script = None;
+        let (&derivation_key, _) = b.inputs[0].bip32_derivation.iter().next().unwrap();
+        b.inputs[0]
+            .bip32_derivation
+            .get_mut(&derivation_key)
+            .unwrap()
+            .0 = bip32::Fingerprint::from([0u8; 4]);
+
+        // From b to a: the signer added the signature and the witness script
+        let diff = PsbtDiff::new(&a, &b);
+        assert!(!diff.unsigned_tx);
+        assert_eq!(diff.inputs[0].field("partial_sigs"), Some(Change::Added));
+        assert_eq!(
+            diff.inputs[0].partial_sigs.get(&sig_key),
+            Some(&Change::Added)
+        );
+        assert_eq!(diff.inputs[0].field("witness_script"), Some(Change::Added));
+        assert_eq!(
+            diff.inputs[0].field("bip32_derivation"),
+            Some(Change::Changed)
+        );
+        assert_eq!(
+            diff.inputs[0].bip32_derivation.get(&derivation_key),
+            Some(&Change::Changed)
+        );
+        assert_eq!(diff.inputs[0].bip32_derivation.len(), 1);
+        assert!(diff.outputs[0].is_empty());
+
+        // And the other way around
+        let diff = PsbtDiff::new(&b, &a);
+        assert_eq!(
+            diff.inputs[0].field("witness_script"),
+            Some(Change::Removed)
+        );
+        assert_eq!(
+            diff.summary()[..2],
+            [
+                format!("input #0: bip32_derivation {} changed", derivation_key),
+                format!("input #0: partial_sigs {} removed", sig_key),
+            ]
+        );
+
+        b.unsigned_tx.lock_time = bitcoin::absolute::LockTime::from_consensus(100);
+        assert!(PsbtDiff::new(&a, &b).unsigned_tx);
+    }
+}
@@ -0,0 +1,111 @@
+#[allow(unused_imports)]
+use log::*;
+
+use wasm_bindgen::JsCast;
+use yew::prelude::*;
+use yew::web_sys;
+
+use bitcoin::{psbt, Network};
+
+use crate::json::psbt_to_json;
+
+/// Offer `data` to the user as a file download
+pub fn download(filename: &str, mime: &str, data: &[u8]) {
+    let window = web_sys::window().unwrap();
+    let document = window.document().unwrap();
+
+    let parts = js_sys::Array::of1(&js_sys::Uint8Array::from(data));
+    let options = web_sys::BlobPropertyBag::new();
+    options.set_type(mime);
+    let blob = web_sys::Blob::new_with_u8_array_sequence_and_options(&parts, &options).unwrap();
+    let url = web_sys::Url::create_object_url_with_blob(&blob).unwrap();
+
+    let anchor = document
+        .create_element("a")
+        .unwrap()
+        .dyn_into::<web_sys::HtmlAnchorElement>()
+        .unwrap();
+    anchor.set_href(&url);
+    anchor.set_download(filename);
+    anchor.click();
+
+    web_sys::Url::revoke_object_url(&url).unwrap();
+}
+
+pub fn copy_to_clipboard(text: &str) {
+    let navigator = web_sys::window().unwrap().navigator();
+    let _ = navigator.clipboard().write_text(text);
+}
+
+pub struct ExportMenu {
+    link: ComponentLink<Self>,
+    props: ExportMenuProps,
+}
+
+#[derive(Clone, Properties)]
+pub struct ExportMenuProps {
+    pub psbt: psbt::Psbt,
+    pub network: Network,
+}
+
+pub enum ExportMenuMsg {
+    DownloadBinary,
+    DownloadJson,
+    CopyHex,
+    CopyBase64,
+}
+
+impl Component for ExportMenu {
+    type Message = ExportMenuMsg;
+    type Properties = ExportMenuProps;
+
+    fn create(props: Self::Properties, link: ComponentLink<Self>) -> Self {
+        ExportMenu { link, props }
+    }
+
+    fn change(&mut self, props: Self::Properties) -> ShouldRender {
+        self.props = props;
+        true
+    }
+
+    fn update(&mut self, msg: Self::Message) -> ShouldRender {
+        let psbt = &self.props.psbt;
+        let name = psbt.unsigned_tx.txid().to_string();
+
+        match msg {
+            ExportMenuMsg::DownloadBinary => download(
+                &format!("{}.psbt", name),
+                "application/octet-stream",
+                &psbt.serialize(),
+            ),
+            ExportMenuMsg::DownloadJson => download(
+                &format!("{}.json", name),
+                "application/json",
+                serde_json::to_string_pretty(&psbt_to_json(psbt, self.props.network))
+                    .unwrap()
+                    .as_bytes(),
+            ),
+            ExportMenuMsg::CopyHex => copy_to_clipboard(&psbt.serialize_hex()),
+            ExportMenuMsg::CopyBase64 => copy_to_clipboard(&psbt.to_string()),
+        }
+
+        false
+    }
+
+    fn view(&self) -> Html {
+        html! {
+            <div class="dropdown">
+                <button class="btn btn-outline-secondary dropdown-toggle" type="button" id="exportDropdown" data-bs-toggle="dropdown" aria-expanded="false">
+                    <i class="bi bi-box-arrow-up"></i>{ " Export" }
+                </button>
+                <ul class="dropdown-menu dropdown-menu-end" aria-labelledby="exportDropdown">
+                    <li><a class="dropdown-item" onclick=self.link.callback(|_| ExportMenuMsg::DownloadBinary)><i class="bi bi-download"></i>{ " Binary file (.psbt)" }</a></li>
+                    <li><a class="dropdown-item" onclick=self.link.callback(|_| ExportMenuMsg::DownloadJson)><i class="bi bi-filetype-json"></i>{ " decodepsbt JSON" }</a></li>
+                    <li><hr class="dropdown-divider" /></li>
+                    <li><a class="dropdown-item" onclick=self.link.callback(|_| ExportMenuMsg::CopyHex)><i class="bi bi-clipboard"></i>{ " Copy hex" }</a></li>
+                    <li><a class="dropdown-item" onclick=self.link.callback(|_| ExportMenuMsg::CopyBase64)><i class="bi bi-clipboard"></i>{ " Copy base64" }</a></li>
+                </ul>
+            </div>
+        }
+    }
+}
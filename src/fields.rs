@@ -9,13 +9,22 @@ use log::*;
 use yew::prelude::*;
 use yew::virtual_dom::VComp;
 
-use crate::app::{Field, ParentMessage};
+use bitcoin::Amount;
+
+use crate::app::ParentMessage;
 use crate::bootstrap::*;
+use crate::derivation::HardenedStyle;
+use crate::diff::Change;
+use crate::export::copy_to_clipboard;
+use crate::model::{Field, MapUpdate};
+use crate::units::{parse_amount, Unit};
 
 pub trait BuildComponent<P: Component, const N: usize>: Sized {
     fn build_component<X: 'static + Clone + PartialEq>(
         &self,
         is_map: bool,
+        read_only: bool,
+        hardened_style: HardenedStyle,
         tag: Option<X>,
         label: Option<[String; N]>,
         parent: ComponentLink<P>,
@@ -32,6 +41,8 @@ where
     fn build_component<X: 'static + Clone + PartialEq>(
         &self,
         is_map: bool,
+        read_only: bool,
+        hardened_style: HardenedStyle,
         tag: Option<X>,
         label: Option<[String; N]>,
         parent: ComponentLink<P>,
@@ -45,6 +56,8 @@ where
                 size: None,
                 is_scrollable: false,
                 is_map,
+                read_only,
+                hardened_style,
                 tag,
                 label,
                 parent,
@@ -69,6 +82,9 @@ where
     pub size: Option<[usize; N]>,
     pub is_map: bool,
     pub is_scrollable: bool,
+    /// Show the value as text instead of an editable field
+    pub read_only: bool,
+    pub hardened_style: HardenedStyle,
 }
 
 pub struct SingleFieldPropsBuilder<T, P: Component, X, const N: usize> {
@@ -79,6 +95,8 @@ pub struct SingleFieldPropsBuilder<T, P: Component, X, const N: usize> {
     size: Option<[usize; N]>,
     is_map: bool,
     is_scrollable: bool,
+    read_only: bool,
+    hardened_style: HardenedStyle,
 }
 
 pub trait SingleOrArrayLabels<const N: usize> {
@@ -143,6 +161,16 @@ where
         self
     }
 
+    pub fn read_only(mut self, read_only: bool) -> Self {
+        self.read_only = read_only;
+        self
+    }
+
+    pub fn hardened_style(mut self, hardened_style: HardenedStyle) -> Self {
+        self.hardened_style = hardened_style;
+        self
+    }
+
     pub fn build(self) -> SingleFieldProps<T, P, X, N> {
         SingleFieldProps {
             value: self.value.unwrap(),
@@ -152,6 +180,8 @@ where
             label: self.label,
             is_map: self.is_map,
             is_scrollable: self.is_scrollable,
+            read_only: self.read_only,
+            hardened_style: self.hardened_style,
         }
     }
 }
@@ -173,6 +203,8 @@ where
             size: None,
             is_map: false,
             is_scrollable: false,
+            read_only: false,
+            hardened_style: HardenedStyle::default(),
         }
     }
 }
@@ -207,6 +239,35 @@ fn set_height(field: &yew::web_sys::HtmlElement) {
         .unwrap();
 }
 
+/// Value of a field in read-only mode, as text with a button to copy it
+fn view_read_only(label: Option<&String>, value: &str) -> Html {
+    let copied = value.to_string();
+
+    html! {
+        <div class="read-only-field border rounded bg-white px-2 py-1 h-100 d-flex align-items-start">
+            <div class="flex-grow-1 overflow-hidden">
+                { label.map(|l| html! { <div class="small text-muted">{ l }</div> }).unwrap_or_default() }
+                {
+                    match value.is_empty() {
+                        true => html! { <div class="text-muted fst-italic">{ "Empty" }</div> },
+                        false => html! { <div class="font-monospace text-break">{ value }</div> },
+                    }
+                }
+            </div>
+            {
+                match value.is_empty() {
+                    true => html! {},
+                    false => html! {
+                        <button type="button" class="btn btn-sm btn-link text-secondary p-0 ms-2" title="Copy" onclick=Callback::from(move |_| copy_to_clipboard(&copied))>
+                            <i class="bi bi-clipboard"></i>
+                        </button>
+                    },
+                }
+            }
+        </div>
+    }
+}
+
 impl<T, P, X, const N: usize> Component for SingleField<T, P, X, N>
 where
     T: 'static + Field<N> + PartialEq,
@@ -220,7 +281,7 @@ where
     fn create(props: Self::Properties, link: ComponentLink<Self>) -> Self {
         SingleField {
             link,
-            serialized: props.value.bip174_serialize(),
+            serialized: props.value.bip174_display(props.hardened_style),
             props,
             error: None,
             node_ref: NodeRef::default(),
@@ -229,8 +290,9 @@ where
     }
 
     fn change(&mut self, props: Self::Properties) -> ShouldRender {
-        if props.value != self.props.value {
-            self.serialized = props.value.bip174_serialize();
+        let restyled = props.hardened_style != self.props.hardened_style && self.error.is_none();
+        if props.value != self.props.value || restyled {
+            self.serialized = props.value.bip174_display(props.hardened_style);
             self.error = None;
         }
 
@@ -259,7 +321,12 @@ where
                             self.props.tag.clone(),
                         ));
                 }
-                Err(e) => self.error = Some(format!("{:?}", e)),
+                Err(e) => {
+                    self.error = Some(match &self.props.label {
+                        Some(label) => format!("{}: {}", label[i], e),
+                        None => e.to_string(),
+                    })
+                }
             }
         }
 
@@ -289,6 +356,20 @@ where
             Some(s) => s.iter().map(Option::Some).collect::<Vec<_>>(),
             None => vec![None; N],
         };
+        if self.props.read_only {
+            return html! {
+                <Row>
+                    {
+                        for self.serialized.iter().zip(size.iter().zip(padding.iter())).zip(label.iter()).map(|((v, (s, p)), l)| html! {
+                            <Column xs=*s class=classes!(p).to_string()>
+                                { view_read_only(*l, v) }
+                            </Column>
+                        })
+                    }
+                </Row>
+            };
+        }
+
         let is_invalid = self.error.as_ref().map(|_| "is-invalid");
         let error = self
             .error
@@ -359,6 +440,8 @@ where
     pub tag: Option<X>,
     #[prop_or(None)]
     pub label: Option<String>,
+    #[prop_or_default]
+    pub read_only: bool,
 }
 
 pub enum SelectFieldMsg {
@@ -423,6 +506,15 @@ where
 
     fn view(&self) -> Html {
         let selected = |v: Option<&T>| v == self.props.selected.as_ref();
+        if self.props.read_only {
+            let value = self
+                .props
+                .selected
+                .as_ref()
+                .map(ToString::to_string)
+                .unwrap_or_else(|| "Default".into());
+            return view_read_only(self.props.label.as_ref(), &value);
+        }
 
         html! {
             <div class="form-floating">
@@ -489,6 +581,10 @@ macro_rules! impl_marker {
             fn bip174_serialize(&self) -> [String; N] {
                 self.1.bip174_serialize()
             }
+
+            fn bip174_display(&self, hardened: HardenedStyle) -> [String; N] {
+                self.1.bip174_display(hardened)
+            }
         }
     };
 }
@@ -515,6 +611,9 @@ where
     pub label: Option<String>,
     pub key_label: Option<[String; KN]>,
     pub value_label: Option<[String; VN]>,
+    pub highlight: BTreeMap<K, Change>,
+    pub read_only: bool,
+    pub hardened_style: HardenedStyle,
 }
 
 #[derive(Clone)]
@@ -525,6 +624,9 @@ pub struct MapFieldPropsBuilder<K, V, P: Component, X, const KN: usize, const VN
     label: Option<String>,
     key_label: Option<[String; KN]>,
     value_label: Option<[String; VN]>,
+    highlight: BTreeMap<K, Change>,
+    read_only: bool,
+    hardened_style: HardenedStyle,
 }
 
 #[allow(unused)]
@@ -566,6 +668,21 @@ where
         self
     }
 
+    pub fn highlight(mut self, highlight: BTreeMap<K, Change>) -> Self {
+        self.highlight = highlight;
+        self
+    }
+
+    pub fn read_only(mut self, read_only: bool) -> Self {
+        self.read_only = read_only;
+        self
+    }
+
+    pub fn hardened_style(mut self, hardened_style: HardenedStyle) -> Self {
+        self.hardened_style = hardened_style;
+        self
+    }
+
     pub fn build(self) -> MapFieldProps<K, V, P, X, KN, VN> {
         MapFieldProps {
             map: self.map.unwrap(),
@@ -574,6 +691,9 @@ where
             label: self.label,
             key_label: self.key_label,
             value_label: self.value_label,
+            highlight: self.highlight,
+            read_only: self.read_only,
+            hardened_style: self.hardened_style,
         }
     }
 }
@@ -595,6 +715,9 @@ where
             label: None,
             key_label: None,
             value_label: None,
+            highlight: BTreeMap::new(),
+            read_only: false,
+            hardened_style: HardenedStyle::default(),
         }
     }
 }
@@ -610,12 +733,6 @@ pub enum MapFieldMsg<K, V> {
     AddNew,
 }
 
-#[derive(Clone, Debug)]
-pub enum MapUpdate<K, V> {
-    Set(K, V),
-    Remove(K),
-}
-
 #[derive(Clone)]
 pub struct MapField<K, V, P, X, const KN: usize, const VN: usize>
 where
@@ -710,14 +827,20 @@ where
     fn view(&self) -> Html {
         #![allow(unused_parens)]
 
+        let read_only = self.props.read_only;
+        let hardened_style = self.props.hardened_style;
         let new_key = (Key, self.new_key.clone()).build_component(
             true,
+            false,
+            hardened_style,
             None,
             self.props.key_label.clone(),
             self.link.clone(),
         );
         let new_value = (Value, self.new_value.clone()).build_component(
             true,
+            false,
+            hardened_style,
             None,
             self.props.value_label.clone(),
             self.link.clone(),
@@ -730,11 +853,26 @@ where
                     for self.props.map.iter().map(|(k, v)| {
                         let k_cloned = k.clone();
 
-                        let key = (Key, k.clone()).build_component(true, Some(k.clone()), self.props.key_label.clone(), self.link.clone());
-                        let value = (Value, v.clone()).build_component(true, Some(k.clone()), self.props.value_label.clone(), self.link.clone());
+                        let key = (Key, k.clone()).build_component(true, read_only, hardened_style, Some(k.clone()), self.props.key_label.clone(), self.link.clone());
+                        let value = (Value, v.clone()).build_component(true, read_only, hardened_style, Some(k.clone()), self.props.value_label.clone(), self.link.clone());
+
+                        let change = self.props.highlight.get(k).map(Change::class);
+
+                        if read_only {
+                            return html! {
+                                <Row class=classes!("px-1", "d-flex", "align-items-stretch", "map-row", change).to_string()>
+                                    <Column xs=5>
+                                        { key }
+                                    </Column>
+                                    <Column xs=7>
+                                        { value }
+                                    </Column>
+                                </Row>
+                            };
+                        }
 
                         html! {
-                            <Row class="px-1 d-flex align-items-stretch map-row">
+                            <Row class=classes!("px-1", "d-flex", "align-items-stretch", "map-row", change).to_string()>
                                 <Column xs=4>
                                     { key }
                                 </Column>
@@ -748,17 +886,128 @@ where
                         }
                     })
                 }
-                <Row class="px-1 d-flex align-items-stretch map-row">
-                    <Column xs=4>
-                        { new_key }
-                    </Column>
-                    <Column xs=7>
-                        { new_value }
-                    </Column>
-                    <Column xs=1 class="p-0">
-                        <button type="button" class="btn-height-stretch btn btn-outline-secondary" disabled=(self.new_key.is_none() || self.new_value.is_none()) onclick=self.link.callback_once(|_| MapFieldMsg::AddNew)><i class="bi bi-plus"></i></button>
-                    </Column>
-                </Row>
+                {
+                    // Keys that only exist in the PSBT we are comparing against
+                    for self.props.highlight.iter().filter(|(_, c)| **c == Change::Removed).map(|(k, c)| html! {
+                        <Row class=classes!("px-1", "map-row", c.class()).to_string()>
+                            <Column xs=12>
+                                <del class="font-monospace text-break">{ k.bip174_serialize().join(" ") }</del>
+                            </Column>
+                        </Row>
+                    })
+                }
+                {
+                    if read_only && self.props.map.is_empty() {
+                        html! { <div class="text-muted fst-italic">{ "Empty" }</div> }
+                    } else if read_only {
+                        html! {}
+                    } else {
+                        html! {
+                            <Row class="px-1 d-flex align-items-stretch map-row">
+                                <Column xs=4>
+                                    { new_key }
+                                </Column>
+                                <Column xs=7>
+                                    { new_value }
+                                </Column>
+                                <Column xs=1 class="p-0">
+                                    <button type="button" class="btn-height-stretch btn btn-outline-secondary" disabled=(self.new_key.is_none() || self.new_value.is_none()) onclick=self.link.callback_once(|_| MapFieldMsg::AddNew)><i class="bi bi-plus"></i></button>
+                                </Column>
+                            </Row>
+                        }
+                    }
+                }
+            </div>
+        }
+    }
+}
+
+#[derive(Clone, Properties)]
+pub struct AmountFieldProps {
+    pub label: String,
+    pub value: Amount,
+    pub unit: Unit,
+    #[prop_or_default]
+    pub read_only: bool,
+    pub onchange: Callback<Amount>,
+}
+
+/// Amount shown in the selected unit, which can be entered in any unit by adding a suffix
+pub struct AmountField {
+    link: ComponentLink<Self>,
+    props: AmountFieldProps,
+
+    text: String,
+    error: Option<String>,
+}
+
+pub enum AmountFieldMsg {
+    Change(ChangeData),
+}
+
+impl Component for AmountField {
+    type Message = AmountFieldMsg;
+    type Properties = AmountFieldProps;
+
+    fn create(props: Self::Properties, link: ComponentLink<Self>) -> Self {
+        AmountField {
+            link,
+            text: props.unit.format_value(props.value),
+            props,
+            error: None,
+        }
+    }
+
+    fn change(&mut self, props: Self::Properties) -> ShouldRender {
+        if props.value != self.props.value || props.unit != self.props.unit {
+            self.text = props.unit.format_value(props.value);
+            self.error = None;
+        }
+        self.props = props;
+        true
+    }
+
+    fn update(&mut self, msg: Self::Message) -> ShouldRender {
+        let AmountFieldMsg::Change(data) = msg;
+        let text = match data {
+            ChangeData::Value(text) => text,
+            _ => return false,
+        };
+
+        match parse_amount(&text, self.props.unit) {
+            Ok(amount) if amount == self.props.value => {
+                self.text = self.props.unit.format_value(amount);
+                self.error = None;
+            }
+            Ok(amount) => {
+                self.error = None;
+                self.props.onchange.emit(amount);
+            }
+            Err(e) => {
+                self.text = text;
+                self.error = Some(e);
+            }
+        }
+        true
+    }
+
+    fn view(&self) -> Html {
+        if self.props.read_only {
+            return view_read_only(
+                Some(&self.props.label),
+                &self.props.unit.format(self.props.value),
+            );
+        }
+
+        let is_invalid = self.error.as_ref().map(|_| "is-invalid");
+        html! {
+            <div class=classes!("input-group", "has-validation", is_invalid)>
+                <div class=classes!("form-floating", is_invalid)>
+                    <input type="text" class=classes!("form-control", "font-monospace", is_invalid) value=self.text.clone() onchange=self.link.callback(AmountFieldMsg::Change) spellcheck="false" />
+                    <label>{ &self.props.label }</label>
+                </div>
+                <span class="input-group-text">{ self.props.unit.to_string() }</span>
+                { self.error.as_ref().map(|e| html! { <div class="invalid-feedback">{ e }</div> }).unwrap_or_default() }
             </div>
         }
     }
@@ -0,0 +1,237 @@
+use std::convert::TryFrom;
+
+use bitcoin::script::{Builder, PushBytes};
+use bitcoin::{psbt, ScriptBuf, Transaction, TxIn, WScriptHash, Witness};
+
+use crate::lint::spent_output;
+use crate::template::Template;
+
+/// Stack items that satisfy one of the standard templates with the partial signatures
+fn satisfy(template: &Template, input: &psbt::Input) -> Result<Vec<Vec<u8>>, String> {
+    let signed_key = |matches: &dyn Fn(&bitcoin::PublicKey) -> bool| {
+        input
+            .partial_sigs
+            .iter()
+            .find(|(key, _)| matches(key))
+            .map(|(key, sig)| vec![sig.to_vec(), key.to_bytes()])
+            .ok_or_else(|| "missing the signature".to_string())
+    };
+
+    match template {
+        Template::P2pk(key) => input
+            .partial_sigs
+            .get(key)
+            .map(|sig| vec![sig.to_vec()])
+            .ok_or_else(|| "missing the signature".to_string()),
+        Template::P2pkh(hash) => signed_key(&|key| key.pubkey_hash() == *hash),
+        Template::P2wpkh(hash) => signed_key(&|key| key.wpubkey_hash() == Some(*hash)),
+        Template::Multisig { required, keys } => {
+            let sigs = keys
+                .iter()
+                .filter_map(|key| input.partial_sigs.get(key))
+                .take(*required)
+                .map(|sig| sig.to_vec())
+                .collect::<Vec<_>>();
+            if sigs.len() < *required {
+                return Err(format!("needs {} signatures, has {}", required, sigs.len()));
+            }
+
+            // The extra element consumed by OP_CHECKMULTISIG
+            Ok(std::iter::once(vec![]).chain(sigs).collect())
+        }
+        other => Err(format!("can't satisfy a {} script", other.name())),
+    }
+}
+
+fn satisfy_witness_script(input: &psbt::Input, hash: &WScriptHash) -> Result<Vec<Vec<u8>>, String> {
+    let witness_script = input
+        .witness_script
+        .as_ref()
+        .ok_or("missing the witness script")?;
+    if witness_script.wscript_hash() != *hash {
+        return Err("the witness script doesn't match the spent output".to_string());
+    }
+    let mut stack = satisfy(&Template::classify(witness_script), input)?;
+    stack.push(witness_script.to_bytes());
+
+    Ok(stack)
+}
+
+fn build_script_sig(stack: Vec<Vec<u8>>) -> Result<ScriptBuf, String> {
+    stack
+        .iter()
+        .try_fold(Builder::new(), |builder, item| {
+            <&PushBytes>::try_from(item.as_slice()).map(|data| builder.push_slice(data))
+        })
+        .map(Builder::into_script)
+        .map_err(|_| "an element of the script sig is too large to be pushed".to_string())
+}
+
+/// Build the final script sig and witness of an input with a standard script, then remove
+/// everything else that isn't needed anymore as the finalizer does in BIP 174. The input is left
+/// untouched when it can't be finalized
+pub fn finalize_input(input: &mut psbt::Input, txin: &TxIn) -> Result<(), String> {
+    if input.final_script_sig.is_some() || input.final_script_witness.is_some() {
+        return Ok(());
+    }
+
+    let script_pubkey = &spent_output(input, txin)
+        .ok_or("the spent output is unknown")?
+        .script_pubkey;
+    let (script_sig, witness) = match Template::classify(script_pubkey) {
+        Template::P2tr(_) => {
+            let sig = input
+                .tap_key_sig
+                .ok_or("missing the key path signature, script path spends aren't supported")?;
+            (None, Some(vec![sig.to_vec()]))
+        }
+        Template::P2sh(hash) => {
+            let redeem_script = input
+                .redeem_script
+                .as_ref()
+                .ok_or("missing the redeem script")?;
+            if redeem_script.script_hash() != hash {
+                return Err("the redeem script doesn't match the spent output".to_string());
+            }
+            let (mut stack, witness) = match Template::classify(redeem_script) {
+                template @ Template::P2wpkh(_) => (vec![], Some(satisfy(&template, input)?)),
+                Template::P2wsh(hash) => (vec![], Some(satisfy_witness_script(input, &hash)?)),
+                template => (satisfy(&template, input)?, None),
+            };
+            stack.push(redeem_script.to_bytes());
+
+            (Some(stack), witness)
+        }
+        Template::P2wsh(hash) => (None, Some(satisfy_witness_script(input, &hash)?)),
+        template @ Template::P2wpkh(_) => (None, Some(satisfy(&template, input)?)),
+        template => (Some(satisfy(&template, input)?), None),
+    };
+
+    let final_script_sig = script_sig.map(build_script_sig).transpose()?;
+
+    *input = psbt::Input {
+        non_witness_utxo: input.non_witness_utxo.take(),
+        witness_utxo: input.witness_utxo.take(),
+        final_script_sig,
+        final_script_witness: witness.map(|stack| Witness::from_slice(&stack)),
+        proprietary: std::mem::take(&mut input.proprietary),
+        unknown: std::mem::take(&mut input.unknown),
+        ..Default::default()
+    };
+
+    Ok(())
+}
+
+/// Finalize every input that can be, returning a description of why the others couldn't
+pub fn finalize(psbt: &mut psbt::Psbt) -> Result<(), Vec<String>> {
+    let errors = psbt
+        .inputs
+        .iter_mut()
+        .zip(psbt.unsigned_tx.input.iter())
+        .enumerate()
+        .filter_map(|(index, (input, txin))| {
+            finalize_input(input, txin)
+                .err()
+                .map(|e| format!("input #{}: {}", index, e))
+        })
+        .collect::<Vec<_>>();
+
+    match errors.is_empty() {
+        true => Ok(()),
+        false => Err(errors),
+    }
+}
+
+/// Extract the signed transaction, once every input has been finalized
+pub fn extract(psbt: psbt::Psbt) -> Result<Transaction, String> {
+    if let Some(index) = psbt
+        .inputs
+        .iter()
+        .position(|i| i.final_script_sig.is_none() && i.final_script_witness.is_none())
+    {
+        return Err(format!("input #{} is not finalized", index));
+    }
+
+    psbt.extract_tx().map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::model::{parse_psbt, EXAMPLE_PSBTS};
+
+    use bitcoin::hashes::Hash;
+
+    use super::*;
+
+    #[test]
+    fn test_finalize() {
+        let mut psbt = parse_psbt(EXAMPLE_PSBTS[1].1).unwrap();
+        let original = psbt.clone();
+        assert_eq!(
+            finalize(&mut psbt),
+            Err(vec!["input #0: needs 2 signatures, has 1".to_string()])
+        );
+        assert_eq!(psbt, original);
+        assert_eq!(
+            extract(psbt.clone()),
+            Err("input #0 is not finalized".to_string())
+        );
+
+        // Pretend that the signature is from the second key too
+        let sig = *psbt.inputs[0].partial_sigs.values().next().unwrap();
+        let second_key = *psbt.inputs[0].bip32_derivation.keys().nth(1).unwrap();
+        psbt.inputs[0]
+            .partial_sigs
+            .insert(bitcoin::PublicKey::new(second_key), sig);
+        let witness_script = psbt.inputs[0].witness_script.clone().unwrap();
+        let redeem_script = psbt.inputs[0].redeem_script.clone().unwrap();
+        let psbt_with_both_sigs = psbt.clone();
+        assert_eq!(finalize(&mut psbt), Ok(()));
+
+        let input = &psbt.inputs[0];
+        assert_eq!(
+            input.final_script_sig,
+            Some(build_script_sig(vec![redeem_script.to_bytes()]).unwrap())
+        );
+        let witness = input.final_script_witness.as_ref().unwrap();
+        assert_eq!(witness.len(), 4);
+        assert!(witness.nth(0).unwrap().is_empty());
+        assert_eq!(witness.last(), Some(witness_script.as_bytes()));
+        assert!(input.partial_sigs.is_empty() && input.bip32_derivation.is_empty());
+        assert!(input.witness_utxo.is_some());
+        let tx = extract(psbt.clone()).unwrap();
+        assert_eq!(tx.input[0].witness, *witness);
+
+        // Scripts that don't hash to the spent output are rejected without touching the input
+        let mut wrong = original.clone();
+        wrong.inputs[0].redeem_script = Some(ScriptBuf::new_p2wsh(&WScriptHash::all_zeros()));
+        assert_eq!(
+            finalize(&mut wrong),
+            Err(vec![
+                "input #0: the redeem script doesn't match the spent output".to_string()
+            ])
+        );
+        let mut wrong = psbt_with_both_sigs.clone();
+        wrong.inputs[0].witness_script = Some(ScriptBuf::from(vec![0x51]));
+        let unchanged = wrong.clone();
+        assert_eq!(
+            finalize(&mut wrong),
+            Err(vec![
+                "input #0: the witness script doesn't match the spent output".to_string()
+            ])
+        );
+        assert_eq!(wrong, unchanged);
+
+        // P2WPKH spending the first key
+        let mut psbt = original;
+        let (&key, _) = psbt.inputs[0].partial_sigs.iter().next().unwrap();
+        psbt.inputs[0].witness_utxo.as_mut().unwrap().script_pubkey =
+            ScriptBuf::new_p2wpkh(&key.wpubkey_hash().unwrap());
+        assert_eq!(finalize(&mut psbt), Ok(()));
+        assert_eq!(psbt.inputs[0].final_script_sig, None);
+        assert_eq!(
+            psbt.inputs[0].final_script_witness.as_ref().unwrap().nth(1),
+            Some(&key.to_bytes()[..])
+        );
+    }
+}
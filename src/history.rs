@@ -1,36 +1,125 @@
 use bitcoin::psbt;
 
-use crate::app::*;
+use crate::model::*;
+
+/// Consecutive edits of the same field closer than this are merged into a single entry
+const COALESCE_WINDOW_MS: f64 = 1500.0;
+
+#[derive(Clone, Debug)]
+pub struct HistoryEntry {
+    /// What the user did, which stays the same as the entry is undone and redone
+    pub description: String,
+    action: PsbtMessage,
+
+    // Field edited by this entry and when, used to coalesce consecutive edits
+    field: Option<String>,
+    time: f64,
+}
 
 #[derive(Clone, Debug, Default)]
 pub struct History {
-    items: Vec<PsbtMessage>,
+    items: Vec<HistoryEntry>,
     position: usize,
+
+    transaction: Option<(String, Vec<PsbtMessage>)>,
 }
 
 impl History {
-    pub fn add(&mut self, action: PsbtMessage) {
+    fn push(&mut self, entry: HistoryEntry) {
         self.items.drain(self.position..);
-        self.items.push(action);
+        self.items.push(entry);
         self.position += 1;
     }
 
+    pub fn add(&mut self, action: PsbtMessage, description: String) {
+        if let Some((_, actions)) = &mut self.transaction {
+            actions.push(action);
+            return;
+        }
+
+        self.push(HistoryEntry {
+            description,
+            action,
+            field: None,
+            time: 0.0,
+        });
+    }
+
+    /// Add the edit of `field` made at `now` (in milliseconds), merging it with the previous entry
+    /// if that changed the same field shortly before
+    pub fn add_edit(&mut self, action: PsbtMessage, description: String, field: String, now: f64) {
+        if self.transaction.is_some() {
+            return self.add(action, description);
+        }
+
+        // Never merge with an entry that has been undone
+        let at_end = self.position == self.items.len();
+        match self.items.last_mut() {
+            Some(last)
+                if at_end
+                    && last.field.as_ref() == Some(&field)
+                    && now - last.time < COALESCE_WINDOW_MS =>
+            {
+                // The newest change has to be reverted first
+                let previous = std::mem::replace(&mut last.action, PsbtMessage::None);
+                let mut actions = vec![action];
+                match previous {
+                    PsbtMessage::Batch(previous) => actions.extend(previous),
+                    previous => actions.push(previous),
+                }
+
+                last.action = PsbtMessage::Batch(actions);
+                last.description = description;
+                last.time = now;
+            }
+            _ => self.push(HistoryEntry {
+                description,
+                action,
+                field: Some(field),
+                time: now,
+            }),
+        }
+    }
+
+    /// Start grouping everything added to the history until `commit()` into a single entry
+    pub fn begin(&mut self, description: String) {
+        if self.transaction.is_none() {
+            self.transaction = Some((description, vec![]));
+        }
+    }
+
+    pub fn commit(&mut self) {
+        let (description, mut actions) = match self.transaction.take() {
+            Some(transaction) => transaction,
+            None => return,
+        };
+
+        // Undo in the opposite order
+        actions.reverse();
+        let action = match actions.len() {
+            0 => return,
+            1 => actions.pop().unwrap(),
+            _ => PsbtMessage::Batch(actions),
+        };
+        self.add(action, description);
+    }
+
     pub fn undo(&mut self, psbt: &mut Option<psbt::Psbt>) -> bool {
         let prev_position = match self.position {
             0 => return false,
             x => x - 1,
         };
 
-        let action = &self.items[prev_position];
-        self.items[prev_position] = action.clone().apply_to(psbt);
+        let entry = &mut self.items[prev_position];
+        entry.action = entry.action.clone().apply_to(psbt);
         self.position -= 1;
 
         true
     }
 
     pub fn redo(&mut self, psbt: &mut Option<psbt::Psbt>) -> bool {
-        if let Some(action) = self.items.get(self.position) {
-            self.items[self.position] = action.clone().apply_to(psbt);
+        if let Some(entry) = self.items.get_mut(self.position) {
+            entry.action = entry.action.clone().apply_to(psbt);
             self.position += 1;
 
             true
@@ -38,4 +127,292 @@ impl History {
             false
         }
     }
+
+    /// Undo or redo until `position` entries are applied
+    pub fn jump_to(&mut self, position: usize, psbt: &mut Option<psbt::Psbt>) -> bool {
+        let mut changed = false;
+        while self.position > position {
+            changed |= self.undo(psbt);
+        }
+        while self.position < position.min(self.items.len()) {
+            changed |= self.redo(psbt);
+        }
+
+        changed
+    }
+
+    pub fn entries(&self) -> &[HistoryEntry] {
+        &self.items
+    }
+
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    pub fn to_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "items": self.items.iter().map(|entry| serde_json::json!({
+                "description": entry.description,
+                "action": entry.action.to_json(),
+            })).collect::<Vec<_>>(),
+            "position": self.position,
+        })
+    }
+
+    pub fn from_json(json: &serde_json::Value) -> Option<Self> {
+        let items = json
+            .get("items")?
+            .as_array()?
+            .iter()
+            .map(|item| {
+                Some(HistoryEntry {
+                    description: item.get("description")?.as_str()?.to_string(),
+                    action: PsbtMessage::from_json(item.get("action")?)?,
+                    field: None,
+                    time: 0.0,
+                })
+            })
+            .collect::<Option<Vec<_>>>()?;
+        let position = json.get("position")?.as_u64()? as usize;
+
+        match position <= items.len() {
+            true => Some(History {
+                items,
+                position,
+                transaction: None,
+            }),
+            false => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bitcoin::sighash::EcdsaSighashType;
+    use bitcoin::{ScriptBuf, Witness};
+
+    use super::*;
+
+    #[test]
+    fn test_history_json_roundtrip() {
+        let original = parse_psbt(EXAMPLE_PSBTS[1].1).unwrap();
+        let mut psbt = Some(original.clone());
+        let mut history = History::default();
+
+        let input = &original.inputs[0];
+        let (pubkey, sig) = input.partial_sigs.iter().next().unwrap();
+        let (derivation_key, key_source) = input.bip32_derivation.iter().next().unwrap();
+        let messages = vec![
+            PsbtMessage::ChangeInput(
+                0,
+                PsbtInputMsg::ChangeSigHash(Some(EcdsaSighashType::None.into())),
+            ),
+            PsbtMessage::ChangeInput(0, PsbtInputMsg::ChangeWitnessUtxo(WitnessUtxo(None))),
+            PsbtMessage::ChangeInput(
+                0,
+                PsbtInputMsg::ChangeNonWitnessUtxo(NonWitnessUtxo(Some(
+                    original.unsigned_tx.clone(),
+                ))),
+            ),
+            PsbtMessage::ChangeInput(0, PsbtInputMsg::ChangeRedeemScript(RedeemScript(None))),
+            PsbtMessage::ChangeInput(
+                0,
+                PsbtInputMsg::ChangeWitnessScript(WitnessScript(Some(ScriptBuf::new()))),
+            ),
+            PsbtMessage::ChangeInput(
+                0,
+                PsbtInputMsg::ChangeFinalScript(FinalScript(input.redeem_script.clone())),
+            ),
+            PsbtMessage::ChangeInput(
+                0,
+                PsbtInputMsg::ChangeFinalWitness(FinalWitness(Some(Witness::from_slice(&[vec![
+                    1u8,
+                ]])))),
+            ),
+            PsbtMessage::ChangeInput(
+                0,
+                PsbtInputMsg::ChangePartialSigs(MapUpdate::Remove((*pubkey).into())),
+            ),
+            PsbtMessage::ChangeInput(
+                0,
+                PsbtInputMsg::ChangeBIP32Derivation(MapUpdate::Remove((*derivation_key).into())),
+            ),
+            PsbtMessage::ChangeOutput(
+                0,
+                PsbtOutputMsg::ChangeRedeemScript(RedeemScript(input.witness_script.clone())),
+            ),
+            PsbtMessage::ChangeOutput(
+                0,
+                PsbtOutputMsg::ChangeWitnessScript(WitnessScript(input.witness_script.clone())),
+            ),
+            PsbtMessage::ChangeOutput(
+                0,
+                PsbtOutputMsg::ChangeBIP32Derivation(MapUpdate::Set(
+                    (*derivation_key).into(),
+                    key_source.clone(),
+                )),
+            ),
+            PsbtMessage::ChangeInput(
+                0,
+                PsbtInputMsg::ChangePartialSigs(MapUpdate::Set((*pubkey).into(), (*sig).into())),
+            ),
+            PsbtMessage::ChangePsbt(None),
+        ];
+        for msg in messages {
+            let json = msg.to_json();
+            assert_eq!(PsbtMessage::from_json(&json).unwrap().to_json(), json);
+
+            let description = msg.describe();
+            let opposite = msg.apply_to(&mut psbt);
+            history.add(opposite, description);
+        }
+
+        let mut history = History::from_json(&history.to_json()).unwrap();
+        while history.undo(&mut psbt) {}
+        assert_eq!(psbt, Some(original));
+    }
+
+    #[test]
+    fn test_history_jump_to() {
+        let original = parse_psbt(EXAMPLE_PSBTS[1].1).unwrap();
+        let mut psbt = None;
+        let mut history = History::default();
+
+        let messages = vec![
+            PsbtMessage::Labelled(
+                "Loaded example".to_string(),
+                Box::new(PsbtMessage::ChangePsbt(Some(original.clone()))),
+            ),
+            PsbtMessage::ChangeInput(0, PsbtInputMsg::ChangeWitnessScript(WitnessScript(None))),
+            PsbtMessage::ChangeOutput(
+                0,
+                PsbtOutputMsg::ChangeRedeemScript(RedeemScript(Some(ScriptBuf::new()))),
+            ),
+        ];
+        for msg in messages {
+            let description = msg.describe();
+            let opposite = msg.apply_to(&mut psbt);
+            history.add(opposite, description);
+        }
+        let edited = psbt.clone();
+
+        assert_eq!(
+            history
+                .entries()
+                .iter()
+                .map(|e| e.description.as_str())
+                .collect::<Vec<_>>(),
+            vec![
+                "Loaded example",
+                "Input #0: remove Witness Script",
+                "Output #0: set Redeem Script"
+            ]
+        );
+
+        assert!(history.jump_to(1, &mut psbt));
+        assert_eq!(history.position(), 1);
+        assert_eq!(psbt.as_ref(), Some(&original));
+
+        assert!(history.jump_to(0, &mut psbt));
+        assert_eq!(psbt, None);
+        assert!(!history.jump_to(0, &mut psbt));
+
+        assert!(history.jump_to(3, &mut psbt));
+        assert_eq!(psbt, edited);
+        // Descriptions survive undo and redo, and the JSON roundtrip
+        let history = History::from_json(&history.to_json()).unwrap();
+        assert_eq!(
+            history.entries()[2].description,
+            "Output #0: set Redeem Script"
+        );
+
+        // Every entry must have a description
+        let mut json = history.to_json();
+        json["items"][0]
+            .as_object_mut()
+            .unwrap()
+            .remove("description");
+        assert!(History::from_json(&json).is_none());
+    }
+
+    #[test]
+    fn test_history_coalescing() {
+        let original = parse_psbt(EXAMPLE_PSBTS[1].1).unwrap();
+        let mut psbt = Some(original.clone());
+        let mut history = History::default();
+        let edit = |history: &mut History, psbt: &mut Option<psbt::Psbt>, msg: PsbtMessage, now| {
+            let description = msg.describe();
+            let field = msg.field_key().unwrap();
+            let opposite = msg.apply_to(psbt);
+            history.add_edit(opposite, description, field, now);
+        };
+
+        // What `MapField` sends when a key is changed
+        let (pubkey, sig) = original.inputs[0].partial_sigs.iter().next().unwrap();
+        let new_pubkey = original.inputs[0].bip32_derivation.keys().nth(1).unwrap();
+        let new_pubkey = bitcoin::PublicKey::new(*new_pubkey);
+        edit(
+            &mut history,
+            &mut psbt,
+            PsbtMessage::ChangeInput(
+                0,
+                PsbtInputMsg::ChangePartialSigs(MapUpdate::Remove((*pubkey).into())),
+            ),
+            1000.0,
+        );
+        edit(
+            &mut history,
+            &mut psbt,
+            PsbtMessage::ChangeInput(
+                0,
+                PsbtInputMsg::ChangePartialSigs(MapUpdate::Set(new_pubkey.into(), (*sig).into())),
+            ),
+            1000.0,
+        );
+        assert_eq!(history.entries().len(), 1);
+
+        // Typing in the same field, first within the window and then after a pause
+        for (script, now) in [
+            (vec![0x51], 1500.0),
+            (vec![0x52], 2000.0),
+            (vec![0x53], 9000.0),
+        ] {
+            edit(
+                &mut history,
+                &mut psbt,
+                PsbtMessage::ChangeInput(
+                    0,
+                    PsbtInputMsg::ChangeWitnessScript(WitnessScript(Some(ScriptBuf::from(script)))),
+                ),
+                now,
+            );
+        }
+        assert_eq!(history.entries().len(), 3);
+        let edited = psbt.clone();
+
+        // A transaction is a single entry no matter how many messages it contains
+        history.begin("Strip scripts".to_string());
+        for msg in [
+            PsbtMessage::ChangeInput(0, PsbtInputMsg::ChangeWitnessScript(WitnessScript(None))),
+            PsbtMessage::ChangeInput(0, PsbtInputMsg::ChangeRedeemScript(RedeemScript(None))),
+        ] {
+            let opposite = msg.apply_to(&mut psbt);
+            history.add(opposite, String::new());
+        }
+        history.commit();
+        assert_eq!(history.entries().len(), 4);
+        assert_eq!(history.entries()[3].description, "Strip scripts");
+        assert_eq!(psbt.as_ref().unwrap().inputs[0].redeem_script, None);
+
+        let mut history = History::from_json(&history.to_json()).unwrap();
+        assert!(history.undo(&mut psbt));
+        assert_eq!(psbt, edited);
+        while history.undo(&mut psbt) {}
+        assert_eq!(psbt, Some(original));
+        history.jump_to(4, &mut psbt);
+        assert_eq!(psbt.as_ref().unwrap().inputs[0].witness_script, None);
+        assert!(psbt.as_ref().unwrap().inputs[0]
+            .partial_sigs
+            .contains_key(&new_pubkey));
+    }
 }
@@ -0,0 +1,1179 @@
+use std::collections::BTreeMap;
+use std::convert::TryInto;
+use std::str::FromStr;
+
+use bitcoin::{
+    bip32,
+    ecdsa::Signature,
+    psbt::{self, PsbtSighashType},
+    secp256k1, Address, Network, ScriptBuf, Transaction, TxIn, TxOut, Witness,
+};
+
+use crate::model::{Field, ParseError};
+
+/// Name of a sighash type the way Bitcoin Core's `SighashToStr` prints it
+pub fn core_sighash_name(sighash: u32) -> &'static str {
+    match sighash {
+        0x00 => "DEFAULT",
+        0x01 => "ALL",
+        0x02 => "NONE",
+        0x03 => "SINGLE",
+        0x81 => "ALL|ANYONECANPAY",
+        0x82 => "NONE|ANYONECANPAY",
+        0x83 => "SINGLE|ANYONECANPAY",
+        _ => "",
+    }
+}
+
+fn core_script_num(data: &[u8]) -> i64 {
+    let mut result = data
+        .iter()
+        .rev()
+        .fold(0i64, |acc, byte| (acc << 8) | *byte as i64);
+    if data.last().map(|b| b & 0x80 != 0).unwrap_or(false) {
+        result = -(result & !(0x80i64 << (8 * (data.len() - 1))));
+    }
+    result
+}
+
+fn core_opcode_name(op: bitcoin::opcodes::Opcode) -> String {
+    use bitcoin::opcodes::all::*;
+
+    match op {
+        OP_PUSHNUM_NEG1 => "-1".into(),
+        OP_CLTV => "OP_CHECKLOCKTIMEVERIFY".into(),
+        OP_CSV => "OP_CHECKSEQUENCEVERIFY".into(),
+        op if op.to_u8() >= OP_PUSHNUM_1.to_u8() && op.to_u8() <= OP_PUSHNUM_16.to_u8() => {
+            (op.to_u8() - OP_PUSHNUM_1.to_u8() + 1).to_string()
+        }
+        op if op.to_u8() > OP_CHECKSIGADD.to_u8() && op != OP_INVALIDOPCODE => "OP_UNKNOWN".into(),
+        op => op.to_string(),
+    }
+}
+
+/// Disassemble a script like Bitcoin Core's `ScriptToAsmStr`: pushes of up to four bytes are
+/// shown as numbers and, if `sighash_decode` is set, signatures get their sighash type spelled
+/// out
+pub fn core_script_asm(script: &bitcoin::Script, sighash_decode: bool) -> String {
+    use bitcoin::hashes::hex::DisplayHex;
+    use bitcoin::script::Instruction;
+
+    let mut parts = vec![];
+    for instruction in script.instructions() {
+        match instruction {
+            Ok(Instruction::PushBytes(data)) if data.len() <= 4 => {
+                parts.push(core_script_num(data.as_bytes()).to_string())
+            }
+            Ok(Instruction::PushBytes(data)) => {
+                let data = data.as_bytes();
+                match data.split_last() {
+                    Some((sighash, sig))
+                        if sighash_decode && Signature::from_slice(data).is_ok() =>
+                    {
+                        parts.push(format!(
+                            "{}[{}]",
+                            sig.to_lower_hex_string(),
+                            core_sighash_name(*sighash as u32)
+                        ))
+                    }
+                    _ => parts.push(data.to_lower_hex_string()),
+                }
+            }
+            Ok(Instruction::Op(op)) => parts.push(core_opcode_name(op)),
+            Err(_) => {
+                parts.push("[error]".into());
+                break;
+            }
+        }
+    }
+
+    parts.join(" ")
+}
+
+/// Script type the way Bitcoin Core's `GetTxnOutputType` names it
+pub fn core_script_type(script: &bitcoin::Script) -> &'static str {
+    let bytes = script.as_bytes();
+    if script.is_p2pkh() {
+        "pubkeyhash"
+    } else if script.is_p2sh() {
+        "scripthash"
+    } else if script.is_p2wpkh() {
+        "witness_v0_keyhash"
+    } else if script.is_p2wsh() {
+        "witness_v0_scripthash"
+    } else if script.is_p2tr() {
+        "witness_v1_taproot"
+    } else if bytes == [0x51, 0x02, 0x4e, 0x73] {
+        "anchor"
+    } else if script.is_witness_program() {
+        "witness_unknown"
+    } else if script.is_op_return() {
+        "nulldata"
+    } else if script.is_p2pk() {
+        "pubkey"
+    } else if script.is_multisig() {
+        "multisig"
+    } else {
+        "nonstandard"
+    }
+}
+
+/// Checksum of an output descriptor, as defined in BIP 380
+pub fn descriptor_checksum(desc: &str) -> Option<String> {
+    const INPUT_CHARSET: &str = "0123456789()[],'/*abcdefgh@:$%{}IJKLMNOPQRSTUVWXYZ&+-.;<=>?!^_|~ijklmnopqrstuvwxyzABCDEFGH`#\"\\ ";
+    const CHECKSUM_CHARSET: &[u8] = b"qpzry9x8gf2tvdw0s3jn54khce6mua7l";
+
+    fn poly_mod(mut c: u64, val: u64) -> u64 {
+        let c0 = c >> 35;
+        c = ((c & 0x7ffffffff) << 5) ^ val;
+        for (bit, gen) in [
+            0xf5dee51989,
+            0xa9fdca3312,
+            0x1bab10e32d,
+            0x3706b1677a,
+            0x644d626ffd,
+        ]
+        .iter()
+        .enumerate()
+        {
+            if c0 & (1 << bit) != 0 {
+                c ^= gen;
+            }
+        }
+        c
+    }
+
+    let (mut c, mut cls, mut cls_count) = (1, 0, 0);
+    for ch in desc.chars() {
+        let pos = INPUT_CHARSET.find(ch)? as u64;
+        c = poly_mod(c, pos & 31);
+        cls = cls * 3 + (pos >> 5);
+        cls_count += 1;
+        if cls_count == 3 {
+            c = poly_mod(c, cls);
+            cls = 0;
+            cls_count = 0;
+        }
+    }
+    if cls_count > 0 {
+        c = poly_mod(c, cls);
+    }
+    for _ in 0..8 {
+        c = poly_mod(c, 0);
+    }
+    c ^= 1;
+
+    Some(
+        (0..8)
+            .map(|j| CHECKSUM_CHARSET[((c >> (5 * (7 - j))) & 31) as usize] as char)
+            .collect(),
+    )
+}
+
+/// Descriptor inferred by Bitcoin Core for a script it has no other information about
+fn core_infer_descriptor(script: &bitcoin::Script, network: Network) -> String {
+    use bitcoin::hashes::hex::DisplayHex;
+    use bitcoin::script::Instruction;
+
+    let desc = if let Some(pk) = script.p2pk_public_key() {
+        format!("pk({})", pk)
+    } else if script.is_multisig() {
+        let mut pushes = script
+            .instructions()
+            .filter_map(|i| match i {
+                Ok(Instruction::PushBytes(data)) => Some(data.as_bytes().to_lower_hex_string()),
+                Ok(Instruction::Op(op)) => core_opcode_name(op)
+                    .parse::<u8>()
+                    .ok()
+                    .map(|n| n.to_string()),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        pushes.pop();
+        format!("multi({})", pushes.join(","))
+    } else if script.is_p2tr() {
+        format!("rawtr({})", script.as_bytes()[2..].to_lower_hex_string())
+    } else if let Ok(address) = Address::from_script(script, network) {
+        format!("addr({})", address)
+    } else {
+        format!("raw({})", script.to_hex_string())
+    };
+
+    match descriptor_checksum(&desc) {
+        Some(checksum) => format!("{}#{}", desc, checksum),
+        None => desc,
+    }
+}
+
+fn script_pubkey_to_json(script: &bitcoin::Script, network: Network) -> serde_json::Value {
+    let mut json = serde_json::Map::new();
+    json.insert("asm".into(), core_script_asm(script, false).into());
+    json.insert("desc".into(), core_infer_descriptor(script, network).into());
+    json.insert("hex".into(), script.to_hex_string().into());
+    if let Ok(address) = Address::from_script(script, network) {
+        json.insert("address".into(), address.to_string().into());
+    }
+    json.insert("type".into(), core_script_type(script).into());
+
+    json.into()
+}
+
+fn script_to_json(script: &bitcoin::Script, with_type: bool) -> serde_json::Value {
+    let mut json = serde_json::Map::new();
+    json.insert("asm".into(), core_script_asm(script, false).into());
+    json.insert("hex".into(), script.to_hex_string().into());
+    if with_type {
+        json.insert("type".into(), core_script_type(script).into());
+    }
+
+    json.into()
+}
+
+fn witness_to_json(witness: &Witness) -> serde_json::Value {
+    use bitcoin::hashes::hex::DisplayHex;
+
+    witness
+        .iter()
+        .map(|item| item.to_lower_hex_string())
+        .collect::<Vec<_>>()
+        .into()
+}
+
+/// Serialize a transaction like Bitcoin Core's `decoderawtransaction`
+pub fn transaction_to_json(tx: &Transaction, network: Network) -> serde_json::Value {
+    use bitcoin::hashes::hex::DisplayHex;
+    use serde_json::json;
+
+    let vin = tx
+        .input
+        .iter()
+        .map(|input| {
+            let mut json = serde_json::Map::new();
+            if tx.is_coinbase() {
+                json.insert(
+                    "coinbase".into(),
+                    input.script_sig.as_bytes().to_lower_hex_string().into(),
+                );
+            } else {
+                json.insert("txid".into(), input.previous_output.txid.to_string().into());
+                json.insert("vout".into(), input.previous_output.vout.into());
+                json.insert(
+                    "scriptSig".into(),
+                    json!({
+                        "asm": core_script_asm(&input.script_sig, true),
+                        "hex": input.script_sig.to_hex_string(),
+                    }),
+                );
+            }
+            if !input.witness.is_empty() {
+                json.insert("txinwitness".into(), witness_to_json(&input.witness));
+            }
+            json.insert("sequence".into(), input.sequence.0.into());
+
+            json.into()
+        })
+        .collect::<Vec<serde_json::Value>>();
+    let vout = tx
+        .output
+        .iter()
+        .enumerate()
+        .map(|(n, output)| {
+            json!({
+                "value": output.value.to_btc(),
+                "n": n,
+                "scriptPubKey": script_pubkey_to_json(&output.script_pubkey, network),
+            })
+        })
+        .collect::<Vec<_>>();
+
+    json!({
+        "txid": tx.txid().to_string(),
+        "hash": tx.wtxid().to_string(),
+        "version": tx.version.0,
+        "size": tx.total_size(),
+        "vsize": tx.vsize(),
+        "weight": tx.weight().to_wu(),
+        "locktime": tx.lock_time.to_consensus_u32(),
+        "vin": vin,
+        "vout": vout,
+    })
+}
+
+fn key_source_to_json(
+    pubkey: String,
+    (fingerprint, path): &bip32::KeySource,
+) -> serde_json::Map<String, serde_json::Value> {
+    let mut json = serde_json::Map::new();
+    json.insert("pubkey".into(), pubkey.into());
+    json.insert(
+        "master_fingerprint".into(),
+        format!("{:x}", fingerprint).into(),
+    );
+    json.insert("path".into(), path.to_string().into());
+
+    json
+}
+
+fn bip32_derivs_to_json(
+    map: &BTreeMap<secp256k1::PublicKey, bip32::KeySource>,
+) -> serde_json::Value {
+    map.iter()
+        .map(|(k, v)| key_source_to_json(k.to_string(), v).into())
+        .collect::<Vec<serde_json::Value>>()
+        .into()
+}
+
+fn tap_bip32_derivs_to_json(
+    map: &BTreeMap<secp256k1::XOnlyPublicKey, (Vec<bitcoin::TapLeafHash>, bip32::KeySource)>,
+) -> serde_json::Value {
+    map.iter()
+        .map(|(k, (leaf_hashes, key_source))| {
+            let mut json = key_source_to_json(k.to_string(), key_source);
+            json.insert(
+                "leaf_hashes".into(),
+                leaf_hashes
+                    .iter()
+                    .map(|h| h.to_string())
+                    .collect::<Vec<_>>()
+                    .into(),
+            );
+            json.into()
+        })
+        .collect::<Vec<serde_json::Value>>()
+        .into()
+}
+
+fn preimages_to_json<H: AsRef<[u8]>>(map: &BTreeMap<H, Vec<u8>>) -> serde_json::Value {
+    use bitcoin::hashes::hex::DisplayHex;
+
+    map.iter()
+        .map(|(k, v)| {
+            (
+                k.as_ref().to_lower_hex_string(),
+                v.to_lower_hex_string().into(),
+            )
+        })
+        .collect::<serde_json::Map<_, _>>()
+        .into()
+}
+
+fn proprietary_to_json(map: &BTreeMap<psbt::raw::ProprietaryKey, Vec<u8>>) -> serde_json::Value {
+    use bitcoin::hashes::hex::DisplayHex;
+    use serde_json::json;
+
+    map.iter()
+        .map(|(k, v)| {
+            let mut key = vec![k.to_key().type_value];
+            key.extend(&k.to_key().key);
+
+            json!({
+                "identifier": k.prefix.to_lower_hex_string(),
+                "subtype": k.subtype,
+                "key": key.to_lower_hex_string(),
+                "value": v.to_lower_hex_string(),
+            })
+        })
+        .collect::<Vec<_>>()
+        .into()
+}
+
+fn unknown_to_json(map: &BTreeMap<psbt::raw::Key, Vec<u8>>) -> serde_json::Value {
+    use bitcoin::hashes::hex::DisplayHex;
+
+    map.iter()
+        .map(|(k, v)| {
+            let mut key = vec![k.type_value];
+            key.extend(&k.key);
+            (key.to_lower_hex_string(), v.to_lower_hex_string().into())
+        })
+        .collect::<serde_json::Map<_, _>>()
+        .into()
+}
+
+/// Serialize a PSBT to the same JSON structure returned by Bitcoin Core's `decodepsbt`
+pub fn psbt_to_json(psbt: &psbt::Psbt, network: Network) -> serde_json::Value {
+    use bitcoin::hashes::hex::DisplayHex;
+    use serde_json::{json, Map, Value};
+
+    let global_xpubs = psbt
+        .xpub
+        .iter()
+        .map(|(xpub, (fingerprint, path))| {
+            json!({
+                "xpub": xpub.to_string(),
+                "master_fingerprint": format!("{:x}", fingerprint),
+                "path": path.to_string(),
+            })
+        })
+        .collect::<Vec<_>>();
+
+    let inputs = psbt
+        .inputs
+        .iter()
+        .map(|input| {
+            let mut json = Map::new();
+            if let Some(tx) = &input.non_witness_utxo {
+                json.insert("non_witness_utxo".into(), transaction_to_json(tx, network));
+            }
+            if let Some(txout) = &input.witness_utxo {
+                json.insert(
+                    "witness_utxo".into(),
+                    json!({
+                        "amount": txout.value.to_btc(),
+                        "scriptPubKey": script_pubkey_to_json(&txout.script_pubkey, network),
+                    }),
+                );
+            }
+            if !input.partial_sigs.is_empty() {
+                json.insert(
+                    "partial_signatures".into(),
+                    input
+                        .partial_sigs
+                        .iter()
+                        .map(|(k, v)| (k.to_string(), v.serialize().to_lower_hex_string().into()))
+                        .collect::<Map<_, _>>()
+                        .into(),
+                );
+            }
+            if let Some(sighash) = input.sighash_type {
+                json.insert("sighash".into(), core_sighash_name(sighash.to_u32()).into());
+            }
+            if let Some(script) = &input.redeem_script {
+                json.insert("redeem_script".into(), script_to_json(script, true));
+            }
+            if let Some(script) = &input.witness_script {
+                json.insert("witness_script".into(), script_to_json(script, true));
+            }
+            if !input.bip32_derivation.is_empty() {
+                json.insert(
+                    "bip32_derivs".into(),
+                    bip32_derivs_to_json(&input.bip32_derivation),
+                );
+            }
+            if let Some(script) = &input.final_script_sig {
+                json.insert("final_scriptSig".into(), script_to_json(script, false));
+            }
+            if let Some(witness) = &input.final_script_witness {
+                json.insert("final_scriptwitness".into(), witness_to_json(witness));
+            }
+            if !input.ripemd160_preimages.is_empty() {
+                json.insert(
+                    "ripemd160_preimages".into(),
+                    preimages_to_json(&input.ripemd160_preimages),
+                );
+            }
+            if !input.sha256_preimages.is_empty() {
+                json.insert(
+                    "sha256_preimages".into(),
+                    preimages_to_json(&input.sha256_preimages),
+                );
+            }
+            if !input.hash160_preimages.is_empty() {
+                json.insert(
+                    "hash160_preimages".into(),
+                    preimages_to_json(&input.hash160_preimages),
+                );
+            }
+            if !input.hash256_preimages.is_empty() {
+                json.insert(
+                    "hash256_preimages".into(),
+                    preimages_to_json(&input.hash256_preimages),
+                );
+            }
+            if let Some(sig) = &input.tap_key_sig {
+                json.insert(
+                    "taproot_key_path_sig".into(),
+                    sig.to_vec().to_lower_hex_string().into(),
+                );
+            }
+            if !input.tap_script_sigs.is_empty() {
+                json.insert(
+                    "taproot_script_path_sigs".into(),
+                    input
+                        .tap_script_sigs
+                        .iter()
+                        .map(|((pubkey, leaf_hash), sig)| {
+                            json!({
+                                "pubkey": pubkey.to_string(),
+                                "leaf_hash": leaf_hash.to_string(),
+                                "sig": sig.to_vec().to_lower_hex_string(),
+                            })
+                        })
+                        .collect::<Vec<_>>()
+                        .into(),
+                );
+            }
+            if !input.tap_scripts.is_empty() {
+                json.insert(
+                    "taproot_scripts".into(),
+                    input
+                        .tap_scripts
+                        .iter()
+                        .map(|(control_block, (script, leaf_version))| {
+                            json!({
+                                "script": script.to_hex_string(),
+                                "leaf_ver": leaf_version.to_consensus(),
+                                "control_blocks": [control_block.serialize().to_lower_hex_string()],
+                            })
+                        })
+                        .collect::<Vec<_>>()
+                        .into(),
+                );
+            }
+            if !input.tap_key_origins.is_empty() {
+                json.insert(
+                    "taproot_bip32_derivs".into(),
+                    tap_bip32_derivs_to_json(&input.tap_key_origins),
+                );
+            }
+            if let Some(key) = &input.tap_internal_key {
+                json.insert("taproot_internal_key".into(), key.to_string().into());
+            }
+            if let Some(root) = &input.tap_merkle_root {
+                json.insert("taproot_merkle_root".into(), root.to_string().into());
+            }
+            if !input.proprietary.is_empty() {
+                json.insert(
+                    "proprietary".into(),
+                    proprietary_to_json(&input.proprietary),
+                );
+            }
+            if !input.unknown.is_empty() {
+                json.insert("unknown".into(), unknown_to_json(&input.unknown));
+            }
+
+            json.into()
+        })
+        .collect::<Vec<Value>>();
+
+    let outputs = psbt
+        .outputs
+        .iter()
+        .map(|output| {
+            let mut json = Map::new();
+            if let Some(script) = &output.redeem_script {
+                json.insert("redeem_script".into(), script_to_json(script, true));
+            }
+            if let Some(script) = &output.witness_script {
+                json.insert("witness_script".into(), script_to_json(script, true));
+            }
+            if !output.bip32_derivation.is_empty() {
+                json.insert(
+                    "bip32_derivs".into(),
+                    bip32_derivs_to_json(&output.bip32_derivation),
+                );
+            }
+            if let Some(key) = &output.tap_internal_key {
+                json.insert("taproot_internal_key".into(), key.to_string().into());
+            }
+            if let Some(tree) = &output.tap_tree {
+                json.insert(
+                    "taproot_tree".into(),
+                    tree.script_leaves()
+                        .map(|leaf| {
+                            json!({
+                                "depth": leaf.merkle_branch().len(),
+                                "leaf_ver": leaf.version().to_consensus(),
+                                "script": leaf.script().to_hex_string(),
+                            })
+                        })
+                        .collect::<Vec<_>>()
+                        .into(),
+                );
+            }
+            if !output.tap_key_origins.is_empty() {
+                json.insert(
+                    "taproot_bip32_derivs".into(),
+                    tap_bip32_derivs_to_json(&output.tap_key_origins),
+                );
+            }
+            if !output.proprietary.is_empty() {
+                json.insert(
+                    "proprietary".into(),
+                    proprietary_to_json(&output.proprietary),
+                );
+            }
+            if !output.unknown.is_empty() {
+                json.insert("unknown".into(), unknown_to_json(&output.unknown));
+            }
+
+            json.into()
+        })
+        .collect::<Vec<Value>>();
+
+    let mut json = Map::new();
+    json.insert("tx".into(), transaction_to_json(&psbt.unsigned_tx, network));
+    json.insert("global_xpubs".into(), global_xpubs.into());
+    json.insert("psbt_version".into(), psbt.version.into());
+    json.insert("proprietary".into(), proprietary_to_json(&psbt.proprietary));
+    json.insert("unknown".into(), unknown_to_json(&psbt.unknown));
+    json.insert("inputs".into(), inputs.into());
+    json.insert("outputs".into(), outputs.into());
+    if let Ok(fee) = psbt.fee() {
+        json.insert("fee".into(), fee.to_btc().into());
+    }
+
+    json.into()
+}
+
+/// A PSBT rebuilt from the JSON returned by Bitcoin Core's `decodepsbt`
+#[derive(Debug)]
+pub struct JsonImport {
+    pub psbt: psbt::Psbt,
+    /// Path of every field that was present in the JSON but couldn't be rebuilt
+    pub skipped: Vec<String>,
+}
+
+fn bytes_from_json(json: &serde_json::Value) -> Option<Vec<u8>> {
+    use bitcoin::hashes::hex::FromHex;
+
+    Vec::<u8>::from_hex(json.as_str()?).ok()
+}
+
+fn from_str_json<T: FromStr>(json: &serde_json::Value) -> Option<T> {
+    json.as_str()?.parse().ok()
+}
+
+fn script_from_json(json: &serde_json::Value) -> Option<ScriptBuf> {
+    ScriptBuf::from_hex(json.get("hex")?.as_str()?).ok()
+}
+
+fn amount_from_json(json: &serde_json::Value) -> Option<bitcoin::Amount> {
+    let btc = json.as_f64().filter(|v| *v >= 0.0)?;
+    Some(bitcoin::Amount::from_sat(
+        (btc * 100_000_000.0).round() as u64
+    ))
+}
+
+fn witness_from_json(json: &serde_json::Value) -> Option<Witness> {
+    json.as_array()?
+        .iter()
+        .map(bytes_from_json)
+        .collect::<Option<Vec<_>>>()
+        .map(|items| Witness::from_slice(&items))
+}
+
+fn key_source_from_json(json: &serde_json::Value) -> Option<bip32::KeySource> {
+    Some((
+        from_str_json(json.get("master_fingerprint")?)?,
+        from_str_json(json.get("path")?)?,
+    ))
+}
+
+fn bip32_derivs_from_json(
+    json: &serde_json::Value,
+) -> Option<BTreeMap<secp256k1::PublicKey, bip32::KeySource>> {
+    json.as_array()?
+        .iter()
+        .map(|entry| {
+            Some((
+                from_str_json(entry.get("pubkey")?)?,
+                key_source_from_json(entry)?,
+            ))
+        })
+        .collect()
+}
+
+fn tap_bip32_derivs_from_json(
+    json: &serde_json::Value,
+) -> Option<BTreeMap<secp256k1::XOnlyPublicKey, (Vec<bitcoin::TapLeafHash>, bip32::KeySource)>> {
+    json.as_array()?
+        .iter()
+        .map(|entry| {
+            let leaf_hashes = match entry.get("leaf_hashes") {
+                Some(hashes) => hashes
+                    .as_array()?
+                    .iter()
+                    .map(from_str_json)
+                    .collect::<Option<Vec<_>>>()?,
+                None => vec![],
+            };
+            Some((
+                from_str_json(entry.get("pubkey")?)?,
+                (leaf_hashes, key_source_from_json(entry)?),
+            ))
+        })
+        .collect()
+}
+
+fn preimages_from_json<H: bitcoin::hashes::Hash + Ord>(
+    json: &serde_json::Value,
+) -> Option<BTreeMap<H, Vec<u8>>> {
+    use bitcoin::hashes::hex::FromHex;
+
+    json.as_object()?
+        .iter()
+        .map(|(k, v)| {
+            Some((
+                H::from_slice(&Vec::<u8>::from_hex(k).ok()?).ok()?,
+                bytes_from_json(v)?,
+            ))
+        })
+        .collect()
+}
+
+fn proprietary_from_json(
+    json: &serde_json::Value,
+) -> Option<BTreeMap<psbt::raw::ProprietaryKey, Vec<u8>>> {
+    json.as_array()?
+        .iter()
+        .map(|entry| {
+            let key = bytes_from_json(entry.get("key")?)?;
+            let (type_value, key) = key.split_first()?;
+            let key = psbt::raw::Key {
+                type_value: *type_value,
+                key: key.to_vec(),
+            };
+
+            Some((key.try_into().ok()?, bytes_from_json(entry.get("value")?)?))
+        })
+        .collect()
+}
+
+fn unknown_from_json(json: &serde_json::Value) -> Option<BTreeMap<psbt::raw::Key, Vec<u8>>> {
+    use bitcoin::hashes::hex::FromHex;
+
+    json.as_object()?
+        .iter()
+        .map(|(k, v)| {
+            let key = Vec::<u8>::from_hex(k).ok()?;
+            let (type_value, key) = key.split_first()?;
+            let key = psbt::raw::Key {
+                type_value: *type_value,
+                key: key.to_vec(),
+            };
+
+            Some((key, bytes_from_json(v)?))
+        })
+        .collect()
+}
+
+/// Rebuild a transaction from the JSON returned by Bitcoin Core's `decoderawtransaction`
+pub fn transaction_from_json(json: &serde_json::Value) -> Option<Transaction> {
+    use bitcoin::{absolute::LockTime, transaction::Version, OutPoint, Sequence};
+
+    let input = json
+        .get("vin")?
+        .as_array()?
+        .iter()
+        .map(|vin| {
+            let (previous_output, script_sig) = match vin.get("coinbase") {
+                Some(coinbase) => (
+                    OutPoint::null(),
+                    ScriptBuf::from(bytes_from_json(coinbase)?),
+                ),
+                None => (
+                    OutPoint::new(
+                        from_str_json(vin.get("txid")?)?,
+                        vin.get("vout")?.as_u64()?.try_into().ok()?,
+                    ),
+                    script_from_json(vin.get("scriptSig")?)?,
+                ),
+            };
+            let witness = match vin.get("txinwitness") {
+                Some(witness) => witness_from_json(witness)?,
+                None => Witness::new(),
+            };
+
+            Some(TxIn {
+                previous_output,
+                script_sig,
+                sequence: Sequence(vin.get("sequence")?.as_u64()?.try_into().ok()?),
+                witness,
+            })
+        })
+        .collect::<Option<Vec<_>>>()?;
+    let output = json
+        .get("vout")?
+        .as_array()?
+        .iter()
+        .map(|vout| {
+            Some(TxOut {
+                value: amount_from_json(vout.get("value")?)?,
+                script_pubkey: script_from_json(vout.get("scriptPubKey")?)?,
+            })
+        })
+        .collect::<Option<Vec<_>>>()?;
+
+    Some(Transaction {
+        version: Version(json.get("version")?.as_i64()?.try_into().ok()?),
+        lock_time: LockTime::from_consensus(json.get("locktime")?.as_u64()?.try_into().ok()?),
+        input,
+        output,
+    })
+}
+
+fn input_field_from_json(
+    input: &mut psbt::Input,
+    key: &str,
+    json: &serde_json::Value,
+) -> Option<()> {
+    use bitcoin::taproot::{self, ControlBlock, LeafVersion};
+
+    match key {
+        "non_witness_utxo" => input.non_witness_utxo = Some(transaction_from_json(json)?),
+        "witness_utxo" => {
+            input.witness_utxo = Some(TxOut {
+                value: amount_from_json(json.get("amount")?)?,
+                script_pubkey: script_from_json(json.get("scriptPubKey")?)?,
+            })
+        }
+        "partial_signatures" => {
+            input.partial_sigs = json
+                .as_object()?
+                .iter()
+                .map(|(k, v)| {
+                    Some((
+                        k.parse().ok()?,
+                        Signature::from_slice(&bytes_from_json(v)?).ok()?,
+                    ))
+                })
+                .collect::<Option<_>>()?
+        }
+        "sighash" => {
+            let sighash = [0x00, 0x01, 0x02, 0x03, 0x81, 0x82, 0x83]
+                .iter()
+                .find(|v| core_sighash_name(**v) == json.as_str().unwrap_or_default())?;
+            input.sighash_type = Some(PsbtSighashType::from_u32(*sighash));
+        }
+        "redeem_script" => input.redeem_script = Some(script_from_json(json)?),
+        "witness_script" => input.witness_script = Some(script_from_json(json)?),
+        "bip32_derivs" => input.bip32_derivation = bip32_derivs_from_json(json)?,
+        "final_scriptSig" => input.final_script_sig = Some(script_from_json(json)?),
+        "final_scriptwitness" => input.final_script_witness = Some(witness_from_json(json)?),
+        "ripemd160_preimages" => input.ripemd160_preimages = preimages_from_json(json)?,
+        "sha256_preimages" => input.sha256_preimages = preimages_from_json(json)?,
+        "hash160_preimages" => input.hash160_preimages = preimages_from_json(json)?,
+        "hash256_preimages" => input.hash256_preimages = preimages_from_json(json)?,
+        "taproot_key_path_sig" => {
+            input.tap_key_sig = Some(taproot::Signature::from_slice(&bytes_from_json(json)?).ok()?)
+        }
+        "taproot_script_path_sigs" => {
+            input.tap_script_sigs = json
+                .as_array()?
+                .iter()
+                .map(|entry| {
+                    Some((
+                        (
+                            from_str_json(entry.get("pubkey")?)?,
+                            from_str_json(entry.get("leaf_hash")?)?,
+                        ),
+                        taproot::Signature::from_slice(&bytes_from_json(entry.get("sig")?)?)
+                            .ok()?,
+                    ))
+                })
+                .collect::<Option<_>>()?
+        }
+        "taproot_scripts" => {
+            let mut tap_scripts = BTreeMap::new();
+            for entry in json.as_array()? {
+                let script = ScriptBuf::from(bytes_from_json(entry.get("script")?)?);
+                let leaf_version =
+                    LeafVersion::from_consensus(entry.get("leaf_ver")?.as_u64()?.try_into().ok()?)
+                        .ok()?;
+                for control_block in entry.get("control_blocks")?.as_array()? {
+                    tap_scripts.insert(
+                        ControlBlock::decode(&bytes_from_json(control_block)?).ok()?,
+                        (script.clone(), leaf_version),
+                    );
+                }
+            }
+            input.tap_scripts = tap_scripts;
+        }
+        "taproot_bip32_derivs" => input.tap_key_origins = tap_bip32_derivs_from_json(json)?,
+        "taproot_internal_key" => input.tap_internal_key = Some(from_str_json(json)?),
+        "taproot_merkle_root" => input.tap_merkle_root = Some(from_str_json(json)?),
+        "proprietary" => input.proprietary = proprietary_from_json(json)?,
+        "unknown" => input.unknown = unknown_from_json(json)?,
+        _ => return None,
+    }
+
+    Some(())
+}
+
+fn output_field_from_json(
+    output: &mut psbt::Output,
+    key: &str,
+    json: &serde_json::Value,
+) -> Option<()> {
+    use bitcoin::taproot::{LeafVersion, TapTree, TaprootBuilder};
+    use std::convert::TryFrom;
+
+    match key {
+        "redeem_script" => output.redeem_script = Some(script_from_json(json)?),
+        "witness_script" => output.witness_script = Some(script_from_json(json)?),
+        "bip32_derivs" => output.bip32_derivation = bip32_derivs_from_json(json)?,
+        "taproot_internal_key" => output.tap_internal_key = Some(from_str_json(json)?),
+        "taproot_tree" => {
+            let mut builder = TaprootBuilder::new();
+            for leaf in json.as_array()? {
+                builder = builder
+                    .add_leaf_with_ver(
+                        leaf.get("depth")?.as_u64()?.try_into().ok()?,
+                        ScriptBuf::from(bytes_from_json(leaf.get("script")?)?),
+                        LeafVersion::from_consensus(
+                            leaf.get("leaf_ver")?.as_u64()?.try_into().ok()?,
+                        )
+                        .ok()?,
+                    )
+                    .ok()?;
+            }
+            output.tap_tree = Some(TapTree::try_from(builder).ok()?);
+        }
+        "taproot_bip32_derivs" => output.tap_key_origins = tap_bip32_derivs_from_json(json)?,
+        "proprietary" => output.proprietary = proprietary_from_json(json)?,
+        "unknown" => output.unknown = unknown_from_json(json)?,
+        _ => return None,
+    }
+
+    Some(())
+}
+
+/// Rebuild a PSBT from the JSON returned by Bitcoin Core's `decodepsbt`
+///
+/// Only the unsigned transaction is required: every other field that can't be parsed is left
+/// empty and reported in [`JsonImport::skipped`].
+pub fn psbt_from_json(json: &serde_json::Value) -> Result<JsonImport, ParseError> {
+    let tx = json
+        .get("tx")
+        .and_then(transaction_from_json)
+        .ok_or(ParseError::JsonField("tx"))?;
+    let mut psbt = psbt::Psbt::from_unsigned_tx(tx)?;
+    let mut skipped = vec![];
+
+    let parse_global = |psbt: &mut psbt::Psbt, key: &str, json: &serde_json::Value| {
+        match key {
+            "global_xpubs" => {
+                psbt.xpub = json
+                    .as_array()?
+                    .iter()
+                    .map(|entry| {
+                        Some((
+                            bip32::Xpub::bip174_deserialize([entry.get("xpub")?.as_str()?]).ok()?,
+                            key_source_from_json(entry)?,
+                        ))
+                    })
+                    .collect::<Option<_>>()?
+            }
+            "psbt_version" => psbt.version = json.as_u64()?.try_into().ok()?,
+            "proprietary" => psbt.proprietary = proprietary_from_json(json)?,
+            "unknown" => psbt.unknown = unknown_from_json(json)?,
+            // Derived from the other fields
+            "tx" | "inputs" | "outputs" | "fee" => {}
+            _ => return None,
+        }
+
+        Some(())
+    };
+    for (key, value) in json.as_object().into_iter().flatten() {
+        if parse_global(&mut psbt, key, value).is_none() {
+            skipped.push(key.clone());
+        }
+    }
+
+    let inputs = json.get("inputs").and_then(|v| v.as_array());
+    for (index, input) in inputs.into_iter().flatten().enumerate() {
+        let psbt_input = match psbt.inputs.get_mut(index) {
+            Some(psbt_input) => psbt_input,
+            None => {
+                skipped.push(format!("inputs[{}]", index));
+                continue;
+            }
+        };
+        for (key, value) in input.as_object().into_iter().flatten() {
+            if input_field_from_json(psbt_input, key, value).is_none() {
+                skipped.push(format!("inputs[{}].{}", index, key));
+            }
+        }
+    }
+
+    let outputs = json.get("outputs").and_then(|v| v.as_array());
+    for (index, output) in outputs.into_iter().flatten().enumerate() {
+        let psbt_output = match psbt.outputs.get_mut(index) {
+            Some(psbt_output) => psbt_output,
+            None => {
+                skipped.push(format!("outputs[{}]", index));
+                continue;
+            }
+        };
+        for (key, value) in output.as_object().into_iter().flatten() {
+            if output_field_from_json(psbt_output, key, value).is_none() {
+                skipped.push(format!("outputs[{}].{}", index, key));
+            }
+        }
+    }
+
+    Ok(JsonImport { psbt, skipped })
+}
+
+#[cfg(test)]
+mod tests {
+    use bitcoin::sighash::EcdsaSighashType;
+
+    use super::*;
+    use crate::model::*;
+
+    fn example_psbts() -> Vec<psbt::Psbt> {
+        EXAMPLE_PSBTS
+            .iter()
+            .map(|(_, psbt)| parse_psbt(psbt).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_json_roundtrip_examples() {
+        for psbt in example_psbts() {
+            let import = psbt_from_json(&psbt_to_json(&psbt, Network::Testnet)).unwrap();
+            assert!(import.skipped.is_empty(), "{:?}", import.skipped);
+            assert_eq!(import.psbt, psbt);
+        }
+    }
+
+    #[test]
+    fn test_json_roundtrip_all_fields() {
+        use bitcoin::hashes::{hash160, ripemd160, sha256, sha256d, Hash};
+        use bitcoin::taproot::{LeafVersion, TapLeafHash, TapTree, TaprootBuilder};
+        use std::convert::TryFrom;
+
+        let mut psbt = example_psbts().remove(1);
+        let xonly = secp256k1::XOnlyPublicKey::from_str(
+            "b1341ccba7683b6af4f1238cd6e97e7167d569fac47f1e48d47541844355bd46",
+        )
+        .unwrap();
+        let script = ScriptBuf::from_hex(
+            "20b1341ccba7683b6af4f1238cd6e97e7167d569fac47f1e48d47541844355bd46ac",
+        )
+        .unwrap();
+        let leaf_hash = TapLeafHash::from_script(&script, LeafVersion::TapScript);
+        let key_source = psbt.inputs[0]
+            .bip32_derivation
+            .values()
+            .next()
+            .unwrap()
+            .clone();
+        let proprietary = psbt::raw::ProprietaryKey {
+            prefix: b"bip174".to_vec(),
+            subtype: 0x42,
+            key: vec![1, 2, 3],
+        };
+        let unknown = psbt::raw::Key {
+            type_value: 0xF0,
+            key: vec![4, 5, 6],
+        };
+
+        psbt.xpub.insert(
+            bip32::Xpub::from_priv(
+                &secp256k1::Secp256k1::new(),
+                &bip32::Xpriv::new_master(Network::Testnet, &[0x42; 32]).unwrap(),
+            ),
+            key_source.clone(),
+        );
+        psbt.proprietary.insert(proprietary.clone(), vec![0xaa]);
+        psbt.unknown.insert(unknown.clone(), vec![0xbb]);
+
+        let input = &mut psbt.inputs[0];
+        input.sighash_type = Some(EcdsaSighashType::SinglePlusAnyoneCanPay.into());
+        input.final_script_sig =
+            Some(ScriptBuf::from_hex("0014b1341ccba7683b6af4f1238cd6e97e7167d5").unwrap());
+        input.final_script_witness = Some(Witness::from_slice(&[vec![1u8, 2, 3], vec![]]));
+        input
+            .ripemd160_preimages
+            .insert(ripemd160::Hash::hash(b"a"), b"a".to_vec());
+        input
+            .sha256_preimages
+            .insert(sha256::Hash::hash(b"b"), b"b".to_vec());
+        input
+            .hash160_preimages
+            .insert(hash160::Hash::hash(b"c"), b"c".to_vec());
+        input
+            .hash256_preimages
+            .insert(sha256d::Hash::hash(b"d"), b"d".to_vec());
+        input.tap_key_sig = Some(bitcoin::taproot::Signature::from_slice(&[0x01; 64]).unwrap());
+        input.tap_script_sigs.insert(
+            (xonly, leaf_hash),
+            bitcoin::taproot::Signature::from_slice(&[0x02; 65]).unwrap(),
+        );
+        input
+            .tap_key_origins
+            .insert(xonly, (vec![leaf_hash], key_source.clone()));
+        input.tap_internal_key = Some(xonly);
+        input.tap_merkle_root = Some(leaf_hash.into());
+        input.proprietary.insert(proprietary.clone(), vec![0xcc]);
+        input.unknown.insert(unknown.clone(), vec![0xdd]);
+
+        let builder = TaprootBuilder::new()
+            .add_leaf(1, script.clone())
+            .unwrap()
+            .add_leaf(1, ScriptBuf::new_op_return([0u8; 4]))
+            .unwrap();
+        let output = &mut psbt.outputs[0];
+        output.redeem_script = Some(script.clone());
+        output.witness_script = Some(script);
+        output.bip32_derivation = psbt.inputs[0].bip32_derivation.clone();
+        output.tap_internal_key = Some(xonly);
+        output.tap_tree = Some(TapTree::try_from(builder).unwrap());
+        output
+            .tap_key_origins
+            .insert(xonly, (vec![leaf_hash], key_source));
+        output.proprietary.insert(proprietary, vec![0xee]);
+        output.unknown.insert(unknown, vec![0xff]);
+
+        let import = psbt_from_json(&psbt_to_json(&psbt, Network::Testnet)).unwrap();
+        assert!(import.skipped.is_empty(), "{:?}", import.skipped);
+        assert_eq!(import.psbt, psbt);
+    }
+
+    #[test]
+    fn test_json_import_reports_skipped_fields() {
+        let psbt = example_psbts().remove(1);
+        let mut json = psbt_to_json(&psbt, Network::Testnet);
+        json["inputs"][0]["witness_script"]["hex"] = "zz".into();
+        json["outputs"][0]["musig2_participant_pubkeys"] = serde_json::json!([]);
+
+        let import = psbt_from_json(&json).unwrap();
+        assert_eq!(
+            import.skipped,
+            vec![
+                "inputs[0].witness_script".to_string(),
+                "outputs[0].musig2_participant_pubkeys".to_string()
+            ]
+        );
+        assert_eq!(import.psbt.inputs[0].witness_script, None);
+        assert_eq!(
+            import.psbt.inputs[0].redeem_script,
+            psbt.inputs[0].redeem_script
+        );
+    }
+
+    #[test]
+    fn test_json_import_requires_tx() {
+        assert!(matches!(
+            psbt_from_json(&serde_json::json!({ "inputs": [] })),
+            Err(ParseError::JsonField("tx"))
+        ));
+    }
+
+    #[test]
+    fn test_core_script_asm() {
+        let script = ScriptBuf::from_hex(
+            "5121030000000000000000000000000000000000000000000000000000000000000001018151ae",
+        )
+        .unwrap();
+        assert_eq!(
+            core_script_asm(&script, false),
+            "1 030000000000000000000000000000000000000000000000000000000000000001 -1 1 OP_CHECKMULTISIG"
+        );
+        let script = ScriptBuf::from_hex("6a04ffffffff").unwrap();
+        assert_eq!(core_script_asm(&script, false), "OP_RETURN -2147483647");
+    }
+
+    #[test]
+    fn test_descriptor_checksum() {
+        assert_eq!(
+            descriptor_checksum("addr(mkmZxiEcEd8ZqjQWVZuC6so5dFMKEFpN2j)").unwrap(),
+            "02wpgw69"
+        );
+        assert_eq!(descriptor_checksum("raw(deadbeef)").unwrap(), "89f8spxm");
+        assert_eq!(descriptor_checksum("raw(\u{e9})"), None);
+    }
+}
@@ -1,13 +1,35 @@
 #![recursion_limit = "512"]
 
+pub mod api;
 mod app;
+mod bbqr;
 mod bootstrap;
+mod derivation;
+mod diff;
+mod export;
 mod fields;
+pub mod finalize;
 mod history;
+pub mod json;
+mod lint;
+pub mod model;
 mod navbar;
+mod network;
+mod persistence;
+mod qr;
+mod share;
+mod shortcuts;
+mod slip132;
+mod tabs;
+mod template;
+mod units;
+mod upgrade;
+mod ur;
+mod xpub;
 
 use wasm_bindgen::prelude::*;
 
+#[cfg(target_arch = "wasm32")]
 #[global_allocator]
 static ALLOC: wee_alloc::WeeAlloc = wee_alloc::WeeAlloc::INIT;
 
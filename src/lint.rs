@@ -0,0 +1,387 @@
+use bitcoin::{psbt, Amount, Script, ScriptBuf, TxIn, TxOut, WitnessVersion};
+
+use crate::template::Template;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+impl Severity {
+    pub fn class(&self) -> &'static str {
+        match self {
+            Severity::Warning => "warning",
+            Severity::Error => "danger",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Location {
+    Global,
+    Input(usize),
+    Output(usize),
+}
+
+impl std::fmt::Display for Location {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Location::Global => write!(f, "Global"),
+            Location::Input(index) => write!(f, "Input #{}", index),
+            Location::Output(index) => write!(f, "Output #{}", index),
+        }
+    }
+}
+
+/// A likely mistake found in a PSBT
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Finding {
+    pub severity: Severity,
+    pub location: Location,
+    pub message: String,
+}
+
+/// Collect the findings of a single input or output
+struct Findings<'a> {
+    location: Location,
+    findings: &'a mut Vec<Finding>,
+}
+
+impl Findings<'_> {
+    fn warning<S: Into<String>>(&mut self, message: S) {
+        self.push(Severity::Warning, message.into());
+    }
+
+    fn error<S: Into<String>>(&mut self, message: S) {
+        self.push(Severity::Error, message.into());
+    }
+
+    fn push(&mut self, severity: Severity, message: String) {
+        self.findings.push(Finding {
+            severity,
+            location: self.location,
+            message,
+        });
+    }
+}
+
+/// The output spent by an input, taken from either of the UTXO fields
+pub fn spent_output<'a>(input: &'a psbt::Input, txin: &TxIn) -> Option<&'a TxOut> {
+    input.witness_utxo.as_ref().or_else(|| {
+        input
+            .non_witness_utxo
+            .as_ref()?
+            .output
+            .get(txin.previous_output.vout as usize)
+    })
+}
+
+/// Whether spending `script_pubkey` uses the witness, or `None` if that can't be known yet
+fn is_segwit_spend(script_pubkey: &Script, input: &psbt::Input) -> Option<bool> {
+    if script_pubkey.is_witness_program() {
+        Some(true)
+    } else if script_pubkey.is_p2sh() {
+        input
+            .redeem_script
+            .as_ref()
+            .map(|redeem| redeem.is_witness_program())
+    } else {
+        Some(false)
+    }
+}
+
+/// Segwit v0 inputs that only carry the witness_utxo. Signing them is vulnerable to the fee
+/// attack where the amounts are lied about, so hardware wallets require the non_witness_utxo too.
+pub fn missing_non_witness_utxo(psbt: &psbt::Psbt) -> Vec<usize> {
+    psbt.inputs
+        .iter()
+        .enumerate()
+        .filter(|(_, input)| input.non_witness_utxo.is_none())
+        .filter(|(_, input)| {
+            let spent = match &input.witness_utxo {
+                Some(witness_utxo) => &witness_utxo.script_pubkey,
+                None => return false,
+            };
+            let program = match &input.redeem_script {
+                Some(redeem_script) if spent.is_p2sh() => redeem_script,
+                _ => spent,
+            };
+            program.witness_version() == Some(WitnessVersion::V0)
+        })
+        .map(|(index, _)| index)
+        .collect()
+}
+
+fn lint_input(input: &psbt::Input, txin: &TxIn, mut findings: Findings) {
+    if let Some(tx) = &input.non_witness_utxo {
+        if tx.txid() != txin.previous_output.txid {
+            findings.error(format!(
+                "The non_witness_utxo has txid {} but the input spends {}",
+                tx.txid(),
+                txin.previous_output.txid
+            ));
+        }
+
+        match tx.output.get(txin.previous_output.vout as usize) {
+            None => findings.error(format!(
+                "The input spends output #{} but the non_witness_utxo only has {} output(s)",
+                txin.previous_output.vout,
+                tx.output.len()
+            )),
+            Some(txout) => match &input.witness_utxo {
+                Some(witness_utxo) if witness_utxo != txout => findings.error(format!(
+                    "The witness_utxo ({}, {}) disagrees with output #{} of the non_witness_utxo ({}, {})",
+                    witness_utxo.value,
+                    witness_utxo.script_pubkey,
+                    txin.previous_output.vout,
+                    txout.value,
+                    txout.script_pubkey
+                )),
+                _ => {}
+            },
+        }
+    }
+
+    let spent = spent_output(input, txin).map(|txout| &txout.script_pubkey);
+    if let (Some(witness_utxo), Some(false)) = (
+        &input.witness_utxo,
+        spent.and_then(|spent| is_segwit_spend(spent, input)),
+    ) {
+        findings.warning(format!(
+            "The witness_utxo is set but {} is not a segwit output: non-segwit inputs need the non_witness_utxo",
+            witness_utxo.script_pubkey
+        ));
+    }
+
+    if let (Some(redeem_script), Some(spent)) = (&input.redeem_script, spent) {
+        if ScriptBuf::new_p2sh(&redeem_script.script_hash()) != *spent {
+            findings.error("The hash of the redeem_script doesn't match the spent output");
+        }
+    }
+
+    if let Some(witness_script) = &input.witness_script {
+        // With P2SH-P2WSH the witness program is the redeem script
+        let program = match (spent, &input.redeem_script) {
+            (Some(spent), _) if spent.is_p2wsh() => Some(spent),
+            (_, Some(redeem_script)) if redeem_script.is_p2wsh() => Some(redeem_script),
+            _ => None,
+        };
+        match program {
+            Some(program) if ScriptBuf::new_p2wsh(&witness_script.wscript_hash()) != *program => {
+                findings.error("The witness_script doesn't match the P2WSH program")
+            }
+            None if spent.is_some() => {
+                findings.warning("The witness_script is set but the input doesn't spend a P2WSH")
+            }
+            _ => {}
+        }
+    }
+
+    let finalized = input.final_script_sig.is_some() || input.final_script_witness.is_some();
+    let leftovers = [
+        ("partial_sigs", !input.partial_sigs.is_empty()),
+        ("sighash_type", input.sighash_type.is_some()),
+        ("redeem_script", input.redeem_script.is_some()),
+        ("witness_script", input.witness_script.is_some()),
+        ("bip32_derivation", !input.bip32_derivation.is_empty()),
+        ("tap_key_sig", input.tap_key_sig.is_some()),
+        ("tap_script_sigs", !input.tap_script_sigs.is_empty()),
+        ("tap_scripts", !input.tap_scripts.is_empty()),
+        ("tap_key_origins", !input.tap_key_origins.is_empty()),
+        ("tap_internal_key", input.tap_internal_key.is_some()),
+        ("tap_merkle_root", input.tap_merkle_root.is_some()),
+    ]
+    .iter()
+    .filter(|(_, set)| *set)
+    .map(|(name, _)| *name)
+    .collect::<Vec<_>>();
+    if finalized && !leftovers.is_empty() {
+        findings.warning(format!(
+            "The input is finalized but still contains {}",
+            leftovers.join(", ")
+        ));
+    }
+
+    if let Some(sighash_type) = input.sighash_type {
+        if let Ok(expected) = sighash_type.ecdsa_hash_ty() {
+            for (key, sig) in &input.partial_sigs {
+                if sig.hash_ty != expected {
+                    findings.error(format!(
+                        "The signature of {} uses {} but the sighash_type is {}",
+                        key, sig.hash_ty, expected
+                    ));
+                }
+            }
+        }
+        if let (Ok(expected), Some(sig)) = (sighash_type.taproot_hash_ty(), input.tap_key_sig) {
+            if sig.hash_ty != expected {
+                findings.error(format!(
+                    "The tap_key_sig uses {} but the sighash_type is {}",
+                    sig.hash_ty, expected
+                ));
+            }
+        }
+    }
+}
+
+fn lint_output(txout: &TxOut, mut findings: Findings) {
+    // Pay-to-anchor outputs are exempt from the dust rule, they usually have no value at all
+    let exempt = txout.script_pubkey.is_op_return()
+        || Template::classify(&txout.script_pubkey) == Template::Anchor;
+    if !exempt && txout.value < txout.script_pubkey.dust_value() {
+        findings.warning(format!(
+            "The output is dust: {} is below the {} threshold",
+            txout.value,
+            txout.script_pubkey.dust_value()
+        ));
+    }
+}
+
+/// Look for common mistakes in the PSBT
+pub fn lint(psbt: &psbt::Psbt) -> Vec<Finding> {
+    let mut findings = vec![];
+
+    for (index, (input, txin)) in psbt
+        .inputs
+        .iter()
+        .zip(psbt.unsigned_tx.input.iter())
+        .enumerate()
+    {
+        lint_input(
+            input,
+            txin,
+            Findings {
+                location: Location::Input(index),
+                findings: &mut findings,
+            },
+        );
+    }
+
+    // Only when the value of every input is known
+    let input_value = psbt
+        .inputs
+        .iter()
+        .zip(psbt.unsigned_tx.input.iter())
+        .map(|(input, txin)| spent_output(input, txin).map(|txout| txout.value))
+        .sum::<Option<Amount>>();
+    let output_value = psbt
+        .unsigned_tx
+        .output
+        .iter()
+        .map(|txout| txout.value)
+        .sum::<Amount>();
+    if let Some(input_value) = input_value {
+        if input_value < output_value {
+            Findings {
+                location: Location::Global,
+                findings: &mut findings,
+            }
+            .error(format!(
+                "The outputs spend {} but the inputs are only worth {}",
+                output_value, input_value
+            ));
+        }
+    }
+
+    for (index, txout) in psbt.unsigned_tx.output.iter().enumerate() {
+        lint_output(
+            txout,
+            Findings {
+                location: Location::Output(index),
+                findings: &mut findings,
+            },
+        );
+    }
+
+    findings
+}
+
+#[cfg(test)]
+mod tests {
+    use bitcoin::EcdsaSighashType;
+
+    use crate::model::{parse_psbt, EXAMPLE_PSBTS};
+
+    use super::*;
+
+    fn messages(psbt: &psbt::Psbt) -> Vec<(Location, Severity)> {
+        lint(psbt)
+            .into_iter()
+            .map(|f| (f.location, f.severity))
+            .collect()
+    }
+
+    #[test]
+    fn test_lint() {
+        // P2SH-P2WSH 2-of-2 with one signature
+        let mut psbt = parse_psbt(EXAMPLE_PSBTS[1].1).unwrap();
+        assert_eq!(messages(&psbt), vec![]);
+
+        // A 0-value pay-to-anchor output isn't dust
+        psbt.unsigned_tx.output[0] = TxOut {
+            value: Amount::ZERO,
+            script_pubkey: ScriptBuf::from(vec![0x51, 0x02, 0x4e, 0x73]),
+        };
+        assert_eq!(messages(&psbt), vec![]);
+        let mut psbt = parse_psbt(EXAMPLE_PSBTS[1].1).unwrap();
+
+        psbt.inputs[0].sighash_type = Some(EcdsaSighashType::None.into());
+        psbt.inputs[0].witness_script = Some(ScriptBuf::from(vec![0x51]));
+        psbt.inputs[0].final_script_sig = Some(ScriptBuf::new());
+        psbt.unsigned_tx.output[0].value = Amount::from_sat(100);
+        let findings = lint(&psbt);
+        assert!(findings[0].message.contains("witness_script"));
+        assert!(findings[1].message.contains("still contains"));
+        assert!(findings[2].message.contains("SIGHASH_ALL"));
+        assert_eq!(
+            messages(&psbt),
+            vec![
+                (Location::Input(0), Severity::Error),
+                (Location::Input(0), Severity::Warning),
+                (Location::Input(0), Severity::Error),
+                (Location::Output(0), Severity::Warning),
+            ]
+        );
+
+        // A wrong redeem script makes the witness UTXO look like a non-segwit spend
+        let mut psbt = parse_psbt(EXAMPLE_PSBTS[1].1).unwrap();
+        // Only keep the witness UTXO of the input
+        psbt.inputs[0] = psbt::Input {
+            witness_utxo: psbt.inputs[0].witness_utxo.clone(),
+            ..Default::default()
+        };
+        psbt.inputs[0].redeem_script = Some(ScriptBuf::from(vec![0x51]));
+        assert_eq!(
+            messages(&psbt),
+            vec![
+                (Location::Input(0), Severity::Warning),
+                (Location::Input(0), Severity::Error),
+            ]
+        );
+
+        psbt.inputs[0].redeem_script = None;
+        psbt.inputs[0].non_witness_utxo = Some(psbt.unsigned_tx.clone());
+        assert!(lint(&psbt)[0].message.contains("non_witness_utxo has txid"));
+
+        // Make it spend an output that doesn't exist
+        let mut prev_tx = psbt.unsigned_tx.clone();
+        prev_tx.output[0] = psbt.inputs[0].witness_utxo.clone().unwrap();
+        psbt.unsigned_tx.input[0].previous_output = bitcoin::OutPoint::new(prev_tx.txid(), 1);
+        psbt.inputs[0].non_witness_utxo = Some(prev_tx.clone());
+        let findings = lint(&psbt);
+        assert_eq!(findings.len(), 1);
+        assert!(findings[0].message.contains("only has 1 output(s)"));
+
+        psbt.unsigned_tx.input[0].previous_output.vout = 0;
+        assert_eq!(messages(&psbt), vec![]);
+
+        psbt.inputs[0].witness_utxo.as_mut().unwrap().value = Amount::from_sat(1);
+        assert!(lint(&psbt)[0].message.contains("disagrees"));
+
+        psbt.inputs[0].witness_utxo = Some(prev_tx.output[0].clone());
+        psbt.inputs[0].non_witness_utxo = None;
+        psbt.unsigned_tx.output[0].value = Amount::from_btc(2.0).unwrap();
+        assert_eq!(messages(&psbt), vec![(Location::Global, Severity::Error)]);
+    }
+}
@@ -0,0 +1,1303 @@
+use std::convert::TryInto;
+use std::str::FromStr;
+
+use bitcoin::{
+    bip32,
+    ecdsa::Signature,
+    psbt::{self, PsbtSighashType},
+    secp256k1, Network, ScriptBuf, Transaction, TxOut, Witness,
+};
+
+use crate::derivation::HardenedStyle;
+
+pub const EXAMPLE_PSBTS: [(&str, &str); 3] = [
+    ("One P2PKH input, outputs are empty", "cHNidP8BAHUCAAAAASaBcTce3/KF6Tet7qSze3gADAVmy7OtZGQXE8pCFxv2AAAAAAD+////AtPf9QUAAAAAGXapFNDFmQPFusKGh2DpD9UhpGZap2UgiKwA4fUFAAAAABepFDVF5uM7gyxHBQ8k0+65PJwDlIvHh7MuEwAAAQD9pQEBAAAAAAECiaPHHqtNIOA3G7ukzGmPopXJRjr6Ljl/hTPMti+VZ+UBAAAAFxYAFL4Y0VKpsBIDna89p95PUzSe7LmF/////4b4qkOnHf8USIk6UwpyN+9rRgi7st0tAXHmOuxqSJC0AQAAABcWABT+Pp7xp0XpdNkCxDVZQ6vLNL1TU/////8CAMLrCwAAAAAZdqkUhc/xCX/Z4Ai7NK9wnGIZeziXikiIrHL++E4sAAAAF6kUM5cluiHv1irHU6m80GfWx6ajnQWHAkcwRAIgJxK+IuAnDzlPVoMR3HyppolwuAJf3TskAinwf4pfOiQCIAGLONfc0xTnNMkna9b7QPZzMlvEuqFEyADS8vAtsnZcASED0uFWdJQbrUqZY3LLh+GFbTZSYG2YVi/jnF6efkE/IQUCSDBFAiEA0SuFLYXc2WHS9fSrZgZU327tzHlMDDPOXMMJ/7X85Y0CIGczio4OFyXBl/saiK9Z9R5E5CVbIBZ8hoQDHAXR8lkqASECI7cr7vCWXRC+B3jv7NYfysb3mk6haTkzgHNEZPhPKrMAAAAAAAAA"),
+    ("One P2SH-P2WSH input of a 2-of-2, with metadata", "cHNidP8BAFUCAAAAASeaIyOl37UfxF8iD6WLD8E+HjNCeSqF1+Ns1jM7XLw5AAAAAAD/////AaBa6gsAAAAAGXapFP/pwAYQl8w7Y28ssEYPpPxCfStFiKwAAAAAAAEBIJVe6gsAAAAAF6kUY0UgD2jRieGtwN8cTRbqjxTA2+uHIgIDsTQcy6doO2r08SOM1ul+cWfVafrEfx5I1HVBhENVvUZGMEMCIAQktY7/qqaU4VWepck7v9SokGQiQFXN8HC2dxRpRC0HAh9cjrD+plFtYLisszrWTt5g6Hhb+zqpS5m9+GFR25qaAQEEIgAgdx/RitRZZm3Unz1WTj28QvTIR3TjYK2haBao7UiNVoEBBUdSIQOxNBzLp2g7avTxI4zW6X5xZ9Vp+sR/HkjUdUGEQ1W9RiED3lXR4drIBeP4pYwfv5uUwC89uq/hJ/78pJlfJvggg71SriIGA7E0HMunaDtq9PEjjNbpfnFn1Wn6xH8eSNR1QYRDVb1GELSmumcAAACAAAAAgAQAAIAiBgPeVdHh2sgF4/iljB+/m5TALz26r+En/vykmV8m+CCDvRC0prpnAAAAgAAAAIAFAACAAAA="),
+    ("Revault Unvault TX", "cHNidP8BAIkCAAAAAV+HumeWIAtm1c9hvTgUme25aogn3EvF1+vV7KYKKKdYAAAAAAD9////AkANAwAAAAAAIgAgXA0s+qynDjinXOmpJ/Qhuj87xEB7YcLEVdz7OX5B+l8wdQAAAAAAACIAIKj/nBsC9abIRvrVxbaHRVSZNtMZjsOSosgybAbmDAtwAAAAAAABASuIlAMAAAAAACIAIKI1Ly2kCXvsF5kWmgyAGmH2th23XwgbIDHRo7sHndheAQMEAQAAAAEFR1IhAtk/sjHYB5gv7nUSr0k25UlmeCn+7ztrilD5aKBYhOZ/IQI+TfqYOB5AvGLZO2C3OWNepPtB2MXltlovJy9aNEUezFKuIgYCPk36mDgeQLxi2TtgtzljXqT7QdjF5bZaLycvWjRFHswIeMYQoQoAAAAiBgLZP7Ix2AeYL+51Eq9JNuVJZngp/u87a4pQ+WigWITmfwgbQV1zCgAAAAAAAA=="),
+];
+
+pub trait Field<const N: usize>: Clone + Sized + std::fmt::Debug {
+    type DeserializeError: std::fmt::Debug + std::fmt::Display;
+
+    fn bip174_serialize(&self) -> [String; N];
+    fn bip174_deserialize(s: [&str; N]) -> Result<Self, Self::DeserializeError>;
+
+    /// Like `bip174_serialize`, but following the display preferences of the user
+    fn bip174_display(&self, _hardened: HardenedStyle) -> [String; N] {
+        self.bip174_serialize()
+    }
+}
+
+impl<T: Field<N>, const N: usize> Field<N> for Option<T> {
+    type DeserializeError = <T as Field<N>>::DeserializeError;
+
+    fn bip174_serialize(&self) -> [String; N] {
+        match self {
+            None => {
+                let v = vec![String::new(); N];
+                v.try_into().unwrap()
+            }
+            Some(s) => s.bip174_serialize(),
+        }
+    }
+    fn bip174_display(&self, hardened: HardenedStyle) -> [String; N] {
+        match self {
+            None => self.bip174_serialize(),
+            Some(s) => s.bip174_display(hardened),
+        }
+    }
+    fn bip174_deserialize(s: [&str; N]) -> Result<Self, Self::DeserializeError> {
+        if s.iter().any(|s| s.is_empty()) {
+            Ok(None)
+        } else {
+            Ok(Some(T::bip174_deserialize(s)?))
+        }
+    }
+}
+
+impl Field<1> for PsbtSighashType {
+    type DeserializeError = bitcoin::sighash::SighashTypeParseError;
+
+    fn bip174_deserialize(s: [&str; 1]) -> Result<Self, Self::DeserializeError> {
+        PsbtSighashType::from_str(s[0])
+    }
+
+    fn bip174_serialize(&self) -> [String; 1] {
+        [self.to_string()]
+    }
+}
+
+pub fn field_to_json<F: Field<N>, const N: usize>(field: &F) -> serde_json::Value {
+    field.bip174_serialize().to_vec().into()
+}
+
+pub fn field_from_json<F: Field<N>, const N: usize>(json: &serde_json::Value) -> Option<F> {
+    let strings = json
+        .as_array()?
+        .iter()
+        .map(|v| v.as_str())
+        .collect::<Option<Vec<_>>>()?;
+    F::bip174_deserialize(strings.try_into().ok()?).ok()
+}
+
+/// Parse a field from the same strings shown in the editor, or clear it with `remove`, which
+/// takes no strings at all
+pub fn field_from_strings<F: Field<N>, const N: usize>(
+    strings: &[&str],
+    remove: bool,
+) -> Result<F, String> {
+    let strings: [&str; N] = match (remove, strings.len()) {
+        (true, 0) => [""; N],
+        (true, _) => return Err("remove takes no value".to_string()),
+        (false, 0) => return Err("missing the value".to_string()),
+        (false, len) => strings
+            .try_into()
+            .map_err(|_| format!("expected {} value(s), got {}", N, len))?,
+    };
+    F::bip174_deserialize(strings).map_err(|e| e.to_string())
+}
+
+macro_rules! impl_hex_serialize_field {
+    ($type:ty) => {
+        impl Field<1> for $type {
+            type DeserializeError = ParseError;
+
+            fn bip174_deserialize(s: [&str; 1]) -> Result<Self, Self::DeserializeError> {
+                use bitcoin::consensus::encode::deserialize;
+
+                let data = decode_hex(s[0])?;
+                Ok(deserialize(&data)?)
+            }
+
+            fn bip174_serialize(&self) -> [String; 1] {
+                [bitcoin::consensus::encode::serialize_hex(&self)]
+            }
+        }
+    };
+}
+impl_hex_serialize_field!(TxOut);
+impl_hex_serialize_field!(Transaction);
+impl_hex_serialize_field!(Vec<Vec<u8>>);
+impl_hex_serialize_field!(Vec<u8>);
+impl_hex_serialize_field!(Witness);
+
+/// Decode a hex string, pointing at the first invalid character when there is one
+fn decode_hex(s: &str) -> Result<Vec<u8>, ParseError> {
+    use bitcoin::hashes::hex::{FromHex, HexToBytesError};
+
+    Vec::<u8>::from_hex(s).map_err(|e| {
+        if looks_like_base64(s) {
+            return ParseError::Hex(HexError::Base64);
+        }
+        match (e, s.char_indices().find(|(_, c)| !c.is_ascii_hexdigit())) {
+            (HexToBytesError::InvalidChar(_), Some((offset, c))) => {
+                ParseError::Hex(HexError::InvalidChar { c, offset })
+            }
+            (e, _) => e.into(),
+        }
+    })
+}
+
+/// Whether a string that failed to decode as hex is valid base64 instead. Short strings or
+/// strings with a single case are more likely to be hex with a typo.
+fn looks_like_base64(s: &str) -> bool {
+    let data = s.trim_end_matches('=');
+    let mixed_case = data.chars().any(|c| c.is_ascii_uppercase())
+        && data.chars().any(|c| c.is_ascii_lowercase());
+    s.len() >= 8
+        && s.len().is_multiple_of(4)
+        && s.len() - data.len() <= 2
+        && (mixed_case || data.len() < s.len() || data.contains(['+', '/']))
+        && data
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '+' || c == '/')
+}
+
+/// Catch the most common mistakes before secp256k1 rejects a key with a generic error
+fn check_key_length(data: &[u8]) -> Result<(), ParseError> {
+    match data.len() {
+        33 | 65 => Ok(()),
+        len => Err(ParseError::KeyLength(len)),
+    }
+}
+
+impl Field<1> for bitcoin::PublicKey {
+    type DeserializeError = ParseError;
+
+    fn bip174_deserialize(s: [&str; 1]) -> Result<Self, Self::DeserializeError> {
+        let data = decode_hex(s[0])?;
+        check_key_length(&data)?;
+        Ok(bitcoin::PublicKey::from_slice(&data)?)
+    }
+
+    fn bip174_serialize(&self) -> [String; 1] {
+        [self.to_string()]
+    }
+}
+
+impl Field<1> for secp256k1::PublicKey {
+    type DeserializeError = ParseError;
+
+    fn bip174_deserialize(s: [&str; 1]) -> Result<Self, Self::DeserializeError> {
+        let data = decode_hex(s[0])?;
+        check_key_length(&data)?;
+        Ok(secp256k1::PublicKey::from_slice(&data)?)
+    }
+
+    fn bip174_serialize(&self) -> [String; 1] {
+        [format!("{:x}", self)]
+    }
+}
+
+impl Field<1> for ScriptBuf {
+    type DeserializeError = ParseError;
+
+    fn bip174_deserialize(s: [&str; 1]) -> Result<Self, Self::DeserializeError> {
+        Ok(ScriptBuf::from_bytes(decode_hex(s[0])?))
+    }
+
+    fn bip174_serialize(&self) -> [String; 1] {
+        [self.as_script().to_hex_string()]
+    }
+}
+
+impl Field<1> for Signature {
+    type DeserializeError = ParseError;
+
+    fn bip174_deserialize(s: [&str; 1]) -> Result<Self, Self::DeserializeError> {
+        let data = decode_hex(s[0])?;
+        Ok(Signature::from_slice(&data)?)
+    }
+
+    fn bip174_serialize(&self) -> [String; 1] {
+        [format!("{:x}", &self.serialize())]
+    }
+}
+
+/// Hex encoding of the `psbt\xff` magic that prefixes every serialized PSBT
+const PSBT_MAGIC_HEX: &str = "70736274ff";
+
+/// Parse a PSBT from its base64 or hex encoding, or build an empty one from a raw unsigned
+/// transaction in hex
+pub fn parse_psbt(s: &str) -> Result<psbt::Psbt, ParseError> {
+    use bitcoin::consensus::encode::deserialize;
+
+    let s = s.trim();
+    if !s.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Ok(psbt::Psbt::from_str(s)?);
+    }
+
+    let data = decode_hex(s)?;
+    if s.len() >= PSBT_MAGIC_HEX.len()
+        && s[..PSBT_MAGIC_HEX.len()].eq_ignore_ascii_case(PSBT_MAGIC_HEX)
+    {
+        Ok(psbt::Psbt::deserialize(&data)?)
+    } else {
+        Ok(psbt::Psbt::from_unsigned_tx(deserialize(&data)?)?)
+    }
+}
+
+/// Parse a network either by the name used by Bitcoin Core's `-chain` (`main`, `test`, ...) or by
+/// its full name (`bitcoin`, `testnet`, ...)
+pub fn parse_network(s: &str) -> Result<Network, String> {
+    Network::from_core_arg(s)
+        .or_else(|_| Network::from_str(s))
+        .map_err(|_| format!("unknown network \"{}\"", s))
+}
+
+/// Parse a PSBT from the content of a file, which can either be in binary form or contain any of
+/// the textual encodings accepted by [`parse_psbt`]
+pub fn parse_psbt_bytes(data: &[u8]) -> Result<psbt::Psbt, ParseError> {
+    match std::str::from_utf8(data) {
+        Ok(s) if !data.starts_with(b"psbt\xff") => parse_psbt(s),
+        _ => Ok(psbt::Psbt::deserialize(data)?),
+    }
+}
+
+impl Field<1> for psbt::Psbt {
+    type DeserializeError = ParseError;
+
+    fn bip174_deserialize(s: [&str; 1]) -> Result<Self, Self::DeserializeError> {
+        parse_psbt(s[0])
+    }
+
+    fn bip174_serialize(&self) -> [String; 1] {
+        [self.to_string()]
+    }
+}
+
+/// Accepts the SLIP-132 variants (ypub, zpub, ...) too, but always shows the plain xpub or tpub.
+/// The key keeps the network of its version bytes rather than the selected one: it's what ends up
+/// in the PSBT and what the network is inferred from. The converter panel normalizes for the
+/// selected network instead
+impl Field<1> for bip32::Xpub {
+    type DeserializeError = ParseError;
+
+    fn bip174_deserialize(s: [&str; 1]) -> Result<Self, Self::DeserializeError> {
+        Ok(crate::slip132::decode_extended_key(s[0].trim())?.0)
+    }
+
+    fn bip174_serialize(&self) -> [String; 1] {
+        [self.to_string()]
+    }
+}
+
+impl Field<2> for bitcoin::bip32::KeySource {
+    type DeserializeError = ParseError;
+
+    fn bip174_deserialize(s: [&str; 2]) -> Result<Self, Self::DeserializeError> {
+        use bitcoin::hashes::hex::FromHex;
+
+        let fingerprint = FromHex::from_hex(s[0])?;
+        let path = crate::derivation::parse_path(s[1])?;
+
+        Ok((fingerprint, path))
+    }
+
+    fn bip174_serialize(&self) -> [String; 2] {
+        [format!("{:x}", self.0), self.1.to_string()]
+    }
+
+    fn bip174_display(&self, hardened: HardenedStyle) -> [String; 2] {
+        [
+            format!("{:x}", self.0),
+            crate::derivation::format_path(&self.1, hardened),
+        ]
+    }
+}
+
+macro_rules! declare_ty_wrapper {
+    ($name:ident, $wrap:ty $(, with_ord $tt:tt)?) => {
+        #[derive(Debug, Clone, PartialEq, Eq $(, PartialOrd, Ord $tt )? )]
+        pub struct $name(pub $wrap);
+
+        impl std::ops::Deref for $name {
+            type Target = $wrap;
+
+            fn deref(&self) -> &Self::Target {
+                &self.0
+            }
+        }
+        impl From<$wrap> for $name {
+            fn from(t: $wrap) -> Self {
+                $name(t)
+            }
+        }
+        impl From<$name> for $wrap {
+            fn from(t: $name) -> Self {
+                t.0
+            }
+        }
+
+        impl Field<1> for $name {
+            type DeserializeError = ParseError;
+
+            fn bip174_serialize(&self) -> [String; 1] {
+                self.0.bip174_serialize()
+            }
+            fn bip174_deserialize(s: [&str; 1]) -> Result<Self, Self::DeserializeError> {
+                Ok(<$wrap>::bip174_deserialize(s)?.into())
+            }
+        }
+    };
+}
+
+declare_ty_wrapper!(WitnessUtxo, Option<TxOut>);
+declare_ty_wrapper!(NonWitnessUtxo, Option<Transaction>);
+declare_ty_wrapper!(SecpPublicKeyWrapper, secp256k1::PublicKey, with_ord,);
+declare_ty_wrapper!(PublicKeyWrapper, bitcoin::PublicKey, with_ord,);
+declare_ty_wrapper!(BytesWrapper, Vec<u8>);
+declare_ty_wrapper!(RedeemScript, Option<ScriptBuf>);
+declare_ty_wrapper!(WitnessScript, Option<ScriptBuf>);
+declare_ty_wrapper!(FinalScript, Option<ScriptBuf>);
+declare_ty_wrapper!(FinalWitness, Option<Witness>);
+declare_ty_wrapper!(FinalSignature, Signature);
+
+#[derive(Clone, Debug)]
+pub enum MapUpdate<K, V> {
+    Set(K, V),
+    Remove(K),
+}
+
+impl<K, V> MapUpdate<K, V> {
+    pub fn to_json<const KN: usize, const VN: usize>(&self) -> serde_json::Value
+    where
+        K: Field<KN>,
+        V: Field<VN>,
+    {
+        match self {
+            MapUpdate::Set(k, v) => {
+                serde_json::json!({ "key": field_to_json(k), "value": field_to_json(v) })
+            }
+            MapUpdate::Remove(k) => serde_json::json!({ "key": field_to_json(k) }),
+        }
+    }
+
+    pub fn from_json<const KN: usize, const VN: usize>(json: &serde_json::Value) -> Option<Self>
+    where
+        K: Field<KN>,
+        V: Field<VN>,
+    {
+        let key = field_from_json(json.get("key")?)?;
+        match json.get("value") {
+            Some(value) => Some(MapUpdate::Set(key, field_from_json(value)?)),
+            None => Some(MapUpdate::Remove(key)),
+        }
+    }
+
+    /// The key followed by the value sets an entry, with `remove` the key alone removes it
+    pub fn from_strings<const KN: usize, const VN: usize>(
+        strings: &[&str],
+        remove: bool,
+    ) -> Result<Self, String>
+    where
+        K: Field<KN>,
+        V: Field<VN>,
+    {
+        if strings.len() < KN {
+            return Err("missing the key".to_string());
+        }
+
+        let key = field_from_strings(&strings[..KN], false)?;
+        match &strings[KN..] {
+            [] if remove => Ok(MapUpdate::Remove(key)),
+            _ if remove => Err("remove takes only the key".to_string()),
+            value => Ok(MapUpdate::Set(key, field_from_strings(value, false)?)),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum PsbtMessage {
+    ChangePsbt(Option<psbt::Psbt>),
+    ChangeInput(usize, PsbtInputMsg),
+    ChangeOutput(usize, PsbtOutputMsg),
+    /// Apply the inner message, using the label as its description in the history
+    Labelled(String, Box<PsbtMessage>),
+    /// Apply all the messages in order, as a single change
+    Batch(Vec<PsbtMessage>),
+
+    Undo,
+    Redo,
+    JumpTo(usize),
+    CopyLink,
+
+    None,
+}
+
+impl PsbtMessage {
+    pub fn apply_to(self, psbt: &mut Option<psbt::Psbt>) -> PsbtMessage {
+        match self {
+            PsbtMessage::ChangePsbt(new_psbt) => {
+                let old = psbt.take();
+                *psbt = new_psbt;
+
+                PsbtMessage::ChangePsbt(old)
+            }
+            // The indexes can come from a history restored from the local storage, which may not
+            // match the PSBT anymore
+            PsbtMessage::ChangeInput(index, msg) => psbt
+                .as_mut()
+                .and_then(|psbt| psbt.inputs.get_mut(index))
+                .map(|input| PsbtMessage::ChangeInput(index, msg.apply_to(input)))
+                .unwrap_or(PsbtMessage::None),
+            PsbtMessage::ChangeOutput(index, msg) => psbt
+                .as_mut()
+                .and_then(|psbt| psbt.outputs.get_mut(index))
+                .map(|output| PsbtMessage::ChangeOutput(index, msg.apply_to(output)))
+                .unwrap_or(PsbtMessage::None),
+            PsbtMessage::Labelled(_, msg) => msg.apply_to(psbt),
+            PsbtMessage::Batch(msgs) => {
+                let mut opposites = msgs
+                    .into_iter()
+                    .map(|msg| msg.apply_to(psbt))
+                    .collect::<Vec<_>>();
+                opposites.reverse();
+
+                PsbtMessage::Batch(opposites)
+            }
+            _ => PsbtMessage::None,
+        }
+    }
+
+    /// Human readable description of the edit, shown in the history panel
+    pub fn describe(&self) -> String {
+        match self {
+            PsbtMessage::ChangePsbt(Some(_)) => "Set PSBT".to_string(),
+            PsbtMessage::ChangePsbt(None) => "Clear PSBT".to_string(),
+            PsbtMessage::ChangeInput(index, msg) => format!("Input #{}: {}", index, msg.describe()),
+            PsbtMessage::ChangeOutput(index, msg) => {
+                format!("Output #{}: {}", index, msg.describe())
+            }
+            PsbtMessage::Labelled(label, _) => label.clone(),
+            PsbtMessage::Batch(msgs) => {
+                let mut descriptions = msgs.iter().map(Self::describe).collect::<Vec<_>>();
+                descriptions.dedup();
+                descriptions.join(", ")
+            }
+            _ => String::new(),
+        }
+    }
+
+    /// The field changed by this message, edits of the same field close in time are merged in
+    /// the history
+    pub fn field_key(&self) -> Option<String> {
+        match self {
+            PsbtMessage::ChangePsbt(_) => Some("psbt".to_string()),
+            PsbtMessage::ChangeInput(index, msg) => {
+                Some(format!("input/{}/{}", index, msg.field_name()))
+            }
+            PsbtMessage::ChangeOutput(index, msg) => {
+                Some(format!("output/{}/{}", index, msg.field_name()))
+            }
+            _ => None,
+        }
+    }
+
+    pub fn to_json(&self) -> serde_json::Value {
+        use serde_json::json;
+
+        match self {
+            PsbtMessage::ChangePsbt(psbt) => json!({ "psbt": field_to_json(psbt) }),
+            PsbtMessage::Labelled(_, msg) => msg.to_json(),
+            PsbtMessage::Batch(msgs) => {
+                json!({ "batch": msgs.iter().map(Self::to_json).collect::<Vec<_>>() })
+            }
+            PsbtMessage::ChangeInput(index, msg) => {
+                json!({ "input": index, "change": msg.to_json() })
+            }
+            PsbtMessage::ChangeOutput(index, msg) => {
+                json!({ "output": index, "change": msg.to_json() })
+            }
+            _ => serde_json::Value::Null,
+        }
+    }
+
+    pub fn from_json(json: &serde_json::Value) -> Option<Self> {
+        if json.is_null() {
+            return Some(PsbtMessage::None);
+        }
+
+        let index = |key| json.get(key)?.as_u64().map(|i| i as usize);
+        if let Some(batch) = json.get("batch") {
+            Some(PsbtMessage::Batch(
+                batch
+                    .as_array()?
+                    .iter()
+                    .map(Self::from_json)
+                    .collect::<Option<_>>()?,
+            ))
+        } else if let Some(psbt) = json.get("psbt") {
+            Some(PsbtMessage::ChangePsbt(field_from_json(psbt)?))
+        } else if let Some(index) = index("input") {
+            Some(PsbtMessage::ChangeInput(
+                index,
+                PsbtInputMsg::from_json(json.get("change")?)?,
+            ))
+        } else {
+            Some(PsbtMessage::ChangeOutput(
+                index("output")?,
+                PsbtOutputMsg::from_json(json.get("change")?)?,
+            ))
+        }
+    }
+
+    /// Build the edit of a field of `psbt` from its string representation, checking that the
+    /// input or output exists. With `remove` the field is cleared, or the key in `strings` is
+    /// removed from a map, see [`field_from_strings`]
+    pub fn from_strings(
+        psbt: &psbt::Psbt,
+        kind: &str,
+        index: usize,
+        field: &str,
+        strings: &[&str],
+        remove: bool,
+    ) -> Result<Self, String> {
+        let field_error = |e| format!("{} #{} {}: {}", kind, index, field, e);
+        match kind {
+            "input" if index < psbt.inputs.len() => Ok(PsbtMessage::ChangeInput(
+                index,
+                PsbtInputMsg::from_strings(field, strings, remove).map_err(field_error)?,
+            )),
+            "output" if index < psbt.outputs.len() => Ok(PsbtMessage::ChangeOutput(
+                index,
+                PsbtOutputMsg::from_strings(field, strings, remove).map_err(field_error)?,
+            )),
+            "input" | "output" => Err(format!("there is no {} #{}", kind, index)),
+            _ => Err(format!("expected input or output, got \"{}\"", kind)),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+#[allow(clippy::enum_variant_names)]
+pub enum PsbtInputMsg {
+    ChangeSigHash(Option<PsbtSighashType>),
+    ChangeWitnessUtxo(WitnessUtxo),
+    ChangeNonWitnessUtxo(NonWitnessUtxo),
+    ChangeRedeemScript(RedeemScript),
+    ChangeWitnessScript(WitnessScript),
+    ChangeFinalScript(FinalScript),
+    ChangeFinalWitness(FinalWitness),
+    ChangePartialSigs(MapUpdate<PublicKeyWrapper, FinalSignature>),
+    ChangeBIP32Derivation(MapUpdate<SecpPublicKeyWrapper, bip32::KeySource>),
+}
+
+macro_rules! set_and_return {
+    ($field:expr, $val:expr) => {{
+        let old = $field.clone();
+        $field = $val;
+        old.into()
+    }};
+}
+
+impl PsbtInputMsg {
+    pub fn apply_to(self, psbt_input: &mut psbt::Input) -> PsbtInputMsg {
+        match self {
+            PsbtInputMsg::ChangeSigHash(sighash) => {
+                PsbtInputMsg::ChangeSigHash(set_and_return!(psbt_input.sighash_type, sighash))
+            }
+            PsbtInputMsg::ChangeWitnessUtxo(witness_utxo) => PsbtInputMsg::ChangeWitnessUtxo(
+                set_and_return!(psbt_input.witness_utxo, witness_utxo.0),
+            ),
+            PsbtInputMsg::ChangeNonWitnessUtxo(non_witness_utxo) => {
+                PsbtInputMsg::ChangeNonWitnessUtxo(set_and_return!(
+                    psbt_input.non_witness_utxo,
+                    non_witness_utxo.0
+                ))
+            }
+            PsbtInputMsg::ChangeRedeemScript(redeem_script) => PsbtInputMsg::ChangeRedeemScript(
+                set_and_return!(psbt_input.redeem_script, redeem_script.0),
+            ),
+            PsbtInputMsg::ChangeWitnessScript(witness_script) => PsbtInputMsg::ChangeWitnessScript(
+                set_and_return!(psbt_input.witness_script, witness_script.0),
+            ),
+            PsbtInputMsg::ChangeFinalScript(final_script_sig) => PsbtInputMsg::ChangeFinalScript(
+                set_and_return!(psbt_input.final_script_sig, final_script_sig.0),
+            ),
+            PsbtInputMsg::ChangeFinalWitness(final_script_witness) => {
+                PsbtInputMsg::ChangeFinalWitness(set_and_return!(
+                    psbt_input.final_script_witness,
+                    final_script_witness.0
+                ))
+            }
+            PsbtInputMsg::ChangePartialSigs(c) => {
+                let prev = match c {
+                    MapUpdate::Set(k, v) => (
+                        k.clone(),
+                        psbt_input.partial_sigs.insert(k.into(), v.into()),
+                    ),
+                    MapUpdate::Remove(k) => (k.clone(), psbt_input.partial_sigs.remove(&k)),
+                };
+                match prev {
+                    (k, Some(v)) => PsbtInputMsg::ChangePartialSigs(MapUpdate::Set(k, v.into())),
+                    (k, None) => PsbtInputMsg::ChangePartialSigs(MapUpdate::Remove(k)),
+                }
+            }
+            PsbtInputMsg::ChangeBIP32Derivation(c) => {
+                let prev = match c {
+                    MapUpdate::Set(k, v) => {
+                        (k.clone(), psbt_input.bip32_derivation.insert(k.into(), v))
+                    }
+                    MapUpdate::Remove(k) => (k.clone(), psbt_input.bip32_derivation.remove(&k)),
+                };
+                match prev {
+                    (k, Some(v)) => PsbtInputMsg::ChangeBIP32Derivation(MapUpdate::Set(k, v)),
+                    (k, None) => PsbtInputMsg::ChangeBIP32Derivation(MapUpdate::Remove(k)),
+                }
+            }
+        }
+    }
+}
+
+/// "set" or "remove" depending on whether the new value is empty
+fn describe_change(label: &str, set: bool) -> String {
+    match set {
+        true => format!("set {}", label),
+        false => format!("remove {}", label),
+    }
+}
+
+fn is_map_set<K, V>(update: &MapUpdate<K, V>) -> bool {
+    matches!(update, MapUpdate::Set(..))
+}
+
+impl PsbtInputMsg {
+    pub fn describe(&self) -> String {
+        match self {
+            PsbtInputMsg::ChangeSigHash(v) => describe_change("Sighash Type", v.is_some()),
+            PsbtInputMsg::ChangeWitnessUtxo(v) => describe_change("Witness UTXO", v.0.is_some()),
+            PsbtInputMsg::ChangeNonWitnessUtxo(v) => {
+                describe_change("Non Witness UTXO", v.0.is_some())
+            }
+            PsbtInputMsg::ChangeRedeemScript(v) => describe_change("Redeem Script", v.0.is_some()),
+            PsbtInputMsg::ChangeWitnessScript(v) => {
+                describe_change("Witness Script", v.0.is_some())
+            }
+            PsbtInputMsg::ChangeFinalScript(v) => {
+                describe_change("Final Script Sig", v.0.is_some())
+            }
+            PsbtInputMsg::ChangeFinalWitness(v) => {
+                describe_change("Final Script Witness", v.0.is_some())
+            }
+            PsbtInputMsg::ChangePartialSigs(u) => {
+                describe_change("Partial Signature", is_map_set(u))
+            }
+            PsbtInputMsg::ChangeBIP32Derivation(u) => {
+                describe_change("BIP32 Derivation", is_map_set(u))
+            }
+        }
+    }
+
+    pub fn field_name(&self) -> &'static str {
+        match self {
+            PsbtInputMsg::ChangeSigHash(_) => "sighash_type",
+            PsbtInputMsg::ChangeWitnessUtxo(_) => "witness_utxo",
+            PsbtInputMsg::ChangeNonWitnessUtxo(_) => "non_witness_utxo",
+            PsbtInputMsg::ChangeRedeemScript(_) => "redeem_script",
+            PsbtInputMsg::ChangeWitnessScript(_) => "witness_script",
+            PsbtInputMsg::ChangeFinalScript(_) => "final_script_sig",
+            PsbtInputMsg::ChangeFinalWitness(_) => "final_script_witness",
+            PsbtInputMsg::ChangePartialSigs(_) => "partial_sigs",
+            PsbtInputMsg::ChangeBIP32Derivation(_) => "bip32_derivation",
+        }
+    }
+
+    pub fn to_json(&self) -> serde_json::Value {
+        let value = match self {
+            PsbtInputMsg::ChangeSigHash(v) => field_to_json(v),
+            PsbtInputMsg::ChangeWitnessUtxo(v) => field_to_json(v),
+            PsbtInputMsg::ChangeNonWitnessUtxo(v) => field_to_json(v),
+            PsbtInputMsg::ChangeRedeemScript(v) => field_to_json(v),
+            PsbtInputMsg::ChangeWitnessScript(v) => field_to_json(v),
+            PsbtInputMsg::ChangeFinalScript(v) => field_to_json(v),
+            PsbtInputMsg::ChangeFinalWitness(v) => field_to_json(v),
+            PsbtInputMsg::ChangePartialSigs(u) => u.to_json(),
+            PsbtInputMsg::ChangeBIP32Derivation(u) => u.to_json(),
+        };
+
+        serde_json::json!({ "field": self.field_name(), "value": value })
+    }
+
+    pub fn from_json(json: &serde_json::Value) -> Option<Self> {
+        let value = json.get("value")?;
+        Some(match json.get("field")?.as_str()? {
+            "sighash_type" => PsbtInputMsg::ChangeSigHash(field_from_json(value)?),
+            "witness_utxo" => PsbtInputMsg::ChangeWitnessUtxo(field_from_json(value)?),
+            "non_witness_utxo" => PsbtInputMsg::ChangeNonWitnessUtxo(field_from_json(value)?),
+            "redeem_script" => PsbtInputMsg::ChangeRedeemScript(field_from_json(value)?),
+            "witness_script" => PsbtInputMsg::ChangeWitnessScript(field_from_json(value)?),
+            "final_script_sig" => PsbtInputMsg::ChangeFinalScript(field_from_json(value)?),
+            "final_script_witness" => PsbtInputMsg::ChangeFinalWitness(field_from_json(value)?),
+            "partial_sigs" => PsbtInputMsg::ChangePartialSigs(MapUpdate::from_json(value)?),
+            "bip32_derivation" => PsbtInputMsg::ChangeBIP32Derivation(MapUpdate::from_json(value)?),
+            _ => return None,
+        })
+    }
+
+    /// Build the edit of `field` from its string representation, see [`field_from_strings`]
+    pub fn from_strings(field: &str, strings: &[&str], remove: bool) -> Result<Self, String> {
+        Ok(match field {
+            "sighash_type" => PsbtInputMsg::ChangeSigHash(field_from_strings(strings, remove)?),
+            "witness_utxo" => PsbtInputMsg::ChangeWitnessUtxo(field_from_strings(strings, remove)?),
+            "non_witness_utxo" => {
+                PsbtInputMsg::ChangeNonWitnessUtxo(field_from_strings(strings, remove)?)
+            }
+            "redeem_script" => {
+                PsbtInputMsg::ChangeRedeemScript(field_from_strings(strings, remove)?)
+            }
+            "witness_script" => {
+                PsbtInputMsg::ChangeWitnessScript(field_from_strings(strings, remove)?)
+            }
+            "final_script_sig" => {
+                PsbtInputMsg::ChangeFinalScript(field_from_strings(strings, remove)?)
+            }
+            "final_script_witness" => {
+                PsbtInputMsg::ChangeFinalWitness(field_from_strings(strings, remove)?)
+            }
+            "partial_sigs" => {
+                PsbtInputMsg::ChangePartialSigs(MapUpdate::from_strings(strings, remove)?)
+            }
+            "bip32_derivation" => {
+                PsbtInputMsg::ChangeBIP32Derivation(MapUpdate::from_strings(strings, remove)?)
+            }
+            _ => return Err(format!("unknown input field \"{}\"", field)),
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+#[allow(clippy::enum_variant_names)]
+pub enum PsbtOutputMsg {
+    ChangeRedeemScript(RedeemScript),
+    ChangeWitnessScript(WitnessScript),
+    ChangeBIP32Derivation(MapUpdate<SecpPublicKeyWrapper, bip32::KeySource>),
+}
+
+impl PsbtOutputMsg {
+    pub fn apply_to(self, psbt_output: &mut psbt::Output) -> PsbtOutputMsg {
+        match self {
+            PsbtOutputMsg::ChangeRedeemScript(redeem_script) => PsbtOutputMsg::ChangeRedeemScript(
+                set_and_return!(psbt_output.redeem_script, redeem_script.0),
+            ),
+            PsbtOutputMsg::ChangeWitnessScript(witness_script) => {
+                PsbtOutputMsg::ChangeWitnessScript(set_and_return!(
+                    psbt_output.witness_script,
+                    witness_script.0
+                ))
+            }
+            PsbtOutputMsg::ChangeBIP32Derivation(c) => {
+                let prev = match c {
+                    MapUpdate::Set(k, v) => {
+                        (k.clone(), psbt_output.bip32_derivation.insert(k.into(), v))
+                    }
+                    MapUpdate::Remove(k) => (k.clone(), psbt_output.bip32_derivation.remove(&k)),
+                };
+                match prev {
+                    (k, Some(v)) => PsbtOutputMsg::ChangeBIP32Derivation(MapUpdate::Set(k, v)),
+                    (k, None) => PsbtOutputMsg::ChangeBIP32Derivation(MapUpdate::Remove(k)),
+                }
+            }
+        }
+    }
+}
+
+impl PsbtOutputMsg {
+    pub fn describe(&self) -> String {
+        match self {
+            PsbtOutputMsg::ChangeRedeemScript(v) => describe_change("Redeem Script", v.0.is_some()),
+            PsbtOutputMsg::ChangeWitnessScript(v) => {
+                describe_change("Witness Script", v.0.is_some())
+            }
+            PsbtOutputMsg::ChangeBIP32Derivation(u) => {
+                describe_change("BIP32 Derivation", is_map_set(u))
+            }
+        }
+    }
+
+    pub fn field_name(&self) -> &'static str {
+        match self {
+            PsbtOutputMsg::ChangeRedeemScript(_) => "redeem_script",
+            PsbtOutputMsg::ChangeWitnessScript(_) => "witness_script",
+            PsbtOutputMsg::ChangeBIP32Derivation(_) => "bip32_derivation",
+        }
+    }
+
+    pub fn to_json(&self) -> serde_json::Value {
+        let value = match self {
+            PsbtOutputMsg::ChangeRedeemScript(v) => field_to_json(v),
+            PsbtOutputMsg::ChangeWitnessScript(v) => field_to_json(v),
+            PsbtOutputMsg::ChangeBIP32Derivation(u) => u.to_json(),
+        };
+
+        serde_json::json!({ "field": self.field_name(), "value": value })
+    }
+
+    pub fn from_json(json: &serde_json::Value) -> Option<Self> {
+        let value = json.get("value")?;
+        Some(match json.get("field")?.as_str()? {
+            "redeem_script" => PsbtOutputMsg::ChangeRedeemScript(field_from_json(value)?),
+            "witness_script" => PsbtOutputMsg::ChangeWitnessScript(field_from_json(value)?),
+            "bip32_derivation" => {
+                PsbtOutputMsg::ChangeBIP32Derivation(MapUpdate::from_json(value)?)
+            }
+            _ => return None,
+        })
+    }
+
+    /// Build the edit of `field` from its string representation, see [`field_from_strings`]
+    pub fn from_strings(field: &str, strings: &[&str], remove: bool) -> Result<Self, String> {
+        Ok(match field {
+            "redeem_script" => {
+                PsbtOutputMsg::ChangeRedeemScript(field_from_strings(strings, remove)?)
+            }
+            "witness_script" => {
+                PsbtOutputMsg::ChangeWitnessScript(field_from_strings(strings, remove)?)
+            }
+            "bip32_derivation" => {
+                PsbtOutputMsg::ChangeBIP32Derivation(MapUpdate::from_strings(strings, remove)?)
+            }
+            _ => return Err(format!("unknown output field \"{}\"", field)),
+        })
+    }
+}
+
+#[derive(Debug)]
+pub enum ParseError {
+    Hex(HexError),
+    Encode(bitcoin::consensus::encode::Error),
+    Key(bitcoin::key::Error),
+    Secp(secp256k1::Error),
+    BIP32(bitcoin::bip32::Error),
+    Ecdsa(bitcoin::ecdsa::Error),
+    Psbt(psbt::Error),
+    PsbtParse(psbt::PsbtParseError),
+    Json(serde_json::Error),
+    JsonField(&'static str),
+    /// A public key that is neither 33 nor 65 bytes long
+    KeyLength(usize),
+    Base58(bitcoin::base58::Error),
+    /// Extended keys with unknown version bytes
+    ExtendedKeyVersion([u8; 4]),
+    ExtendedPrivateKey,
+    /// Extended keys that aren't 78 bytes long
+    ExtendedKeyLength(usize),
+}
+#[derive(Debug)]
+pub enum HexError {
+    Bytes(bitcoin::hex::HexToBytesError),
+    Array(bitcoin::hex::HexToArrayError),
+    InvalidChar { c: char, offset: usize },
+    Base64,
+}
+impl std::fmt::Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        use bitcoin::hex::DisplayHex;
+
+        match self {
+            ParseError::Hex(e) => write!(f, "{}", e),
+            ParseError::Encode(e) => write!(f, "invalid serialization: {}", e),
+            ParseError::Key(e) => write!(f, "invalid public key: {}", e),
+            ParseError::Secp(e) => write!(f, "invalid key: {}", e),
+            ParseError::BIP32(e) => write!(
+                f,
+                "invalid derivation path: {}, expected something like m/84'/0'/0'/0/0",
+                e
+            ),
+            ParseError::Ecdsa(e) => write!(
+                f,
+                "invalid signature: {}, expected a DER signature followed by the sighash byte",
+                e
+            ),
+            ParseError::Psbt(e) => write!(f, "invalid PSBT: {}", e),
+            ParseError::PsbtParse(e) => write!(
+                f,
+                "invalid PSBT: {}, expected base64, hex or a raw transaction in hex",
+                e
+            ),
+            ParseError::Json(e) => write!(f, "invalid JSON: {}", e),
+            ParseError::JsonField(name) => write!(f, "missing or invalid field \"{}\"", name),
+            ParseError::KeyLength(32) => write!(
+                f,
+                "this is an x-only key, expected 33 bytes with an 02 or 03 prefix"
+            ),
+            ParseError::KeyLength(len) => write!(
+                f,
+                "invalid public key length of {} bytes, expected 33 (compressed) or 65 (uncompressed)",
+                len
+            ),
+            ParseError::Base58(e) => write!(f, "invalid base58: {}", e),
+            ParseError::ExtendedKeyVersion(version) => write!(
+                f,
+                "unknown version bytes {}, expected an xpub, ypub, zpub, tpub or one of their variants",
+                version.to_lower_hex_string()
+            ),
+            ParseError::ExtendedPrivateKey => write!(
+                f,
+                "this is an extended private key, only paste the public one"
+            ),
+            ParseError::ExtendedKeyLength(len) => {
+                write!(f, "invalid extended key length of {} bytes, expected 78", len)
+            }
+        }
+    }
+}
+impl std::fmt::Display for HexError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        use bitcoin::hex::{HexToArrayError, HexToBytesError};
+
+        match self {
+            HexError::Bytes(HexToBytesError::InvalidChar(c))
+            | HexError::Array(HexToArrayError::Conversion(HexToBytesError::InvalidChar(c))) => {
+                write!(f, "invalid hex character '{}'", *c as char)
+            }
+            HexError::Bytes(HexToBytesError::OddLengthString(len))
+            | HexError::Array(HexToArrayError::Conversion(HexToBytesError::OddLengthString(len))) =>
+            {
+                write!(
+                    f,
+                    "odd number of hex digits ({}), every byte takes two",
+                    len
+                )
+            }
+            HexError::Array(HexToArrayError::InvalidLength(got, expected)) => write!(
+                f,
+                "expected {} hex digits ({} bytes), got {}",
+                expected,
+                expected / 2,
+                got
+            ),
+            HexError::InvalidChar { c, offset } => write!(
+                f,
+                "invalid hex character '{}' at position {} (byte {})",
+                c,
+                offset,
+                offset / 2
+            ),
+            HexError::Base64 => write!(f, "this looks like base64, not hex"),
+        }
+    }
+}
+impl From<bitcoin::hashes::hex::HexToArrayError> for ParseError {
+    fn from(e: bitcoin::hashes::hex::HexToArrayError) -> Self {
+        ParseError::Hex(HexError::Array(e))
+    }
+}
+impl From<bitcoin::hashes::hex::HexToBytesError> for ParseError {
+    fn from(e: bitcoin::hashes::hex::HexToBytesError) -> Self {
+        ParseError::Hex(HexError::Bytes(e))
+    }
+}
+impl From<bitcoin::consensus::encode::Error> for ParseError {
+    fn from(e: bitcoin::consensus::encode::Error) -> Self {
+        ParseError::Encode(e)
+    }
+}
+impl From<bitcoin::key::Error> for ParseError {
+    fn from(e: bitcoin::key::Error) -> Self {
+        ParseError::Key(e)
+    }
+}
+impl From<secp256k1::Error> for ParseError {
+    fn from(e: secp256k1::Error) -> Self {
+        ParseError::Secp(e)
+    }
+}
+impl From<bitcoin::base58::Error> for ParseError {
+    fn from(e: bitcoin::base58::Error) -> Self {
+        ParseError::Base58(e)
+    }
+}
+impl From<bitcoin::bip32::Error> for ParseError {
+    fn from(e: bitcoin::bip32::Error) -> Self {
+        ParseError::BIP32(e)
+    }
+}
+impl From<bitcoin::ecdsa::Error> for ParseError {
+    fn from(e: bitcoin::ecdsa::Error) -> Self {
+        ParseError::Ecdsa(e)
+    }
+}
+impl From<psbt::Error> for ParseError {
+    fn from(e: psbt::Error) -> Self {
+        ParseError::Psbt(e)
+    }
+}
+impl From<psbt::PsbtParseError> for ParseError {
+    fn from(e: psbt::PsbtParseError) -> Self {
+        ParseError::PsbtParse(e)
+    }
+}
+impl From<serde_json::Error> for ParseError {
+    fn from(e: serde_json::Error) -> Self {
+        ParseError::Json(e)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bitcoin::sighash::EcdsaSighashType;
+
+    use super::*;
+
+    fn assert_roundtrip<F: Field<N> + PartialEq, const N: usize>(value: F) {
+        let serialized = value.bip174_serialize();
+        let parsed = F::bip174_deserialize(
+            serialized
+                .iter()
+                .map(String::as_str)
+                .collect::<Vec<_>>()
+                .try_into()
+                .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(parsed, value, "{:?}", serialized);
+    }
+
+    #[test]
+    fn test_field_roundtrip() {
+        let psbt = parse_psbt(EXAMPLE_PSBTS[1].1).unwrap();
+        let input = &psbt.inputs[0];
+        let (&pubkey, &sig) = input.partial_sigs.iter().next().unwrap();
+        let (&secp_key, key_source) = input.bip32_derivation.iter().next().unwrap();
+        let txout = input.witness_utxo.clone().unwrap();
+        let script = input.witness_script.clone().unwrap();
+        let witness = Witness::from_slice(&[sig.to_vec(), script.to_bytes()]);
+
+        assert_roundtrip(psbt.clone());
+        assert_roundtrip(psbt.unsigned_tx.clone());
+        assert_roundtrip(txout.clone());
+        assert_roundtrip(vec![vec![1u8, 2], vec![3]]);
+        assert_roundtrip(vec![0xdeu8, 0xad]);
+        assert_roundtrip(witness.clone());
+        assert_roundtrip(pubkey);
+        assert_roundtrip(secp_key);
+        assert_roundtrip(script.clone());
+        assert_roundtrip(sig);
+        assert_roundtrip(key_source.clone());
+        assert_roundtrip(PsbtSighashType::from(
+            EcdsaSighashType::SinglePlusAnyoneCanPay,
+        ));
+        assert_roundtrip(PsbtSighashType::from_u32(0x42));
+
+        assert_roundtrip(WitnessUtxo(Some(txout)));
+        assert_roundtrip(WitnessUtxo(None));
+        assert_roundtrip(NonWitnessUtxo(Some(psbt.unsigned_tx.clone())));
+        assert_roundtrip(NonWitnessUtxo(None));
+        assert_roundtrip(SecpPublicKeyWrapper(secp_key));
+        assert_roundtrip(PublicKeyWrapper(pubkey));
+        assert_roundtrip(BytesWrapper(vec![0x00, 0xff]));
+        assert_roundtrip(RedeemScript(input.redeem_script.clone()));
+        assert_roundtrip(WitnessScript(None));
+        assert_roundtrip(FinalScript(Some(script)));
+        assert_roundtrip(FinalWitness(Some(witness)));
+        assert_roundtrip(FinalSignature(sig));
+        assert_roundtrip(Some(key_source.clone()));
+        // Paths can be entered the way they are displayed, while the serialization doesn't change
+        let displayed = Some(key_source.clone()).bip174_display(HardenedStyle::H);
+        assert_eq!(displayed[1], "m/0h/0h/4h");
+        assert_eq!(
+            bip32::KeySource::bip174_deserialize([&displayed[0], &displayed[1]]).unwrap(),
+            *key_source
+        );
+        assert_eq!(key_source.bip174_serialize()[1], "m/0'/0'/4'");
+        assert_roundtrip(None::<bip32::KeySource>);
+
+        // Account 0 of the BIP84 test vector, keys keep the network they were encoded for
+        let xpub: bip32::Xpub = "xpub6CatWdiZiodmUeTDp8LT5or8nmbKNcuyvz7WyksVFkKB4RHwCD3XyuvPEbvqAQY3rAPshWcMLoP2fMFMKHPJ4ZeZXYVUhLv1VMrjPC7PW6V".parse().unwrap();
+        assert_roundtrip(xpub);
+        assert_roundtrip(crate::slip132::for_network(&xpub, Network::Testnet));
+        let displayed = xpub.bip174_display(HardenedStyle::H);
+        assert_eq!(displayed, xpub.bip174_serialize());
+        assert_eq!(
+            bip32::Xpub::bip174_deserialize([&displayed[0]]).unwrap(),
+            xpub
+        );
+    }
+
+    #[test]
+    fn test_inverse_restores_psbt() {
+        let psbt = parse_psbt(EXAMPLE_PSBTS[1].1).unwrap();
+        let input = &psbt.inputs[0];
+        let (&pubkey, &sig) = input.partial_sigs.iter().next().unwrap();
+        let (&secp_key, key_source) = input.bip32_derivation.iter().next().unwrap();
+        let other_key = *input.bip32_derivation.keys().nth(1).unwrap();
+        let script = input.witness_script.clone().unwrap();
+
+        let messages = vec![
+            PsbtMessage::ChangePsbt(None),
+            PsbtMessage::ChangeInput(
+                0,
+                PsbtInputMsg::ChangeSigHash(Some(EcdsaSighashType::All.into())),
+            ),
+            PsbtMessage::ChangeInput(0, PsbtInputMsg::ChangeWitnessUtxo(WitnessUtxo(None))),
+            PsbtMessage::ChangeInput(
+                0,
+                PsbtInputMsg::ChangeNonWitnessUtxo(NonWitnessUtxo(Some(psbt.unsigned_tx.clone()))),
+            ),
+            PsbtMessage::ChangeInput(0, PsbtInputMsg::ChangeRedeemScript(RedeemScript(None))),
+            PsbtMessage::ChangeInput(0, PsbtInputMsg::ChangeWitnessScript(WitnessScript(None))),
+            PsbtMessage::ChangeInput(
+                0,
+                PsbtInputMsg::ChangeFinalScript(FinalScript(Some(script.clone()))),
+            ),
+            PsbtMessage::ChangeInput(
+                0,
+                PsbtInputMsg::ChangeFinalWitness(FinalWitness(Some(Witness::from_slice(&[
+                    script.to_bytes(),
+                ])))),
+            ),
+            PsbtMessage::ChangeInput(
+                0,
+                PsbtInputMsg::ChangePartialSigs(MapUpdate::Remove(PublicKeyWrapper(pubkey))),
+            ),
+            PsbtMessage::ChangeInput(
+                0,
+                PsbtInputMsg::ChangePartialSigs(MapUpdate::Set(
+                    PublicKeyWrapper(bitcoin::PublicKey::new(other_key)),
+                    FinalSignature(sig),
+                )),
+            ),
+            PsbtMessage::ChangeInput(
+                0,
+                PsbtInputMsg::ChangeBIP32Derivation(MapUpdate::Remove(SecpPublicKeyWrapper(
+                    secp_key,
+                ))),
+            ),
+            PsbtMessage::ChangeInput(
+                0,
+                PsbtInputMsg::ChangeBIP32Derivation(MapUpdate::Set(
+                    SecpPublicKeyWrapper(other_key),
+                    key_source.clone(),
+                )),
+            ),
+            PsbtMessage::ChangeOutput(
+                0,
+                PsbtOutputMsg::ChangeRedeemScript(RedeemScript(Some(script.clone()))),
+            ),
+            PsbtMessage::ChangeOutput(
+                0,
+                PsbtOutputMsg::ChangeWitnessScript(WitnessScript(Some(script))),
+            ),
+            PsbtMessage::ChangeOutput(
+                0,
+                PsbtOutputMsg::ChangeBIP32Derivation(MapUpdate::Set(
+                    SecpPublicKeyWrapper(secp_key),
+                    key_source.clone(),
+                )),
+            ),
+        ];
+        let all = PsbtMessage::Labelled(
+            "Everything".to_string(),
+            Box::new(PsbtMessage::Batch(messages[1..].to_vec())),
+        );
+
+        let original = psbt.serialize();
+        for msg in messages.into_iter().chain(std::iter::once(all)) {
+            let description = msg.describe();
+            let mut wrapped = Some(psbt.clone());
+            let inverse = msg.apply_to(&mut wrapped);
+            assert_ne!(
+                wrapped.as_ref().map(psbt::Psbt::serialize),
+                Some(original.clone()),
+                "{} didn't change anything",
+                description
+            );
+
+            inverse.apply_to(&mut wrapped);
+            assert_eq!(
+                wrapped.as_ref().map(psbt::Psbt::serialize),
+                Some(original.clone()),
+                "{} wasn't reverted",
+                description
+            );
+        }
+
+        // Stale indexes, e.g. from a saved history, are ignored
+        let mut wrapped = Some(psbt.clone());
+        let stale = PsbtMessage::Batch(vec![
+            PsbtMessage::ChangeInput(5, PsbtInputMsg::ChangeWitnessUtxo(WitnessUtxo(None))),
+            PsbtMessage::ChangeOutput(5, PsbtOutputMsg::ChangeWitnessScript(WitnessScript(None))),
+        ]);
+        stale.apply_to(&mut wrapped);
+        assert_eq!(wrapped.map(|psbt| psbt.serialize()), Some(original));
+    }
+
+    #[test]
+    fn test_parse_error_display() {
+        let error = |s: &str| ScriptBuf::bip174_deserialize([s]).unwrap_err().to_string();
+        assert_eq!(
+            error("0014zz"),
+            "invalid hex character 'z' at position 4 (byte 2)"
+        );
+        assert_eq!(
+            error("00140"),
+            "odd number of hex digits (5), every byte takes two"
+        );
+        assert_eq!(error("ABCDefgh"), "this looks like base64, not hex");
+        assert_eq!(
+            error("00zz"),
+            "invalid hex character 'z' at position 2 (byte 1)"
+        );
+
+        let x_only = "b1341ccba7683b6af4f1238cd6e97e7167d569fac47f1e48d47541844355bd46";
+        assert_eq!(
+            secp256k1::PublicKey::bip174_deserialize([x_only])
+                .unwrap_err()
+                .to_string(),
+            "this is an x-only key, expected 33 bytes with an 02 or 03 prefix"
+        );
+        assert_eq!(
+            bip32::KeySource::bip174_deserialize(["b4a6ba", "m/0"])
+                .unwrap_err()
+                .to_string(),
+            "expected 8 hex digits (4 bytes), got 6"
+        );
+        assert!(Transaction::bip174_deserialize(["0200"])
+            .unwrap_err()
+            .to_string()
+            .starts_with("invalid serialization: "));
+    }
+
+    #[test]
+    fn test_from_strings() {
+        let key = "03b1341ccba7683b6af4f1238cd6e97e7167d569fac47f1e48d47541844355bd46";
+        assert!(matches!(
+            PsbtInputMsg::from_strings("bip32_derivation", &[key, "b4a6ba67", "m/0"], false),
+            Ok(PsbtInputMsg::ChangeBIP32Derivation(MapUpdate::Set(..)))
+        ));
+        assert!(matches!(
+            PsbtInputMsg::from_strings("bip32_derivation", &[key], true),
+            Ok(PsbtInputMsg::ChangeBIP32Derivation(MapUpdate::Remove(_)))
+        ));
+        assert_eq!(
+            PsbtInputMsg::from_strings("bip32_derivation", &[key], false).unwrap_err(),
+            "missing the value"
+        );
+        assert_eq!(
+            PsbtInputMsg::from_strings("bip32_derivation", &[key, "b4a6ba67", "m/0"], true)
+                .unwrap_err(),
+            "remove takes only the key"
+        );
+        assert!(matches!(
+            PsbtOutputMsg::from_strings("redeem_script", &[], true),
+            Ok(PsbtOutputMsg::ChangeRedeemScript(RedeemScript(None)))
+        ));
+        assert_eq!(
+            PsbtOutputMsg::from_strings("redeem_script", &["51"], true).unwrap_err(),
+            "remove takes no value"
+        );
+        assert_eq!(
+            PsbtOutputMsg::from_strings("redeem_script", &[], false).unwrap_err(),
+            "missing the value"
+        );
+        assert_eq!(
+            PsbtInputMsg::from_strings("foo", &[], true).unwrap_err(),
+            "unknown input field \"foo\""
+        );
+        assert_eq!(
+            PsbtInputMsg::from_strings("sighash_type", &["ALL", "1"], false).unwrap_err(),
+            "expected 1 value(s), got 2"
+        );
+    }
+}
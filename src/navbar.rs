@@ -3,6 +3,9 @@ use yew::prelude::*;
 use bitcoin::Network;
 
 use crate::app::{App, AppMsg};
+use crate::derivation::{HardenedStyle, ALL_HARDENED_STYLES};
+use crate::shortcuts;
+use crate::units::{Unit, ALL_UNITS};
 
 const ALL_NETWORKS: [Network; 4] = [
     Network::Bitcoin,
@@ -19,6 +22,12 @@ pub struct Navbar {
 #[derive(Clone, Properties)]
 pub struct NavbarProps {
     pub network: Network,
+    pub unit: Unit,
+    #[prop_or_default]
+    pub hardened_style: HardenedStyle,
+    pub persist: bool,
+    #[prop_or_default]
+    pub read_only: bool,
     pub parent: ComponentLink<App>,
 }
 
@@ -41,15 +50,24 @@ impl Component for Navbar {
     }
 
     fn view(&self) -> Html {
+        let persist = self.props.persist;
+
         html! {
             <>
             <nav class="navbar sticky-top navbar-expand-md navbar-dark bg-dark">
                 <div class="container-fluid">
                   <a class="navbar-brand" href="#">{ "BIP 174" }</a>
 
-                    <div class="btn-group me-2 d-block d-md-none position-absolute" style="right: 72px" role="group" aria-label="First group">
-                        <button type="button" class="btn btn-outline-light" onclick=self.link.callback(|_| AppMsg::Undo)><i class="bi bi-arrow-90deg-left"></i></button><button type="button" class="btn btn-outline-light" onclick=self.link.callback(|_| AppMsg::Redo)><i class="bi bi-arrow-90deg-right"></i></button>
-                    </div>
+                    {
+                        match self.props.read_only {
+                            true => html! {},
+                            false => html! {
+                                <div class="btn-group me-2 d-block d-md-none position-absolute" style="right: 72px" role="group" aria-label="First group">
+                                    <button type="button" class="btn btn-outline-light" title="Undo (Ctrl+Z)" onclick=self.link.callback(|_| AppMsg::Undo)><i class="bi bi-arrow-90deg-left"></i></button><button type="button" class="btn btn-outline-light" title="Redo (Ctrl+Shift+Z)" onclick=self.link.callback(|_| AppMsg::Redo)><i class="bi bi-arrow-90deg-right"></i></button><button type="button" class="btn btn-outline-light" data-bs-toggle="offcanvas" data-bs-target="#historySidebar" aria-controls="historySidebar" title="History"><i class="bi bi-clock-history"></i></button>
+                                </div>
+                            },
+                        }
+                    }
 
                   <button class="navbar-toggler" type="button" data-bs-toggle="collapse" data-bs-target="#navbarContent" aria-controls="navbarContent" aria-expanded="false" aria-label="Toggle navigation">
                     <span class="navbar-toggler-icon"></span>
@@ -71,8 +89,57 @@ impl Component for Navbar {
                       </li>
                     </ul>
 
-                    <div class="btn-group me-2 d-none d-md-block" role="group" aria-label="First group">
-                        <button type="button" class="btn btn-outline-light" onclick=self.link.callback(|_| AppMsg::Undo)><i class="bi bi-arrow-90deg-left"></i></button><button type="button" class="btn btn-outline-light" onclick=self.link.callback(|_| AppMsg::Redo)><i class="bi bi-arrow-90deg-right"></i></button>
+                    {
+                        match self.props.read_only {
+                            true => html! {},
+                            false => html! {
+                                <div class="btn-group me-2 d-none d-md-block" role="group" aria-label="First group">
+                                    <button type="button" class="btn btn-outline-light" title="Undo (Ctrl+Z)" onclick=self.link.callback(|_| AppMsg::Undo)><i class="bi bi-arrow-90deg-left"></i></button><button type="button" class="btn btn-outline-light" title="Redo (Ctrl+Shift+Z)" onclick=self.link.callback(|_| AppMsg::Redo)><i class="bi bi-arrow-90deg-right"></i></button><button type="button" class="btn btn-outline-light" data-bs-toggle="offcanvas" data-bs-target="#historySidebar" aria-controls="historySidebar" title="History"><i class="bi bi-clock-history"></i></button>
+                                </div>
+                            },
+                        }
+                    }
+
+                    <div class="dropdown me-2">
+                      <button class="btn btn-outline-light dropdown-toggle" type="button" id="settingsMenu" data-bs-toggle="dropdown" data-bs-auto-close="outside" aria-expanded="false">
+                        <i class="bi bi-gear"></i>
+                      </button>
+
+                      <ul class="dropdown-menu dropdown-menu-dark dropdown-menu-end" aria-labelledby="settingsMenu">
+                        <li>
+                          <div class="dropdown-item form-check form-switch ps-5">
+                            <input class="form-check-input" type="checkbox" id="persistSwitch" checked=self.props.persist onclick=self.link.callback(move |_| AppMsg::SetPersist(!persist)) />
+                            <label class="form-check-label" for="persistSwitch">{ "Remember PSBT in this browser" }</label>
+                          </div>
+                        </li>
+                        <li><hr class="dropdown-divider" /></li>
+                        <li><h6 class="dropdown-header">{ "Hardened derivation steps" }</h6></li>
+                        {
+                          for ALL_HARDENED_STYLES.iter().map(|style| html! {
+                              <li onclick=self.link.callback(move |_| AppMsg::SetHardenedStyle(*style))>
+                                  <a class=classes!("dropdown-item", if style == &self.props.hardened_style { Some("active") } else { None })><span class="font-monospace">{ format!("m/84{0}/0{0}/0{0}", style) }</span></a>
+                              </li>
+                          })
+                        }
+                        <li><hr class="dropdown-divider" /></li>
+                        <li><a class="dropdown-item" onclick=self.link.callback(|_| AppMsg::ClearSavedData)><i class="bi bi-trash"></i>{ " Clear saved data" }</a></li>
+                      </ul>
+                    </div>
+
+                    <div class="dropdown me-2">
+                      <button class="btn btn-outline-light dropdown-toggle" type="button" id="unitMenu" data-bs-toggle="dropdown" aria-expanded="false" title="Amount unit">
+                        { self.props.unit.to_string() }
+                      </button>
+
+                      <ul class="dropdown-menu dropdown-menu-dark dropdown-menu-end" aria-labelledby="unitMenu">
+                      {
+                        for ALL_UNITS.iter().map(|u| html! {
+                            <li onclick=self.link.callback(move |_| AppMsg::SetUnit(*u))>
+                                <a class=classes!("dropdown-item", if u == &self.props.unit { Some("active") } else { None })>{ u.to_string() }</a>
+                            </li>
+                        })
+                      }
+                      </ul>
                     </div>
 
                     <div class="dropdown">
@@ -105,6 +172,16 @@ impl Component for Navbar {
                         <div class="modal-body">
                             <p>{ "This website is written in Rust and fully open-source: you can find the source code on " }<a href="https://github.com/afilini/bip174.org">{ "GitHub" }</a></p>
                             <p>{ "If you find a bug please file an issue and we'll try to help you!" }</p>
+                            <h6>{ "Keyboard shortcuts" }</h6>
+                            <table class="table table-sm">
+                                <tbody>
+                                    {
+                                        for shortcuts::HELP.iter().map(|(keys, action)| html! {
+                                            <tr><td><kbd>{ keys }</kbd></td><td>{ action }</td></tr>
+                                        })
+                                    }
+                                </tbody>
+                            </table>
                             <p class="text-muted text-center pt-4 fst-italic">{ "Version: " }<span class="font-monospace">{ env!("CARGO_PKG_VERSION") }</span>{ " (" }<span class="font-monospace">{ env!("GIT_STATUS") }</span>{ ")" }</p>
                         </div>
                         <div class="modal-footer">
@@ -0,0 +1,155 @@
+use bitcoin::bip32::{ChildNumber, DerivationPath};
+use bitcoin::{psbt, Network};
+
+use crate::derivation::Scheme;
+use crate::lint::{Finding, Location, Severity};
+
+/// The networks that keys and paths can tell apart: every test network uses the same extended key
+/// version bytes and coin type
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NetworkKind {
+    Main,
+    Test,
+}
+
+impl NetworkKind {
+    pub fn of(network: Network) -> Self {
+        match network {
+            Network::Bitcoin => NetworkKind::Main,
+            _ => NetworkKind::Test,
+        }
+    }
+
+    /// Network of a BIP44 coin type, `None` for the coin types of other chains
+    pub fn from_coin_type(coin_type: u32) -> Option<Self> {
+        match coin_type {
+            0 => Some(NetworkKind::Main),
+            1 => Some(NetworkKind::Test),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            NetworkKind::Main => "mainnet",
+            NetworkKind::Test => "a test network",
+        }
+    }
+}
+
+/// A piece of data in the PSBT that only makes sense on some networks
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct NetworkHint {
+    pub kind: NetworkKind,
+    /// What the network was inferred from
+    pub source: String,
+}
+
+/// Network of the coin type of `path`, when it follows one of the standard derivation schemes
+pub fn path_network(path: &DerivationPath) -> Option<NetworkKind> {
+    match path.as_ref() {
+        [ChildNumber::Hardened { index: purpose }, ChildNumber::Hardened { index: coin_type }, ..]
+            if Scheme::from_purpose(*purpose).is_some() =>
+        {
+            NetworkKind::from_coin_type(*coin_type)
+        }
+        _ => None,
+    }
+}
+
+/// Guess the network the PSBT was created for from the version bytes of the global xpubs, or
+/// failing that from the coin type of the standard derivation paths
+pub fn infer_network(psbt: &psbt::Psbt) -> Option<NetworkHint> {
+    if let Some(xpub) = psbt.xpub.keys().next() {
+        return Some(NetworkHint {
+            kind: NetworkKind::of(xpub.network),
+            source: format!("the global xpub {}", xpub),
+        });
+    }
+
+    let inputs = psbt.inputs.iter().enumerate().flat_map(|(index, input)| {
+        input
+            .bip32_derivation
+            .values()
+            .chain(input.tap_key_origins.values().map(|(_, source)| source))
+            .map(move |(_, path)| (Location::Input(index), path))
+    });
+    let outputs = psbt.outputs.iter().enumerate().flat_map(|(index, output)| {
+        output
+            .bip32_derivation
+            .values()
+            .chain(output.tap_key_origins.values().map(|(_, source)| source))
+            .map(move |(_, path)| (Location::Output(index), path))
+    });
+
+    inputs.chain(outputs).find_map(|(location, path)| {
+        Some(NetworkHint {
+            kind: path_network(path)?,
+            source: format!(
+                "the derivation path {} of {}",
+                path,
+                location.to_string().to_lowercase()
+            ),
+        })
+    })
+}
+
+/// Warn when the PSBT seems to be meant for a different network than the selected one
+pub fn network_mismatch(psbt: &psbt::Psbt, network: Network) -> Option<Finding> {
+    let hint = infer_network(psbt).filter(|hint| hint.kind != NetworkKind::of(network))?;
+
+    Some(Finding {
+        severity: Severity::Warning,
+        location: Location::Global,
+        message: format!(
+            "The PSBT seems to be for {}, judging by {}, but {} is selected: addresses are shown for the wrong network",
+            hint.kind.name(),
+            hint.source,
+            network
+        ),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use bitcoin::bip32::{Fingerprint, Xpub};
+
+    use crate::model::{parse_psbt, EXAMPLE_PSBTS};
+
+    use super::*;
+
+    #[test]
+    fn test_infer_network() {
+        let mut psbt = parse_psbt(EXAMPLE_PSBTS[1].1).unwrap();
+        // The paths of the example don't follow a standard scheme
+        assert_eq!(infer_network(&psbt), None);
+
+        let key = *psbt.inputs[0].bip32_derivation.keys().next().unwrap();
+        let path = DerivationPath::from_str("m/84'/1'/0'/0/3").unwrap();
+        psbt.outputs[0]
+            .bip32_derivation
+            .insert(key, (Fingerprint::default(), path));
+        assert_eq!(
+            infer_network(&psbt),
+            Some(NetworkHint {
+                kind: NetworkKind::Test,
+                source: "the derivation path m/84'/1'/0'/0/3 of output #0".to_string(),
+            })
+        );
+        assert_eq!(network_mismatch(&psbt, Network::Signet), None);
+        assert!(network_mismatch(&psbt, Network::Bitcoin).is_some());
+
+        let xpub = Xpub::from_str("xpub661MyMwAqRbcFtXgS5sYJABqqG9YLmC4Q1Rdap9gSE8NqtwybGhePY2gZ29ESFjqJoCu1Rupje8YtGqsefD265TMg7usUDFdp6W1EGMcet8").unwrap();
+        psbt.xpub
+            .insert(xpub, (Fingerprint::default(), DerivationPath::master()));
+        assert_eq!(infer_network(&psbt).unwrap().kind, NetworkKind::Main);
+        let finding = network_mismatch(&psbt, Network::Testnet).unwrap();
+        assert_eq!(finding.location, Location::Global);
+        assert!(finding
+            .message
+            .starts_with("The PSBT seems to be for mainnet"));
+        assert_eq!(network_mismatch(&psbt, Network::Bitcoin), None);
+    }
+}
@@ -0,0 +1,169 @@
+use std::str::FromStr;
+
+use yew::format::Text;
+use yew::services::storage::{Area, StorageService};
+
+use bitcoin::{psbt, Network};
+
+use crate::derivation::HardenedStyle;
+use crate::history::History;
+use crate::units::Unit;
+
+const ENABLED_KEY: &str = "bip174.persist";
+const PSBT_KEY: &str = "bip174.psbt";
+const NETWORK_KEY: &str = "bip174.network";
+const HISTORY_KEY: &str = "bip174.history";
+const UNIT_KEY: &str = "bip174.unit";
+const HARDENED_KEY: &str = "bip174.hardened";
+
+/// Every key we write, removed by `clear`
+const ALL_KEYS: [&str; 6] = [
+    ENABLED_KEY,
+    PSBT_KEY,
+    NETWORK_KEY,
+    HISTORY_KEY,
+    UNIT_KEY,
+    HARDENED_KEY,
+];
+
+/// Data restored from the local storage of the browser
+#[derive(Debug, Default)]
+pub struct SavedState {
+    pub psbt: Option<psbt::Psbt>,
+    pub network: Option<Network>,
+    pub history: History,
+}
+
+fn storage() -> Option<StorageService> {
+    StorageService::new(Area::Local).ok()
+}
+
+fn restore(key: &str) -> Option<String> {
+    storage()?.restore::<Text>(key).ok()
+}
+
+fn store(key: &str, value: String) {
+    if let Some(mut storage) = storage() {
+        storage.store(key, Ok(value));
+    }
+}
+
+pub fn is_enabled() -> bool {
+    restore(ENABLED_KEY).as_deref() == Some("true")
+}
+
+pub fn enable() {
+    store(ENABLED_KEY, "true".into());
+}
+
+/// Remove everything we've ever saved, which also disables persistence
+pub fn clear() {
+    if let Some(mut storage) = storage() {
+        for key in &ALL_KEYS {
+            storage.remove(key);
+        }
+    }
+}
+
+pub fn save_network(network: Network) {
+    store(NETWORK_KEY, network.to_string());
+}
+
+/// The unit doesn't say anything about the PSBT, so unlike everything else it's saved even when
+/// persistence is disabled
+pub fn save_unit(unit: Unit) {
+    store(UNIT_KEY, unit.to_string());
+}
+
+pub fn load_unit() -> Option<Unit> {
+    restore(UNIT_KEY)?.parse().ok()
+}
+
+/// Like the unit, the hardened notation is a display preference saved regardless of persistence
+pub fn save_hardened_style(style: HardenedStyle) {
+    store(HARDENED_KEY, style.to_string());
+}
+
+pub fn load_hardened_style() -> Option<HardenedStyle> {
+    restore(HARDENED_KEY)?.parse().ok()
+}
+
+fn psbt_entries(psbt: Option<&psbt::Psbt>, history: &History) -> [(&'static str, String); 2] {
+    [
+        (PSBT_KEY, psbt.map(ToString::to_string).unwrap_or_default()),
+        (HISTORY_KEY, history.to_json().to_string()),
+    ]
+}
+
+pub fn save_psbt(psbt: Option<&psbt::Psbt>, history: &History) {
+    for (key, value) in psbt_entries(psbt, history) {
+        store(key, value);
+    }
+}
+
+/// Rebuild the saved state from the stored values, skipping the ones that don't parse
+fn load_from(restore: impl Fn(&str) -> Option<String>) -> SavedState {
+    let psbt = restore(PSBT_KEY).and_then(|s| psbt::Psbt::from_str(&s).ok());
+    let history = restore(HISTORY_KEY)
+        .and_then(|s| serde_json::from_str(&s).ok())
+        .and_then(|json| History::from_json(&json))
+        .unwrap_or_default();
+
+    SavedState {
+        psbt,
+        network: restore(NETWORK_KEY).and_then(|s| Network::from_str(&s).ok()),
+        history,
+    }
+}
+
+pub fn load() -> SavedState {
+    load_from(restore)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{BTreeSet, HashMap};
+
+    use crate::model::{parse_psbt, PsbtMessage, EXAMPLE_PSBTS};
+
+    use super::*;
+
+    #[test]
+    fn test_keys() {
+        let keys = ALL_KEYS.iter().collect::<BTreeSet<_>>();
+        assert_eq!(keys.len(), ALL_KEYS.len());
+        assert!(keys.iter().all(|key| key.starts_with("bip174.")));
+        for (key, _) in psbt_entries(None, &History::default()) {
+            assert!(keys.contains(&key));
+        }
+    }
+
+    #[test]
+    fn test_load_from() {
+        let psbt = parse_psbt(EXAMPLE_PSBTS[1].1).unwrap();
+        let mut history = History::default();
+        history.add(PsbtMessage::ChangePsbt(None), "Loaded PSBT".to_string());
+
+        let mut stored = psbt_entries(Some(&psbt), &history)
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.clone()))
+            .collect::<HashMap<_, _>>();
+        stored.insert(NETWORK_KEY.to_string(), Network::Signet.to_string());
+
+        let saved = load_from(|key| stored.get(key).cloned());
+        assert_eq!(saved.psbt, Some(psbt));
+        assert_eq!(saved.network, Some(Network::Signet));
+        assert_eq!(saved.history.entries().len(), 1);
+        assert_eq!(saved.history.entries()[0].description, "Loaded PSBT");
+
+        // Corrupted values are dropped instead of failing the whole restore
+        stored.insert(PSBT_KEY.to_string(), "not a psbt".to_string());
+        stored.insert(HISTORY_KEY.to_string(), "{".to_string());
+        let saved = load_from(|key| stored.get(key).cloned());
+        assert_eq!(saved.psbt, None);
+        assert_eq!(saved.network, Some(Network::Signet));
+        assert!(saved.history.entries().is_empty());
+
+        assert!(load_from(|_| None).psbt.is_none());
+    }
+}
@@ -0,0 +1,677 @@
+use std::time::Duration;
+
+#[allow(unused_imports)]
+use log::*;
+
+use yew::prelude::*;
+use yew::services::interval::{IntervalService, IntervalTask};
+use yew::services::reader::{File, FileData, ReaderService, ReaderTask};
+
+use bitcoin::psbt;
+use qrcode::{Color, EcLevel, QrCode};
+
+use crate::bbqr::{self, BbqrDecoder};
+use crate::model::parse_psbt;
+use crate::ur::{cbor_bytes, cbor_read_bytes, UrDecoder, UrEncoder, PSBT_UR_TYPE};
+
+/// Longest base64 PSBT offered as a single static QR code
+const STATIC_MAX_LEN: usize = 1000;
+/// Characters of a UR part taken by the type, the sequence numbers, the CBOR header and the
+/// checksum
+const UR_OVERHEAD: usize = 80;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum QrFormat {
+    Base64,
+    Bbqr,
+    Ur,
+}
+
+const ALL_FORMATS: [QrFormat; 3] = [QrFormat::Base64, QrFormat::Bbqr, QrFormat::Ur];
+
+impl QrFormat {
+    fn name(&self) -> &'static str {
+        match self {
+            QrFormat::Base64 => "Static (base64)",
+            QrFormat::Bbqr => "BBQr",
+            QrFormat::Ur => "UR (crypto-psbt)",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Density {
+    Low,
+    Medium,
+    High,
+}
+
+const ALL_DENSITIES: [Density; 3] = [Density::Low, Density::Medium, Density::High];
+
+impl Density {
+    /// Characters in each frame: denser codes need fewer frames but are harder to scan
+    fn max_chars(&self) -> usize {
+        match self {
+            Density::Low => 150,
+            Density::Medium => 300,
+            Density::High => 600,
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            Density::Low => "Low",
+            Density::Medium => "Medium",
+            Density::High => "High",
+        }
+    }
+}
+
+/// Content of the frames showing a PSBT
+#[derive(Clone, Debug)]
+pub enum Frames {
+    Fixed(Vec<String>),
+    /// Fountain coded parts, which never repeat
+    Fountain(UrEncoder),
+}
+
+impl Frames {
+    pub fn new(psbt: &psbt::Psbt, format: QrFormat, density: Density) -> Self {
+        match format {
+            QrFormat::Base64 => Frames::Fixed(vec![psbt.to_string()]),
+            QrFormat::Bbqr => {
+                Frames::Fixed(bbqr::encode(&psbt.serialize(), 'P', density.max_chars()))
+            }
+            QrFormat::Ur => Frames::Fountain(UrEncoder::new(
+                PSBT_UR_TYPE,
+                cbor_bytes(&psbt.serialize()),
+                density.max_chars().saturating_sub(UR_OVERHEAD) / 2,
+            )),
+        }
+    }
+
+    /// How many frames carry different parts of the data
+    pub fn count(&self) -> usize {
+        match self {
+            Frames::Fixed(frames) => frames.len(),
+            Frames::Fountain(encoder) => encoder.seq_len(),
+        }
+    }
+
+    /// Content of frame `index`. UR parts are uppercase so that they fit the compact alphanumeric
+    /// mode of QR codes
+    pub fn get(&self, index: usize) -> String {
+        match self {
+            Frames::Fixed(frames) => frames[index % frames.len()].clone(),
+            Frames::Fountain(encoder) => encoder.part(index as u32 + 1).to_uppercase(),
+        }
+    }
+}
+
+/// Width and SVG path of the dark modules of the QR code encoding `data`
+pub fn qr_svg_path(data: &str) -> Result<(usize, String), String> {
+    let code = QrCode::with_error_correction_level(data, EcLevel::L).map_err(|e| e.to_string())?;
+    let width = code.width();
+    let path = code
+        .to_colors()
+        .iter()
+        .enumerate()
+        .filter(|(_, color)| **color == Color::Dark)
+        .map(|(i, _)| format!("M{},{}h1v1h-1z", i % width, i / width))
+        .collect();
+
+    Ok((width, path))
+}
+
+/// How far the import of the frames of a QR code has got
+#[derive(Clone, Debug, PartialEq)]
+pub enum ImportProgress {
+    Empty,
+    Partial {
+        format: QrFormat,
+        total: usize,
+        missing: Vec<usize>,
+    },
+    Complete(psbt::Psbt),
+}
+
+/// Reassemble a PSBT from the content of QR code frames, one per line. Accepts UR `crypto-psbt`
+/// parts, BBQr parts and the single base64 frame of a static QR code
+pub fn import_frames(text: &str) -> Result<ImportProgress, String> {
+    let mut lines = text
+        .lines()
+        .enumerate()
+        .map(|(i, line)| (i + 1, line.trim()))
+        .filter(|(_, line)| !line.is_empty())
+        .peekable();
+    let first = match lines.peek() {
+        Some((_, first)) => first.to_string(),
+        None => return Ok(ImportProgress::Empty),
+    };
+
+    if first.to_lowercase().starts_with("ur:") {
+        let mut decoder = UrDecoder::default();
+        for (n, line) in lines {
+            decoder
+                .receive(line)
+                .map_err(|e| format!("Line {}: {}", n, e))?;
+        }
+        if decoder.ur_type() != Some(PSBT_UR_TYPE) {
+            return Err(format!(
+                "Expected a \"{}\" UR, got \"{}\"",
+                PSBT_UR_TYPE,
+                decoder.ur_type().unwrap_or_default()
+            ));
+        }
+
+        match decoder.message() {
+            Some(mut message) => {
+                let psbt = cbor_read_bytes(&mut message)?;
+                psbt::Psbt::deserialize(&psbt)
+                    .map(ImportProgress::Complete)
+                    .map_err(|e| e.to_string())
+            }
+            None => Ok(ImportProgress::Partial {
+                format: QrFormat::Ur,
+                total: decoder.seq_len().unwrap_or_default(),
+                missing: decoder.missing(),
+            }),
+        }
+    } else if first.starts_with("B$") {
+        let mut decoder = BbqrDecoder::default();
+        for (n, line) in lines {
+            decoder
+                .receive(line)
+                .map_err(|e| format!("Line {}: {}", n, e))?;
+        }
+        if decoder.file_type() != Some('P') {
+            return Err(format!(
+                "Expected a PSBT, got a BBQr of file type '{}'",
+                decoder.file_type().unwrap_or_default()
+            ));
+        }
+
+        match decoder.message() {
+            Some(message) => psbt::Psbt::deserialize(message)
+                .map(ImportProgress::Complete)
+                .map_err(|e| e.to_string()),
+            None => Ok(ImportProgress::Partial {
+                format: QrFormat::Bbqr,
+                total: decoder.count().unwrap_or_default(),
+                missing: decoder.missing(),
+            }),
+        }
+    } else {
+        match lines.count() {
+            1 => parse_psbt(&first)
+                .map(ImportProgress::Complete)
+                .map_err(|e| e.to_string()),
+            _ => Err("Expected UR or BBQr parts, one per line".to_string()),
+        }
+    }
+}
+
+pub struct QrImport {
+    link: ComponentLink<Self>,
+    props: QrImportProps,
+
+    open: bool,
+    text: String,
+    progress: Result<ImportProgress, String>,
+    reader_task: Option<ReaderTask>,
+}
+
+#[derive(Clone, Properties)]
+pub struct QrImportProps {
+    pub onload: Callback<psbt::Psbt>,
+}
+
+pub enum QrImportMsg {
+    Open,
+    Close,
+    SetText(String),
+    LoadFile(Option<File>),
+    FileLoaded(FileData),
+    Import,
+}
+
+impl QrImport {
+    fn view_progress(&self) -> Html {
+        match &self.progress {
+            Ok(ImportProgress::Empty) => html! {},
+            Ok(ImportProgress::Partial {
+                format,
+                total,
+                missing,
+            }) => {
+                let received = total - missing.len();
+                let percent = received * 100 / total.max(&1);
+                let missing = missing
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                let hint = match format {
+                    QrFormat::Ur => " Any later part can replace the missing ones.",
+                    _ => "",
+                };
+
+                html! {
+                    <>
+                        <div class="progress mb-2">
+                            <div class="progress-bar" role="progressbar" style=format!("width: {}%", percent)>{ format!("{} of {}", received, total) }</div>
+                        </div>
+                        <p class="small text-muted">{ format!("{}: missing part {}.{}", format.name(), missing, hint) }</p>
+                    </>
+                }
+            }
+            Ok(ImportProgress::Complete(psbt)) => html! {
+                <div class="alert alert-success">
+                    { format!("PSBT with {} inputs and {} outputs", psbt.inputs.len(), psbt.outputs.len()) }
+                </div>
+            },
+            Err(e) => html! { <div class="alert alert-danger">{ e }</div> },
+        }
+    }
+}
+
+impl Component for QrImport {
+    type Message = QrImportMsg;
+    type Properties = QrImportProps;
+
+    fn create(props: Self::Properties, link: ComponentLink<Self>) -> Self {
+        QrImport {
+            link,
+            props,
+
+            open: false,
+            text: String::new(),
+            progress: Ok(ImportProgress::Empty),
+            reader_task: None,
+        }
+    }
+
+    fn change(&mut self, props: Self::Properties) -> ShouldRender {
+        self.props = props;
+        false
+    }
+
+    fn update(&mut self, msg: Self::Message) -> ShouldRender {
+        match msg {
+            QrImportMsg::Open => self.open = true,
+            QrImportMsg::Close => {
+                self.open = false;
+                self.text.clear();
+                self.progress = Ok(ImportProgress::Empty);
+            }
+            QrImportMsg::SetText(text) => {
+                self.progress = import_frames(&text);
+                self.text = text;
+            }
+            QrImportMsg::LoadFile(Some(file)) => {
+                match ReaderService::read_file(file, self.link.callback(QrImportMsg::FileLoaded)) {
+                    Ok(task) => self.reader_task = Some(task),
+                    Err(e) => self.progress = Err(e.to_string()),
+                }
+            }
+            QrImportMsg::LoadFile(None) => return false,
+            QrImportMsg::FileLoaded(data) => {
+                self.reader_task = None;
+                // Frames from different files add up, so a scan can be completed later
+                let mut text = self.text.trim_end().to_string();
+                if !text.is_empty() {
+                    text.push('\n');
+                }
+                text.push_str(&String::from_utf8_lossy(&data.content));
+                self.link.send_message(QrImportMsg::SetText(text));
+                return false;
+            }
+            QrImportMsg::Import => {
+                if let Ok(ImportProgress::Complete(psbt)) = &self.progress {
+                    self.props.onload.emit(psbt.clone());
+                    self.link.send_message(QrImportMsg::Close);
+                }
+                return false;
+            }
+        }
+
+        true
+    }
+
+    fn view(&self) -> Html {
+        let button = html! {
+            <button type="button" class="btn btn-outline-secondary me-2" onclick=self.link.callback(|_| QrImportMsg::Open)>
+                <i class="bi bi-qr-code-scan"></i>{ " Import QR" }
+            </button>
+        };
+        if !self.open {
+            return button;
+        }
+
+        let complete = matches!(self.progress, Ok(ImportProgress::Complete(_)));
+        let onfile = self.link.callback(|data| match data {
+            ChangeData::Files(files) => QrImportMsg::LoadFile(files.get(0)),
+            _ => QrImportMsg::LoadFile(None),
+        });
+
+        html! {
+            <>
+            { button }
+            <div class="modal d-block" tabindex="-1" style="background: rgba(0, 0, 0, .5)">
+                <div class="modal-dialog modal-dialog-centered modal-lg">
+                    <div class="modal-content">
+                        <div class="modal-header">
+                            <h5 class="modal-title">{ "Import QR code" }</h5>
+                            <button type="button" class="btn-close" aria-label="Close" onclick=self.link.callback(|_| QrImportMsg::Close)></button>
+                        </div>
+                        <div class="modal-body">
+                            <p class="small text-muted">{ "Paste the content of the scanned frames, one per line: UR parts (ur:crypto-psbt/...), BBQr parts (B$...) or a single base64 PSBT. Frames can be pasted in any order." }</p>
+                            <textarea class="form-control font-monospace mb-2" rows="8" value=self.text.clone() oninput=self.link.callback(|e: InputData| QrImportMsg::SetText(e.value))></textarea>
+                            { self.view_progress() }
+                        </div>
+                        <div class="modal-footer">
+                            <label class="btn btn-outline-secondary me-auto">
+                                <i class="bi bi-file-earmark-text"></i>{ " Load text file" }
+                                <input type="file" class="d-none" accept=".txt" onchange=onfile />
+                            </label>
+                            <button type="button" class="btn btn-secondary" onclick=self.link.callback(|_| QrImportMsg::Close)>{ "Cancel" }</button>
+                            <button type="button" class="btn btn-primary" disabled=!complete onclick=self.link.callback(|_| QrImportMsg::Import)>{ "Load PSBT" }</button>
+                        </div>
+                    </div>
+                </div>
+            </div>
+            </>
+        }
+    }
+}
+
+pub struct QrExport {
+    link: ComponentLink<Self>,
+    props: QrExportProps,
+
+    open: bool,
+    format: QrFormat,
+    density: Density,
+    fps: u32,
+    frame: usize,
+    frames: Option<Frames>,
+    _interval: Option<IntervalTask>,
+}
+
+#[derive(Clone, Properties)]
+pub struct QrExportProps {
+    pub psbt: psbt::Psbt,
+}
+
+pub enum QrExportMsg {
+    Open,
+    Close,
+    SetFormat(QrFormat),
+    SetDensity(Density),
+    SetFps(u32),
+    Tick,
+}
+
+impl QrExport {
+    fn fits_static(&self) -> bool {
+        self.props.psbt.to_string().len() <= STATIC_MAX_LEN
+    }
+
+    /// Encode the PSBT again and restart the animation
+    fn refresh(&mut self) {
+        if self.format == QrFormat::Base64 && !self.fits_static() {
+            self.format = QrFormat::Bbqr;
+        }
+
+        let frames = Frames::new(&self.props.psbt, self.format, self.density);
+        self._interval = match frames.count() {
+            1 => None,
+            _ => Some(IntervalService::spawn(
+                Duration::from_millis(1000 / self.fps as u64),
+                self.link.callback(|_| QrExportMsg::Tick),
+            )),
+        };
+        self.frames = Some(frames);
+        self.frame = 0;
+    }
+
+    fn view_frame(&self, frames: &Frames) -> Html {
+        let (width, path) = match qr_svg_path(&frames.get(self.frame)) {
+            Ok(qr) => qr,
+            Err(e) => {
+                return html! { <div class="alert alert-danger">{ format!("Can't render the QR code: {}", e) }</div> }
+            }
+        };
+        let size = width + 8;
+        let caption = match frames {
+            _ if frames.count() == 1 => "Single frame".to_string(),
+            Frames::Fixed(_) => format!("Part {} of {}", self.frame + 1, frames.count()),
+            Frames::Fountain(_) => format!("Part {}, {} needed", self.frame + 1, frames.count()),
+        };
+
+        html! {
+            <>
+                <svg class="w-100 bg-white" viewBox=format!("0 0 {} {}", size, size) shape-rendering="crispEdges">
+                    <path d=path transform="translate(4 4)" fill="black" />
+                </svg>
+                <p class="text-center text-muted small mb-3">{ caption }</p>
+            </>
+        }
+    }
+}
+
+impl Component for QrExport {
+    type Message = QrExportMsg;
+    type Properties = QrExportProps;
+
+    fn create(props: Self::Properties, link: ComponentLink<Self>) -> Self {
+        let mut qr = QrExport {
+            link,
+            props,
+
+            open: false,
+            format: QrFormat::Base64,
+            density: Density::Medium,
+            fps: 4,
+            frame: 0,
+            frames: None,
+            _interval: None,
+        };
+        if !qr.fits_static() {
+            qr.format = QrFormat::Bbqr;
+        }
+        qr
+    }
+
+    fn change(&mut self, props: Self::Properties) -> ShouldRender {
+        let changed = props.psbt != self.props.psbt;
+        self.props = props;
+        if changed && self.open {
+            self.refresh();
+        }
+        true
+    }
+
+    fn update(&mut self, msg: Self::Message) -> ShouldRender {
+        match msg {
+            QrExportMsg::Open => {
+                self.open = true;
+                self.refresh();
+            }
+            QrExportMsg::Close => {
+                self.open = false;
+                self.frames = None;
+                self._interval = None;
+            }
+            QrExportMsg::SetFormat(format) => {
+                self.format = format;
+                self.refresh();
+            }
+            QrExportMsg::SetDensity(density) => {
+                self.density = density;
+                self.refresh();
+            }
+            QrExportMsg::SetFps(fps) => {
+                self.fps = fps.clamp(1, 10);
+                self.refresh();
+            }
+            QrExportMsg::Tick => self.frame += 1,
+        }
+
+        true
+    }
+
+    fn view(&self) -> Html {
+        let button = html! {
+            <button type="button" class="btn btn-outline-secondary me-2" onclick=self.link.callback(|_| QrExportMsg::Open)>
+                <i class="bi bi-qr-code"></i>{ " QR code" }
+            </button>
+        };
+        let frames = match (&self.frames, self.open) {
+            (Some(frames), true) => frames,
+            _ => return button,
+        };
+
+        let fits_static = self.fits_static();
+        let onformat = self.link.batch_callback(|data| match data {
+            ChangeData::Select(select) => ALL_FORMATS
+                .get(select.selected_index() as usize)
+                .map(|f| QrExportMsg::SetFormat(*f)),
+            _ => None,
+        });
+        let ondensity = self.link.batch_callback(|data| match data {
+            ChangeData::Select(select) => ALL_DENSITIES
+                .get(select.selected_index() as usize)
+                .map(|d| QrExportMsg::SetDensity(*d)),
+            _ => None,
+        });
+
+        html! {
+            <>
+            { button }
+            <div class="modal d-block" tabindex="-1" style="background: rgba(0, 0, 0, .5)">
+                <div class="modal-dialog modal-dialog-centered">
+                    <div class="modal-content">
+                        <div class="modal-header">
+                            <h5 class="modal-title">{ "QR code" }</h5>
+                            <button type="button" class="btn-close" aria-label="Close" onclick=self.link.callback(|_| QrExportMsg::Close)></button>
+                        </div>
+                        <div class="modal-body">
+                            { self.view_frame(frames) }
+                            <div class="row g-2">
+                                <div class="col-6 form-floating">
+                                    <select class="form-select" id="qrFormat" onchange=onformat>
+                                        {
+                                            for ALL_FORMATS.iter().map(|f| html! {
+                                                <option selected=*f == self.format disabled=*f == QrFormat::Base64 && !fits_static>{ f.name() }</option>
+                                            })
+                                        }
+                                    </select>
+                                    <label for="qrFormat">{ "Format" }</label>
+                                </div>
+                                <div class="col-6 form-floating">
+                                    <select class="form-select" id="qrDensity" disabled=self.format == QrFormat::Base64 onchange=ondensity>
+                                        {
+                                            for ALL_DENSITIES.iter().map(|d| html! {
+                                                <option selected=*d == self.density>{ d.name() }</option>
+                                            })
+                                        }
+                                    </select>
+                                    <label for="qrDensity">{ "Density" }</label>
+                                </div>
+                                <div class="col-12">
+                                    <label for="qrFps" class="form-label small">{ format!("Frame rate: {} per second", self.fps) }</label>
+                                    <input type="range" class="form-range" id="qrFps" min="1" max="10" value=self.fps.to_string() disabled=frames.count() == 1 onchange=self.link.batch_callback(|data| match data {
+                                        ChangeData::Value(value) => value.parse().ok().map(QrExportMsg::SetFps),
+                                        _ => None,
+                                    }) />
+                                </div>
+                            </div>
+                        </div>
+                    </div>
+                </div>
+            </div>
+            </>
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::model::{parse_psbt, EXAMPLE_PSBTS};
+
+    use super::*;
+
+    #[test]
+    fn test_frames() {
+        let psbt = parse_psbt(EXAMPLE_PSBTS[0].1).unwrap();
+
+        let frames = Frames::new(&psbt, QrFormat::Base64, Density::Low);
+        assert_eq!(frames.count(), 1);
+        assert_eq!(frames.get(1), psbt.to_string());
+
+        let frames = Frames::new(&psbt, QrFormat::Bbqr, Density::Low);
+        assert!(frames.count() > 1);
+        assert!(frames.get(0).starts_with("B$2P"));
+        assert!((0..frames.count()).all(|i| frames.get(i).len() <= Density::Low.max_chars()));
+
+        let frames = Frames::new(&psbt, QrFormat::Ur, Density::Medium);
+        let parts = (0..frames.count() + 2)
+            .map(|i| frames.get(i))
+            .collect::<Vec<_>>();
+        assert!(parts[0].starts_with(&format!("UR:CRYPTO-PSBT/1-{}/", frames.count())));
+        assert!(parts.iter().all(|p| p.len() <= Density::Medium.max_chars()));
+        assert_ne!(parts[frames.count()], parts[0]);
+
+        let (width, path) = qr_svg_path(&parts[0]).unwrap();
+        assert_eq!((width - 17) % 4, 0);
+        assert!(path.starts_with("M0,0h1v1h-1z"));
+    }
+
+    #[test]
+    fn test_import_frames() {
+        let psbt = parse_psbt(EXAMPLE_PSBTS[0].1).unwrap();
+        assert_eq!(import_frames(" \n"), Ok(ImportProgress::Empty));
+        assert_eq!(
+            import_frames(&psbt.to_string()),
+            Ok(ImportProgress::Complete(psbt.clone()))
+        );
+
+        let frames = Frames::new(&psbt, QrFormat::Bbqr, Density::Low);
+        let parts = (0..frames.count())
+            .map(|i| frames.get(i))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            import_frames(&parts[1..].join("\n")),
+            Ok(ImportProgress::Partial {
+                format: QrFormat::Bbqr,
+                total: parts.len(),
+                missing: vec![1],
+            })
+        );
+        assert_eq!(
+            import_frames(&parts.iter().rev().cloned().collect::<Vec<_>>().join("\n")),
+            Ok(ImportProgress::Complete(psbt.clone()))
+        );
+
+        let frames = Frames::new(&psbt, QrFormat::Ur, Density::Low);
+        let parts = (1..frames.count() * 3)
+            .map(|i| frames.get(i))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            import_frames(&parts.join("\n")),
+            Ok(ImportProgress::Complete(psbt))
+        );
+        match import_frames(&parts[0]) {
+            Ok(ImportProgress::Partial { total, missing, .. }) => {
+                assert_eq!(total, frames.count());
+                assert_eq!(missing.len(), total - 1);
+                assert!(!missing.contains(&2));
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+        assert!(import_frames(&format!("{}\nfoo", parts[0]))
+            .unwrap_err()
+            .starts_with("Line 2: "));
+    }
+}
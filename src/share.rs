@@ -0,0 +1,113 @@
+use std::str::FromStr;
+
+use wasm_bindgen::JsValue;
+use yew::web_sys;
+
+use bitcoin::{psbt, Network};
+
+/// Encode the PSBT and network in a URL fragment. The fragment is never sent to the server, so
+/// sharing a link doesn't leak the PSBT to anyone other than the recipient.
+pub fn encode_fragment(psbt: Option<&psbt::Psbt>, network: Network) -> String {
+    match psbt {
+        Some(psbt) => format!("network={}&psbt={}", network, psbt),
+        None => format!("network={}", network),
+    }
+}
+
+pub fn decode_fragment(fragment: &str) -> (Option<psbt::Psbt>, Option<Network>) {
+    let mut psbt = None;
+    let mut network = None;
+
+    for (key, value) in fragment
+        .trim_start_matches('#')
+        .split('&')
+        .filter_map(|pair| pair.split_once('='))
+    {
+        match key {
+            "psbt" => psbt = psbt::Psbt::from_str(value).ok(),
+            "network" => network = Network::from_str(value).ok(),
+            _ => {}
+        }
+    }
+
+    (psbt, network)
+}
+
+pub fn read_location() -> (Option<psbt::Psbt>, Option<Network>) {
+    web_sys::window()
+        .and_then(|w| w.location().hash().ok())
+        .map(|hash| decode_fragment(&hash))
+        .unwrap_or_default()
+}
+
+/// Whether the `readonly` parameter is set in the query string of the URL, unlike the fragment it
+/// survives the edits of the PSBT
+pub fn is_read_only(search: &str) -> bool {
+    search
+        .trim_start_matches('?')
+        .split('&')
+        .any(|pair| match pair.split_once('=') {
+            Some((key, value)) => key == "readonly" && value != "0" && value != "false",
+            None => pair == "readonly",
+        })
+}
+
+pub fn read_only_requested() -> bool {
+    web_sys::window()
+        .and_then(|w| w.location().search().ok())
+        .map(|search| is_read_only(&search))
+        .unwrap_or_default()
+}
+
+/// Replace the fragment of the current URL without adding an entry to the browser history
+pub fn update_location(psbt: Option<&psbt::Psbt>, network: Network) {
+    if let Some(history) = web_sys::window().and_then(|w| w.history().ok()) {
+        let _ = history.replace_state_with_url(
+            &JsValue::NULL,
+            "",
+            Some(&format!("#{}", encode_fragment(psbt, network))),
+        );
+    }
+}
+
+pub fn share_link(psbt: Option<&psbt::Psbt>, network: Network) -> String {
+    let location = web_sys::window().unwrap().location();
+    format!(
+        "{}{}#{}",
+        location.origin().unwrap_or_default(),
+        location.pathname().unwrap_or_default(),
+        encode_fragment(psbt, network)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::model::{parse_psbt, EXAMPLE_PSBTS};
+
+    use super::*;
+
+    #[test]
+    fn test_fragment_roundtrip() {
+        let psbt = parse_psbt(EXAMPLE_PSBTS[1].1).unwrap();
+
+        let fragment = encode_fragment(Some(&psbt), Network::Signet);
+        assert_eq!(
+            decode_fragment(&format!("#{}", fragment)),
+            (Some(psbt), Some(Network::Signet))
+        );
+        assert_eq!(
+            decode_fragment(&encode_fragment(None, Network::Bitcoin)),
+            (None, Some(Network::Bitcoin))
+        );
+        assert_eq!(decode_fragment(""), (None, None));
+    }
+
+    #[test]
+    fn test_is_read_only() {
+        assert!(is_read_only("?readonly"));
+        assert!(is_read_only("?foo=bar&readonly=1"));
+        assert!(!is_read_only("?readonly=false"));
+        assert!(!is_read_only("?readonly_not"));
+        assert!(!is_read_only(""));
+    }
+}
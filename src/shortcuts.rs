@@ -0,0 +1,164 @@
+use wasm_bindgen::JsCast;
+use yew::web_sys;
+
+/// Keys and what they do, listed in the About dialog
+pub const HELP: [(&str, &str); 8] = [
+    ("Ctrl+Z", "Undo"),
+    ("Ctrl+Shift+Z / Ctrl+Y", "Redo"),
+    ("J", "Next input or output"),
+    ("K", "Previous input or output"),
+    ("I", "First input"),
+    ("O", "First output"),
+    ("/", "Edit the PSBT"),
+    ("Esc", "Leave the field being edited"),
+];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Shortcut {
+    Undo,
+    Redo,
+    NextCard,
+    PreviousCard,
+    FirstInput,
+    FirstOutput,
+    FocusPsbt,
+    Blur,
+}
+
+/// Map a key press to a shortcut. While a field is being edited everything except `Esc` is left
+/// to the browser, so that the native undo of the textareas keeps working.
+pub fn shortcut_for(
+    key: &str,
+    ctrl: bool,
+    shift: bool,
+    alt: bool,
+    editing: bool,
+) -> Option<Shortcut> {
+    if editing {
+        return match key {
+            "Escape" => Some(Shortcut::Blur),
+            _ => None,
+        };
+    }
+    if alt {
+        return None;
+    }
+
+    match (key.to_lowercase().as_str(), ctrl, shift) {
+        ("z", true, false) => Some(Shortcut::Undo),
+        ("z", true, true) | ("y", true, false) => Some(Shortcut::Redo),
+        ("j", false, false) => Some(Shortcut::NextCard),
+        ("k", false, false) => Some(Shortcut::PreviousCard),
+        ("i", false, false) => Some(Shortcut::FirstInput),
+        ("o", false, false) => Some(Shortcut::FirstOutput),
+        ("/", false, _) => Some(Shortcut::FocusPsbt),
+        _ => None,
+    }
+}
+
+/// Whether the key event comes from a field that handles its own keys
+pub fn is_editing(event: &web_sys::KeyboardEvent) -> bool {
+    let element = match event
+        .target()
+        .and_then(|t| t.dyn_into::<web_sys::HtmlElement>().ok())
+    {
+        Some(element) => element,
+        None => return false,
+    };
+
+    matches!(element.tag_name().as_str(), "INPUT" | "TEXTAREA" | "SELECT")
+        || element.is_content_editable()
+}
+
+fn find_all(selector: &str) -> Vec<web_sys::HtmlElement> {
+    let document = web_sys::window().unwrap().document().unwrap();
+    let list = match document.query_selector_all(selector) {
+        Ok(list) => list,
+        Err(_) => return vec![],
+    };
+
+    (0..list.length())
+        .filter_map(|i| list.get(i))
+        .filter_map(|node| node.dyn_into::<web_sys::HtmlElement>().ok())
+        .collect()
+}
+
+fn focus(element: &web_sys::HtmlElement) {
+    element.scroll_into_view();
+    let _ = element.focus();
+}
+
+/// Move the focus `step` cards away from the one currently focused, across inputs and outputs
+pub fn focus_card(step: isize) {
+    let cards = find_all(".active-tab .psbt-card");
+    if cards.is_empty() {
+        return;
+    }
+
+    let active = web_sys::window()
+        .and_then(|w| w.document())
+        .and_then(|d| d.active_element());
+    let next = match cards
+        .iter()
+        .position(|card| Some(card.unchecked_ref::<web_sys::Element>()) == active.as_ref())
+    {
+        Some(current) => (current as isize + step).rem_euclid(cards.len() as isize) as usize,
+        None if step < 0 => cards.len() - 1,
+        None => 0,
+    };
+    focus(&cards[next]);
+}
+
+pub fn focus_first(selector: &str) {
+    if let Some(element) = find_all(&format!(".active-tab {}", selector)).first() {
+        focus(element);
+    }
+}
+
+pub fn blur() {
+    if let Some(element) = web_sys::window()
+        .and_then(|w| w.document())
+        .and_then(|d| d.active_element())
+        .and_then(|e| e.dyn_into::<web_sys::HtmlElement>().ok())
+    {
+        let _ = element.blur();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_shortcut_for() {
+        assert_eq!(
+            shortcut_for("z", true, false, false, false),
+            Some(Shortcut::Undo)
+        );
+        assert_eq!(
+            shortcut_for("Z", true, true, false, false),
+            Some(Shortcut::Redo)
+        );
+        assert_eq!(
+            shortcut_for("y", true, false, false, false),
+            Some(Shortcut::Redo)
+        );
+        assert_eq!(
+            shortcut_for("j", false, false, false, false),
+            Some(Shortcut::NextCard)
+        );
+        assert_eq!(shortcut_for("j", true, false, false, false), None);
+        assert_eq!(
+            shortcut_for("/", false, false, false, false),
+            Some(Shortcut::FocusPsbt)
+        );
+
+        // Leave the native undo of textareas alone
+        assert_eq!(shortcut_for("z", true, false, false, true), None);
+        assert_eq!(shortcut_for("j", false, false, false, true), None);
+        assert_eq!(
+            shortcut_for("Escape", false, false, false, true),
+            Some(Shortcut::Blur)
+        );
+    }
+}
@@ -0,0 +1,210 @@
+use bitcoin::base58;
+use bitcoin::bip32::{self, Xpub};
+use bitcoin::Network;
+
+use crate::model::ParseError;
+use crate::network::NetworkKind;
+
+/// Version bytes of the extended public keys, as registered in SLIP-132
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum KeyFormat {
+    Xpub,
+    Ypub,
+    Zpub,
+    YpubMultisig,
+    ZpubMultisig,
+    Tpub,
+    Upub,
+    Vpub,
+    UpubMultisig,
+    VpubMultisig,
+}
+
+pub const ALL_FORMATS: [KeyFormat; 10] = [
+    KeyFormat::Xpub,
+    KeyFormat::Ypub,
+    KeyFormat::Zpub,
+    KeyFormat::YpubMultisig,
+    KeyFormat::ZpubMultisig,
+    KeyFormat::Tpub,
+    KeyFormat::Upub,
+    KeyFormat::Vpub,
+    KeyFormat::UpubMultisig,
+    KeyFormat::VpubMultisig,
+];
+
+/// Version bytes of the private counterparts, only recognized to refuse them
+const PRIVATE_VERSIONS: [u32; 10] = [
+    0x0488ade4, 0x049d7878, 0x04b2430c, 0x0295b005, 0x02aa7a99, 0x04358394, 0x044a4e28, 0x045f18bc,
+    0x024285b5, 0x02575048,
+];
+
+impl KeyFormat {
+    pub fn version(&self) -> [u8; 4] {
+        let version: u32 = match self {
+            KeyFormat::Xpub => 0x0488b21e,
+            KeyFormat::Ypub => 0x049d7cb2,
+            KeyFormat::Zpub => 0x04b24746,
+            KeyFormat::YpubMultisig => 0x0295b43f,
+            KeyFormat::ZpubMultisig => 0x02aa7ed3,
+            KeyFormat::Tpub => 0x043587cf,
+            KeyFormat::Upub => 0x044a5262,
+            KeyFormat::Vpub => 0x045f1cf6,
+            KeyFormat::UpubMultisig => 0x024289ef,
+            KeyFormat::VpubMultisig => 0x02575483,
+        };
+        version.to_be_bytes()
+    }
+
+    pub fn from_version(version: [u8; 4]) -> Option<Self> {
+        ALL_FORMATS.iter().copied().find(|f| f.version() == version)
+    }
+
+    /// The prefix of the keys in this format
+    pub fn name(&self) -> &'static str {
+        match self {
+            KeyFormat::Xpub => "xpub",
+            KeyFormat::Ypub => "ypub",
+            KeyFormat::Zpub => "zpub",
+            KeyFormat::YpubMultisig => "Ypub",
+            KeyFormat::ZpubMultisig => "Zpub",
+            KeyFormat::Tpub => "tpub",
+            KeyFormat::Upub => "upub",
+            KeyFormat::Vpub => "vpub",
+            KeyFormat::UpubMultisig => "Upub",
+            KeyFormat::VpubMultisig => "Vpub",
+        }
+    }
+
+    /// Scripts the wallets using this format derive
+    pub fn script_type(&self) -> &'static str {
+        match self {
+            KeyFormat::Xpub | KeyFormat::Tpub => "P2PKH or any other",
+            KeyFormat::Ypub | KeyFormat::Upub => "P2SH-P2WPKH",
+            KeyFormat::Zpub | KeyFormat::Vpub => "P2WPKH",
+            KeyFormat::YpubMultisig | KeyFormat::UpubMultisig => "P2SH-P2WSH multisig",
+            KeyFormat::ZpubMultisig | KeyFormat::VpubMultisig => "P2WSH multisig",
+        }
+    }
+
+    pub fn network(&self) -> NetworkKind {
+        match self {
+            KeyFormat::Xpub
+            | KeyFormat::Ypub
+            | KeyFormat::Zpub
+            | KeyFormat::YpubMultisig
+            | KeyFormat::ZpubMultisig => NetworkKind::Main,
+            _ => NetworkKind::Test,
+        }
+    }
+
+    /// The format for the same script type on another network
+    pub fn for_network(&self, network: NetworkKind) -> Self {
+        let index = ALL_FORMATS.iter().position(|f| f == self).unwrap() % 5;
+        match network {
+            NetworkKind::Main => ALL_FORMATS[index],
+            NetworkKind::Test => ALL_FORMATS[index + 5],
+        }
+    }
+}
+
+/// Decode an extended public key in any of the SLIP-132 formats, returning it as a plain xpub or
+/// tpub together with the format it was written in
+pub fn decode_extended_key(s: &str) -> Result<(Xpub, KeyFormat), ParseError> {
+    let mut data = base58::decode_check(s)?;
+    if data.len() != 78 {
+        return Err(ParseError::ExtendedKeyLength(data.len()));
+    }
+
+    let version = [data[0], data[1], data[2], data[3]];
+    let format = match KeyFormat::from_version(version) {
+        Some(format) => format,
+        None if PRIVATE_VERSIONS.contains(&u32::from_be_bytes(version)) => {
+            return Err(ParseError::ExtendedPrivateKey)
+        }
+        None => return Err(ParseError::ExtendedKeyVersion(version)),
+    };
+
+    let standard = match format.network() {
+        NetworkKind::Main => KeyFormat::Xpub,
+        NetworkKind::Test => KeyFormat::Tpub,
+    };
+    data[..4].copy_from_slice(&standard.version());
+    let xpub = Xpub::decode(&data).map_err(|e| match e {
+        bip32::Error::Secp256k1(e) => ParseError::Secp(e),
+        e => ParseError::BIP32(e),
+    })?;
+
+    Ok((xpub, format))
+}
+
+/// Write `xpub` with the version bytes of `format`
+pub fn encode_extended_key(xpub: &Xpub, format: KeyFormat) -> String {
+    let mut data = xpub.encode();
+    data[..4].copy_from_slice(&format.version());
+    base58::encode_check(&data)
+}
+
+/// The same key tagged for `network`, so that it's shown as an xpub or a tpub accordingly
+pub fn for_network(xpub: &Xpub, network: Network) -> Xpub {
+    let network = match NetworkKind::of(network) {
+        NetworkKind::Main => Network::Bitcoin,
+        NetworkKind::Test => Network::Testnet,
+    };
+    Xpub { network, ..*xpub }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use crate::model::Field;
+
+    use super::*;
+
+    // Account 0 of the BIP84 test vector
+    const XPUB: &str = "xpub6CatWdiZiodmUeTDp8LT5or8nmbKNcuyvz7WyksVFkKB4RHwCD3XyuvPEbvqAQY3rAPshWcMLoP2fMFMKHPJ4ZeZXYVUhLv1VMrjPC7PW6V";
+    const ZPUB: &str = "zpub6rFR7y4Q2AijBEqTUquhVz398htDFrtymD9xYYfG1m4wAcvPhXNfE3EfH1r1ADqtfSdVCToUG868RvUUkgDKf31mGDtKsAYz2oz2AGutZYs";
+
+    #[test]
+    fn test_decode_extended_key() {
+        let xpub = Xpub::from_str(XPUB).unwrap();
+        assert_eq!(decode_extended_key(ZPUB).unwrap(), (xpub, KeyFormat::Zpub));
+        assert_eq!(encode_extended_key(&xpub, KeyFormat::Zpub), ZPUB);
+        assert_eq!(Xpub::bip174_deserialize([ZPUB]).unwrap(), xpub);
+        assert_eq!(xpub.bip174_serialize(), [XPUB.to_string()]);
+
+        let tpub = for_network(&xpub, Network::Signet);
+        assert!(tpub.to_string().starts_with("tpub"));
+        for format in ALL_FORMATS.iter() {
+            let encoded = encode_extended_key(&xpub, *format);
+            assert!(encoded.starts_with(format.name()), "{}", encoded);
+            let (decoded, decoded_format) = decode_extended_key(&encoded).unwrap();
+            assert_eq!(decoded_format, *format);
+            assert_eq!(for_network(&decoded, Network::Bitcoin), xpub);
+            assert_eq!(format.for_network(format.network()), *format);
+        }
+        assert_eq!(
+            KeyFormat::ZpubMultisig.for_network(NetworkKind::Test),
+            KeyFormat::VpubMultisig
+        );
+
+        let xprv = "xprv9s21ZrQH143K3QTDL4LXw2F7HEK3wJUD2nW2nRk4stbPy6cq3jPPqjiChkVvvNKmPGJxWUtg6LnF5kejMRNNU3TGtRBeJgk33yuGBxrMPHi";
+        assert!(matches!(
+            decode_extended_key(xprv),
+            Err(ParseError::ExtendedPrivateKey)
+        ));
+        assert!(matches!(
+            decode_extended_key(&base58::encode_check(&[0; 78])),
+            Err(ParseError::ExtendedKeyVersion([0, 0, 0, 0]))
+        ));
+        assert!(matches!(
+            decode_extended_key(&base58::encode_check(&[0; 10])),
+            Err(ParseError::ExtendedKeyLength(10))
+        ));
+        assert!(matches!(
+            decode_extended_key(&ZPUB[1..]),
+            Err(ParseError::Base58(_))
+        ));
+    }
+}
@@ -0,0 +1,123 @@
+use yew::prelude::*;
+
+use crate::app::{App, AppMsg};
+
+/// Name and id of a tab, everything the tab bar needs to render it
+#[derive(Clone, PartialEq)]
+pub struct TabInfo {
+    pub id: usize,
+    pub name: String,
+}
+
+pub struct TabBar {
+    link: ComponentLink<Self>,
+    props: TabBarProps,
+}
+
+#[derive(Clone, Properties)]
+pub struct TabBarProps {
+    pub tabs: Vec<TabInfo>,
+    pub active: usize,
+    pub other: Option<usize>,
+    pub parent: ComponentLink<App>,
+}
+
+impl Component for TabBar {
+    type Message = AppMsg;
+    type Properties = TabBarProps;
+
+    fn create(props: Self::Properties, link: ComponentLink<Self>) -> Self {
+        TabBar { link, props }
+    }
+
+    fn change(&mut self, props: Self::Properties) -> ShouldRender {
+        self.props = props;
+        true
+    }
+
+    fn update(&mut self, msg: Self::Message) -> ShouldRender {
+        self.props.parent.send_message(msg);
+        false
+    }
+
+    fn view(&self) -> Html {
+        let active = self.props.active;
+        let can_close = self.props.tabs.len() > 1;
+        let active_name = self
+            .props
+            .tabs
+            .iter()
+            .find(|tab| tab.id == active)
+            .map(|tab| tab.name.clone())
+            .unwrap_or_default();
+
+        let onrename = self.link.callback(|data| match data {
+            ChangeData::Value(name) => AppMsg::RenameTab(name),
+            _ => AppMsg::RenameTab(String::new()),
+        });
+        let onother = self.link.callback(|data| match data {
+            ChangeData::Select(select) => AppMsg::SelectOtherTab(select.value().parse().ok()),
+            _ => AppMsg::SelectOtherTab(None),
+        });
+
+        html! {
+            <>
+            <ul class="nav nav-tabs mb-2">
+                {
+                    for self.props.tabs.iter().map(|tab| {
+                        let id = tab.id;
+                        html! {
+                            <li class="nav-item">
+                                <a class=classes!("nav-link", if id == active { Some("active") } else { None }) href="#" onclick=self.link.callback(move |e: MouseEvent| { e.prevent_default(); AppMsg::SelectTab(id) })>
+                                    { &tab.name }
+                                    {
+                                        if can_close {
+                                            html! { <i class="bi bi-x ms-1" title="Close tab" onclick=self.link.callback(move |e: MouseEvent| { e.prevent_default(); e.stop_propagation(); AppMsg::CloseTab(id) })></i> }
+                                        } else {
+                                            html! {}
+                                        }
+                                    }
+                                </a>
+                            </li>
+                        }
+                    })
+                }
+                <li class="nav-item">
+                    <a class="nav-link" href="#" title="New tab" onclick=self.link.callback(|e: MouseEvent| { e.prevent_default(); AppMsg::NewTab })><i class="bi bi-plus-lg"></i></a>
+                </li>
+            </ul>
+
+            <div class="d-flex flex-wrap align-items-center mb-3">
+                <div class="input-group input-group-sm me-2 mb-1" style="max-width: 16em;">
+                    <span class="input-group-text">{ "Name" }</span>
+                    <input type="text" class="form-control" value=active_name onchange=onrename />
+                </div>
+                {
+                    if can_close {
+                        html! {
+                            <div class="input-group input-group-sm mb-1" style="max-width: 28em;">
+                                <select class="form-select" onchange=onother>
+                                    <option value="" selected=self.props.other.is_none()>{ "Other tab..." }</option>
+                                    {
+                                        for self.props.tabs.iter().filter(|tab| tab.id != active).map(|tab| html! {
+                                            <option value=tab.id.to_string() selected=self.props.other == Some(tab.id)>{ &tab.name }</option>
+                                        })
+                                    }
+                                </select>
+                                <button class="btn btn-outline-secondary" type="button" disabled=self.props.other.is_none() onclick=self.link.callback(|_| AppMsg::CombineTab)>
+                                    <i class="bi bi-intersect"></i>{ " Combine into this" }
+                                </button>
+                                <button class="btn btn-outline-secondary" type="button" disabled=self.props.other.is_none() onclick=self.link.callback(|_| AppMsg::DiffTab)>
+                                    <i class="bi bi-file-diff"></i>{ " Diff" }
+                                </button>
+                            </div>
+                        }
+                    } else {
+                        html! {}
+                    }
+                }
+            </div>
+            </>
+        }
+    }
+}
@@ -0,0 +1,244 @@
+use bitcoin::hashes::hex::DisplayHex;
+use bitcoin::hashes::Hash;
+use bitcoin::opcodes::all::*;
+use bitcoin::script::Instruction;
+use bitcoin::{psbt, secp256k1, PubkeyHash, Script, ScriptHash, TxIn, WPubkeyHash, WScriptHash};
+
+use crate::lint::spent_output;
+
+/// Standard script templates, with the parameters they commit to
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Template {
+    P2pk(bitcoin::PublicKey),
+    P2pkh(PubkeyHash),
+    P2sh(ScriptHash),
+    P2wpkh(WPubkeyHash),
+    P2wsh(WScriptHash),
+    P2tr(secp256k1::XOnlyPublicKey),
+    Multisig {
+        required: usize,
+        keys: Vec<bitcoin::PublicKey>,
+    },
+    OpReturn(Vec<u8>),
+    Anchor,
+    Nonstandard,
+}
+
+impl Template {
+    pub fn classify(script: &Script) -> Self {
+        let bytes = script.as_bytes();
+        let parsed = if script.is_p2pkh() {
+            PubkeyHash::from_slice(&bytes[3..23])
+                .ok()
+                .map(Template::P2pkh)
+        } else if script.is_p2sh() {
+            ScriptHash::from_slice(&bytes[2..22])
+                .ok()
+                .map(Template::P2sh)
+        } else if script.is_p2wpkh() {
+            WPubkeyHash::from_slice(&bytes[2..22])
+                .ok()
+                .map(Template::P2wpkh)
+        } else if script.is_p2wsh() {
+            WScriptHash::from_slice(&bytes[2..34])
+                .ok()
+                .map(Template::P2wsh)
+        } else if script.is_p2tr() {
+            secp256k1::XOnlyPublicKey::from_slice(&bytes[2..34])
+                .ok()
+                .map(Template::P2tr)
+        } else if bytes == [0x51, 0x02, 0x4e, 0x73] {
+            Some(Template::Anchor)
+        } else if script.is_op_return() {
+            Some(Template::OpReturn(
+                script
+                    .instructions()
+                    .skip(1)
+                    .filter_map(|i| match i {
+                        Ok(Instruction::PushBytes(data)) => Some(data.as_bytes().to_vec()),
+                        _ => None,
+                    })
+                    .flatten()
+                    .collect(),
+            ))
+        } else {
+            parse_p2pk(script).or_else(|| parse_multisig(script))
+        };
+
+        parsed.unwrap_or(Template::Nonstandard)
+    }
+
+    pub fn name(&self) -> String {
+        match self {
+            Template::P2pk(_) => "P2PK".into(),
+            Template::P2pkh(_) => "P2PKH".into(),
+            Template::P2sh(_) => "P2SH".into(),
+            Template::P2wpkh(_) => "P2WPKH".into(),
+            Template::P2wsh(_) => "P2WSH".into(),
+            Template::P2tr(_) => "P2TR".into(),
+            Template::Multisig { required, keys } => {
+                format!("multisig {}-of-{}", required, keys.len())
+            }
+            Template::OpReturn(_) => "OP_RETURN".into(),
+            Template::Anchor => "anchor".into(),
+            Template::Nonstandard => "nonstandard".into(),
+        }
+    }
+
+    /// Whether this is one of the templates that have their own address type
+    fn is_address_type(&self) -> bool {
+        matches!(
+            self,
+            Template::P2pkh(_)
+                | Template::P2sh(_)
+                | Template::P2wpkh(_)
+                | Template::P2wsh(_)
+                | Template::P2tr(_)
+        )
+    }
+
+    /// Label and value of everything the template commits to
+    pub fn parameters(&self) -> Vec<(String, String)> {
+        match self {
+            Template::P2pk(key) => vec![("Public Key".into(), key.to_string())],
+            Template::P2pkh(hash) => vec![("Public Key Hash".into(), hash.to_string())],
+            Template::P2sh(hash) => vec![("Script Hash".into(), hash.to_string())],
+            Template::P2wpkh(hash) => vec![("Public Key Hash".into(), hash.to_string())],
+            Template::P2wsh(hash) => vec![("Script Hash".into(), hash.to_string())],
+            Template::P2tr(key) => vec![("Output Key".into(), key.to_string())],
+            Template::Multisig { keys, .. } => keys
+                .iter()
+                .enumerate()
+                .map(|(i, key)| (format!("Key #{}", i + 1), key.to_string()))
+                .collect(),
+            Template::OpReturn(data) if !data.is_empty() => {
+                vec![("Data".into(), data.to_lower_hex_string())]
+            }
+            _ => vec![],
+        }
+    }
+}
+
+fn parse_p2pk(script: &Script) -> Option<Template> {
+    let mut instructions = script.instructions();
+    let key = match instructions.next()? {
+        Ok(Instruction::PushBytes(data)) => bitcoin::PublicKey::from_slice(data.as_bytes()).ok()?,
+        _ => return None,
+    };
+    match (instructions.next()?, instructions.next()) {
+        (Ok(Instruction::Op(OP_CHECKSIG)), None) => Some(Template::P2pk(key)),
+        _ => None,
+    }
+}
+
+fn parse_multisig(script: &Script) -> Option<Template> {
+    fn small_int(instruction: &Instruction) -> Option<usize> {
+        match instruction {
+            Instruction::Op(op)
+                if (OP_PUSHNUM_1.to_u8()..=OP_PUSHNUM_16.to_u8()).contains(&op.to_u8()) =>
+            {
+                Some((op.to_u8() - OP_PUSHNUM_1.to_u8() + 1) as usize)
+            }
+            _ => None,
+        }
+    }
+
+    let instructions = script.instructions().collect::<Result<Vec<_>, _>>().ok()?;
+    let (last, rest) = instructions.split_last()?;
+    if *last != Instruction::Op(OP_CHECKMULTISIG) || rest.len() < 3 {
+        return None;
+    }
+
+    let required = small_int(&rest[0])?;
+    let total = small_int(&rest[rest.len() - 1])?;
+    let keys = rest[1..rest.len() - 1]
+        .iter()
+        .map(|i| match i {
+            Instruction::PushBytes(data) => bitcoin::PublicKey::from_slice(data.as_bytes()).ok(),
+            _ => None,
+        })
+        .collect::<Option<Vec<_>>>()?;
+
+    match keys.len() == total && required <= total {
+        true => Some(Template::Multisig { required, keys }),
+        false => None,
+    }
+}
+
+/// How an input is spent, following the redeem and witness scripts from the spent output, for
+/// example "P2SH-P2WPKH" or "P2WSH multisig 2-of-3"
+pub fn spend_type(input: &psbt::Input, txin: &TxIn) -> Option<String> {
+    let mut template = Template::classify(&spent_output(input, txin)?.script_pubkey);
+    let mut wrappers = vec![];
+    if let (Template::P2sh(_), Some(script)) = (&template, &input.redeem_script) {
+        wrappers.push("P2SH");
+        template = Template::classify(script);
+    }
+    if let (Template::P2wsh(_), Some(script)) = (&template, &input.witness_script) {
+        wrappers.push("P2WSH");
+        template = Template::classify(script);
+    }
+
+    Some(match (wrappers.is_empty(), template.is_address_type()) {
+        (true, _) => template.name(),
+        (false, true) => format!("{}-{}", wrappers.join("-"), template.name()),
+        (false, false) => format!("{} {}", wrappers.join("-"), template.name()),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use bitcoin::ScriptBuf;
+
+    use crate::model::{parse_psbt, EXAMPLE_PSBTS};
+
+    use super::*;
+
+    #[test]
+    fn test_template() {
+        let classify = |hex: &str| Template::classify(&ScriptBuf::from_hex(hex).unwrap());
+
+        let multisig = classify("522103b1341ccba7683b6af4f1238cd6e97e7167d569fac47f1e48d47541844355bd462103de55d1e1dac805e3f8a58c1fbf9b94c02f3dbaafe127fefca4995f26f82083bd52ae");
+        assert_eq!(multisig.name(), "multisig 2-of-2");
+        assert_eq!(
+            multisig.parameters()[1],
+            (
+                "Key #2".to_string(),
+                "03de55d1e1dac805e3f8a58c1fbf9b94c02f3dbaafe127fefca4995f26f82083bd".to_string()
+            )
+        );
+        assert_eq!(
+            classify("0014d85c2b71d0060b09c9886aeb815e50991dda124d").name(),
+            "P2WPKH"
+        );
+        assert_eq!(classify("51024e73"), Template::Anchor);
+        assert_eq!(
+            classify("6a04deadbeef"),
+            Template::OpReturn(vec![0xde, 0xad, 0xbe, 0xef])
+        );
+        assert_eq!(
+            classify("2103b1341ccba7683b6af4f1238cd6e97e7167d569fac47f1e48d47541844355bd46ac")
+                .name(),
+            "P2PK"
+        );
+        assert_eq!(classify("5152ae"), Template::Nonstandard);
+    }
+
+    #[test]
+    fn test_spend_type() {
+        let psbt = parse_psbt(EXAMPLE_PSBTS[1].1).unwrap();
+        let txin = &psbt.unsigned_tx.input[0];
+
+        let mut input = psbt.inputs[0].clone();
+        assert_eq!(
+            spend_type(&input, txin).as_deref(),
+            Some("P2SH-P2WSH multisig 2-of-2")
+        );
+        input.witness_script = None;
+        assert_eq!(spend_type(&input, txin).as_deref(), Some("P2SH-P2WSH"));
+        input.redeem_script = None;
+        assert_eq!(spend_type(&input, txin).as_deref(), Some("P2SH"));
+        input.witness_utxo = None;
+        assert_eq!(spend_type(&input, txin), None);
+    }
+}
@@ -0,0 +1,157 @@
+use std::fmt;
+use std::str::FromStr;
+
+use bitcoin::{Amount, Denomination};
+
+/// Unit used to show and enter amounts
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum Unit {
+    #[default]
+    Btc,
+    MBtc,
+    Bits,
+    Sats,
+}
+
+pub const ALL_UNITS: [Unit; 4] = [Unit::Btc, Unit::MBtc, Unit::Bits, Unit::Sats];
+
+impl Unit {
+    fn denomination(&self) -> Denomination {
+        match self {
+            Unit::Btc => Denomination::Bitcoin,
+            Unit::MBtc => Denomination::MilliBitcoin,
+            Unit::Bits => Denomination::Bit,
+            Unit::Sats => Denomination::Satoshi,
+        }
+    }
+
+    fn decimals(&self) -> u32 {
+        match self {
+            Unit::Btc => 8,
+            Unit::MBtc => 5,
+            Unit::Bits => 2,
+            Unit::Sats => 0,
+        }
+    }
+
+    /// The amount in this unit with its digits grouped by thousands, without the unit
+    pub fn format_value(&self, amount: Amount) -> String {
+        let scale = 10u64.pow(self.decimals());
+        let integer = group_digits(&(amount.to_sat() / scale).to_string());
+        match self.decimals() {
+            0 => integer,
+            decimals => format!(
+                "{}.{:0width$}",
+                integer,
+                amount.to_sat() % scale,
+                width = decimals as usize
+            ),
+        }
+    }
+
+    /// The amount in this unit, followed by the unit
+    pub fn format(&self, amount: Amount) -> String {
+        format!("{} {}", self.format_value(amount), self)
+    }
+}
+
+impl fmt::Display for Unit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            Unit::Btc => "BTC",
+            Unit::MBtc => "mBTC",
+            Unit::Bits => "bits",
+            Unit::Sats => "sats",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+impl FromStr for Unit {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "btc" => Ok(Unit::Btc),
+            "mbtc" => Ok(Unit::MBtc),
+            "bits" | "bit" | "ubtc" | "µbtc" => Ok(Unit::Bits),
+            "sats" | "sat" | "satoshi" | "satoshis" => Ok(Unit::Sats),
+            _ => Err(format!("unknown unit \"{}\"", s)),
+        }
+    }
+}
+
+/// Separate the thousands of a string of digits with commas
+fn group_digits(digits: &str) -> String {
+    let mut out = String::with_capacity(digits.len() * 4 / 3);
+    for (i, c) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            out.push(',');
+        }
+        out.push(c);
+    }
+    out
+}
+
+/// Parse an amount written in any unit, like `0.5 BTC` or `50,000 sats`. Without a unit the
+/// amount is taken to be in `default`
+pub fn parse_amount(s: &str, default: Unit) -> Result<Amount, String> {
+    let s = s.trim();
+    let split = s.find(|c: char| c.is_alphabetic()).unwrap_or(s.len());
+    let (value, unit) = s.split_at(split);
+    let unit = match unit.trim() {
+        "" => default,
+        unit => unit.parse()?,
+    };
+
+    let value = value
+        .chars()
+        .filter(|c| *c != ',' && *c != '_' && !c.is_whitespace())
+        .collect::<String>();
+    if value.is_empty() {
+        return Err("missing amount".to_string());
+    }
+    Amount::from_str_in(&value, unit.denomination()).map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format() {
+        let amount = Amount::from_sat(123_456_789);
+        assert_eq!(Unit::Btc.format(amount), "1.23456789 BTC");
+        assert_eq!(Unit::MBtc.format(amount), "1,234.56789 mBTC");
+        assert_eq!(Unit::Bits.format(amount), "1,234,567.89 bits");
+        assert_eq!(Unit::Sats.format(amount), "123,456,789 sats");
+        assert_eq!(Unit::Btc.format_value(Amount::from_sat(1)), "0.00000001");
+        assert_eq!(Unit::Sats.format_value(Amount::ZERO), "0");
+
+        for unit in ALL_UNITS.iter() {
+            assert_eq!(unit.to_string().parse::<Unit>(), Ok(*unit));
+        }
+    }
+
+    #[test]
+    fn test_parse_amount() {
+        let amount = Amount::from_sat(150_000);
+        assert_eq!(parse_amount("0.0015", Unit::Btc), Ok(amount));
+        assert_eq!(parse_amount("150,000", Unit::Sats), Ok(amount));
+        assert_eq!(parse_amount("150000 sat", Unit::Btc), Ok(amount));
+        assert_eq!(parse_amount("1.5mBTC", Unit::Sats), Ok(amount));
+        assert_eq!(parse_amount(" 1,500.00 bits ", Unit::Btc), Ok(amount));
+        assert_eq!(parse_amount("0.0015 btc", Unit::Sats), Ok(amount));
+
+        assert_eq!(
+            parse_amount("1 doge", Unit::Btc).unwrap_err(),
+            "unknown unit \"doge\""
+        );
+        assert_eq!(
+            parse_amount("sats", Unit::Btc).unwrap_err(),
+            "missing amount"
+        );
+        assert!(parse_amount("0.5 sats", Unit::Btc).is_err());
+        assert!(parse_amount("-1", Unit::Btc).is_err());
+    }
+}
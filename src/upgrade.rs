@@ -0,0 +1,165 @@
+#[allow(unused_imports)]
+use log::*;
+
+use yew::prelude::*;
+
+use bitcoin::{psbt, Transaction};
+
+use crate::app::Psbt;
+use crate::lint::missing_non_witness_utxo;
+use crate::model::{Field, NonWitnessUtxo, PsbtInputMsg, PsbtMessage};
+
+/// Set `tx` as the non_witness_utxo of every input that spends one of its outputs
+pub fn insert_prev_tx(psbt: &psbt::Psbt, tx: &Transaction) -> Result<PsbtMessage, String> {
+    let txid = tx.txid();
+    let changes = psbt
+        .unsigned_tx
+        .input
+        .iter()
+        .enumerate()
+        .filter(|(_, txin)| txin.previous_output.txid == txid)
+        .map(|(index, _)| {
+            PsbtMessage::ChangeInput(
+                index,
+                PsbtInputMsg::ChangeNonWitnessUtxo(NonWitnessUtxo::from(Some(tx.clone()))),
+            )
+        })
+        .collect::<Vec<_>>();
+
+    match changes.is_empty() {
+        true => Err(format!("No input spends {}", txid)),
+        false => Ok(PsbtMessage::Labelled(
+            format!("Added previous transaction {}", txid),
+            Box::new(PsbtMessage::Batch(changes)),
+        )),
+    }
+}
+
+pub struct NonWitnessUtxoUpgrade {
+    link: ComponentLink<Self>,
+    props: NonWitnessUtxoUpgradeProps,
+
+    hex: String,
+    error: Option<String>,
+}
+
+#[derive(Clone, Properties)]
+pub struct NonWitnessUtxoUpgradeProps {
+    pub psbt: psbt::Psbt,
+    pub parent: ComponentLink<Psbt>,
+}
+
+pub enum NonWitnessUtxoUpgradeMsg {
+    SetHex(String),
+    Insert,
+}
+
+impl Component for NonWitnessUtxoUpgrade {
+    type Message = NonWitnessUtxoUpgradeMsg;
+    type Properties = NonWitnessUtxoUpgradeProps;
+
+    fn create(props: Self::Properties, link: ComponentLink<Self>) -> Self {
+        NonWitnessUtxoUpgrade {
+            link,
+            props,
+            hex: String::new(),
+            error: None,
+        }
+    }
+
+    fn change(&mut self, props: Self::Properties) -> ShouldRender {
+        self.props = props;
+        true
+    }
+
+    fn update(&mut self, msg: Self::Message) -> ShouldRender {
+        match msg {
+            NonWitnessUtxoUpgradeMsg::SetHex(hex) => self.hex = hex,
+            NonWitnessUtxoUpgradeMsg::Insert => {
+                let result = Transaction::bip174_deserialize([self.hex.trim()])
+                    .map_err(|e| e.to_string())
+                    .and_then(|tx| insert_prev_tx(&self.props.psbt, &tx));
+                match result {
+                    Ok(msg) => {
+                        self.props.parent.send_message(msg);
+                        self.hex.clear();
+                        self.error = None;
+                    }
+                    Err(e) => self.error = Some(e),
+                }
+            }
+        }
+
+        true
+    }
+
+    fn view(&self) -> Html {
+        let missing = missing_non_witness_utxo(&self.props.psbt);
+        if missing.is_empty() {
+            return html! {};
+        }
+
+        let inputs = missing
+            .iter()
+            .map(|i| format!("#{}", i))
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        html! {
+            <div class="alert alert-warning mt-3">
+                <h6 class="alert-heading"><i class="bi bi-shield-exclamation"></i>{ " Missing non_witness_utxo" }</h6>
+                <p>
+                    { format!("The segwit v0 input(s) {} only have the witness_utxo. ", inputs) }
+                    { "Because of the fee attack on segwit v0 signatures most hardware wallets refuse to sign them without the full previous transaction." }
+                </p>
+                <div class="input-group">
+                    <textarea class=classes!("form-control", "font-monospace", self.error.as_ref().map(|_| "is-invalid")) rows="2" placeholder="Previous transaction hex" spellcheck="false" value=self.hex.clone() oninput=self.link.callback(|e: InputData| NonWitnessUtxoUpgradeMsg::SetHex(e.value))></textarea>
+                    <button class="btn btn-outline-dark" type="button" disabled=self.hex.trim().is_empty() onclick=self.link.callback(|_| NonWitnessUtxoUpgradeMsg::Insert)>
+                        <i class="bi bi-box-arrow-in-down"></i>{ " Insert" }
+                    </button>
+                    { self.error.as_ref().map(|e| html! { <div class="invalid-feedback">{ e }</div> }).unwrap_or_default() }
+                </div>
+            </div>
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::model::{parse_psbt, EXAMPLE_PSBTS};
+
+    use super::*;
+
+    #[test]
+    fn test_insert_prev_tx() {
+        let mut psbt = parse_psbt(EXAMPLE_PSBTS[1].1).unwrap();
+        // Only keep the witness UTXO of the input
+        psbt.inputs[0] = psbt::Input {
+            witness_utxo: psbt.inputs[0].witness_utxo.clone(),
+            ..Default::default()
+        };
+        // P2SH without the redeem script, we can't tell whether it's segwit
+        assert!(missing_non_witness_utxo(&psbt).is_empty());
+
+        psbt.inputs[0].redeem_script = Some(
+            bitcoin::ScriptBuf::from_hex(
+                "0020771fd18ad459666dd49f3d564e3dbc42f4c84774e360ada16816a8ed488d5681",
+            )
+            .unwrap(),
+        );
+        assert_eq!(missing_non_witness_utxo(&psbt), vec![0]);
+
+        let mut prev_tx = psbt.unsigned_tx.clone();
+        prev_tx.output[0] = psbt.inputs[0].witness_utxo.clone().unwrap();
+        assert!(insert_prev_tx(&psbt, &prev_tx).is_err());
+
+        psbt.unsigned_tx.input[0].previous_output = bitcoin::OutPoint::new(prev_tx.txid(), 0);
+        let mut wrapped = Some(psbt);
+        insert_prev_tx(wrapped.as_ref().unwrap(), &prev_tx)
+            .unwrap()
+            .apply_to(&mut wrapped);
+        let psbt = wrapped.unwrap();
+        assert_eq!(psbt.inputs[0].non_witness_utxo, Some(prev_tx));
+        assert!(missing_non_witness_utxo(&psbt).is_empty());
+    }
+}
@@ -0,0 +1,665 @@
+use std::convert::TryInto;
+
+use bitcoin::hashes::{sha256, Hash};
+
+/// The UR type of a PSBT, as defined in BCR-2020-006
+pub const PSBT_UR_TYPE: &str = "crypto-psbt";
+
+/// Largest message we're willing to reassemble, well above the size of any PSBT shown as QR codes
+const MAX_MESSAGE_LEN: usize = 1 << 24;
+
+#[rustfmt::skip]
+const BYTEWORDS: [&str; 256] = [
+    "able", "acid", "also", "apex", "aqua", "arch", "atom", "aunt", "away", "axis", "back", "bald", "barn", "belt", "beta", "bias",
+    "blue", "body", "brag", "brew", "bulb", "buzz", "calm", "cash", "cats", "chef", "city", "claw", "code", "cola", "cook", "cost",
+    "crux", "curl", "cusp", "cyan", "dark", "data", "days", "deli", "dice", "diet", "door", "down", "draw", "drop", "drum", "dull",
+    "duty", "each", "easy", "echo", "edge", "epic", "even", "exam", "exit", "eyes", "fact", "fair", "fern", "figs", "film", "fish",
+    "fizz", "flap", "flew", "flux", "foxy", "free", "frog", "fuel", "fund", "gala", "game", "gear", "gems", "gift", "girl", "glow",
+    "good", "gray", "grim", "guru", "gush", "gyro", "half", "hang", "hard", "hawk", "heat", "help", "high", "hill", "holy", "hope",
+    "horn", "huts", "iced", "idea", "idle", "inch", "inky", "into", "iris", "iron", "item", "jade", "jazz", "join", "jolt", "jowl",
+    "judo", "jugs", "jump", "junk", "jury", "keep", "keno", "kept", "keys", "kick", "kiln", "king", "kite", "kiwi", "knob", "lamb",
+    "lava", "lazy", "leaf", "legs", "liar", "limp", "lion", "list", "logo", "loud", "love", "luau", "luck", "lung", "main", "many",
+    "math", "maze", "memo", "menu", "meow", "mild", "mint", "miss", "monk", "nail", "navy", "need", "news", "next", "noon", "note",
+    "numb", "obey", "oboe", "omit", "onyx", "open", "oval", "owls", "paid", "part", "peck", "play", "plus", "poem", "pool", "pose",
+    "puff", "puma", "purr", "quad", "quiz", "race", "ramp", "real", "redo", "rich", "road", "rock", "roof", "ruby", "ruin", "runs",
+    "rust", "safe", "saga", "scar", "sets", "silk", "skew", "slot", "soap", "solo", "song", "stub", "surf", "swan", "taco", "task",
+    "taxi", "tent", "tied", "time", "tiny", "toil", "tomb", "toys", "trip", "tuna", "twin", "ugly", "undo", "unit", "urge", "user",
+    "vast", "very", "veto", "vial", "vibe", "view", "visa", "void", "vows", "wall", "wand", "warm", "wasp", "wave", "waxy", "webs",
+    "what", "when", "whiz", "wolf", "work", "yank", "yawn", "yell", "yoga", "yurt", "zaps", "zero", "zest", "zinc", "zone", "zoom",
+];
+
+/// Encode `data` with the minimal form of bytewords (first and last letter of each word),
+/// followed by its CRC32 checksum
+pub fn bytewords_minimal(data: &[u8]) -> String {
+    let checksum = crc32fast::hash(data).to_be_bytes();
+    data.iter()
+        .chain(checksum.iter())
+        .map(|b| {
+            let word = BYTEWORDS[*b as usize].as_bytes();
+            format!("{}{}", word[0] as char, word[3] as char)
+        })
+        .collect()
+}
+
+/// Decode the minimal bytewords in `text`, checking and removing the CRC32 at the end
+pub fn bytewords_minimal_decode(text: &str) -> Result<Vec<u8>, String> {
+    if !text.len().is_multiple_of(2) || !text.is_ascii() {
+        return Err("invalid bytewords".to_string());
+    }
+
+    let mut data = text
+        .as_bytes()
+        .chunks(2)
+        .map(|pair| {
+            let pair = pair.to_ascii_lowercase();
+            BYTEWORDS
+                .iter()
+                .position(|w| w.as_bytes()[0] == pair[0] && w.as_bytes()[3] == pair[1])
+                .map(|b| b as u8)
+                .ok_or_else(|| format!("invalid byteword \"{}\"", String::from_utf8_lossy(&pair)))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    if data.len() < 4 {
+        return Err("missing checksum".to_string());
+    }
+    let checksum = data.split_off(data.len() - 4);
+    if crc32fast::hash(&data).to_be_bytes() != checksum[..] {
+        return Err("invalid checksum".to_string());
+    }
+    Ok(data)
+}
+
+/// Head of a CBOR data item with the smallest encoding of `value`
+fn cbor_head(major: u8, value: u64, out: &mut Vec<u8>) {
+    let major = major << 5;
+    match value {
+        0..=23 => out.push(major | value as u8),
+        24..=0xff => out.extend([major | 24, value as u8]),
+        0x100..=0xffff => {
+            out.push(major | 25);
+            out.extend((value as u16).to_be_bytes());
+        }
+        0x1_0000..=0xffff_ffff => {
+            out.push(major | 26);
+            out.extend((value as u32).to_be_bytes());
+        }
+        _ => {
+            out.push(major | 27);
+            out.extend(value.to_be_bytes());
+        }
+    }
+}
+
+/// CBOR byte string containing `data`, which is all a `crypto-psbt` is made of
+pub fn cbor_bytes(data: &[u8]) -> Vec<u8> {
+    let mut out = vec![];
+    cbor_head(2, data.len() as u64, &mut out);
+    out.extend(data);
+    out
+}
+
+/// Read the head of a CBOR data item from the start of `data`, returning its major type and value
+fn cbor_read_head(data: &mut &[u8]) -> Result<(u8, u64), String> {
+    let (first, rest) = data.split_first().ok_or("unexpected end of CBOR data")?;
+    let len = match first & 0x1f {
+        0..=23 => 0,
+        24 => 1,
+        25 => 2,
+        26 => 4,
+        27 => 8,
+        _ => return Err("unsupported CBOR item".to_string()),
+    };
+    if rest.len() < len {
+        return Err("unexpected end of CBOR data".to_string());
+    }
+
+    let value = match len {
+        0 => (first & 0x1f) as u64,
+        _ => rest[..len].iter().fold(0, |acc, b| acc << 8 | *b as u64),
+    };
+    *data = &rest[len..];
+    Ok((first >> 5, value))
+}
+
+fn cbor_read_uint(data: &mut &[u8]) -> Result<u64, String> {
+    match cbor_read_head(data)? {
+        (0, value) => Ok(value),
+        _ => Err("expected a CBOR unsigned integer".to_string()),
+    }
+}
+
+/// Read a CBOR byte string from the start of `data`
+pub fn cbor_read_bytes(data: &mut &[u8]) -> Result<Vec<u8>, String> {
+    let len = match cbor_read_head(data)? {
+        (2, len) => len as usize,
+        _ => return Err("expected a CBOR byte string".to_string()),
+    };
+    if data.len() < len {
+        return Err("unexpected end of CBOR data".to_string());
+    }
+
+    let (bytes, rest) = data.split_at(len);
+    *data = rest;
+    Ok(bytes.to_vec())
+}
+
+/// The Xoshiro256** generator used by the fountain encoder to pick the fragments mixed in a part
+struct Xoshiro256([u64; 4]);
+
+impl Xoshiro256 {
+    fn from_seed(seed: &[u8]) -> Self {
+        let digest = sha256::Hash::hash(seed).to_byte_array();
+        let mut state = [0; 4];
+        for (s, chunk) in state.iter_mut().zip(digest.chunks(8)) {
+            *s = u64::from_be_bytes(chunk.try_into().unwrap());
+        }
+        Xoshiro256(state)
+    }
+
+    fn next(&mut self) -> u64 {
+        let s = &mut self.0;
+        let result = s[1].wrapping_mul(5).rotate_left(7).wrapping_mul(9);
+        let t = s[1] << 17;
+
+        s[2] ^= s[0];
+        s[3] ^= s[1];
+        s[1] ^= s[2];
+        s[0] ^= s[3];
+        s[2] ^= t;
+        s[3] = s[3].rotate_left(45);
+
+        result
+    }
+
+    fn next_double(&mut self) -> f64 {
+        self.next() as f64 / (u64::MAX as f64 + 1.0)
+    }
+
+    fn next_int(&mut self, low: u64, high: u64) -> u64 {
+        (self.next_double() * (high - low + 1) as f64) as u64 + low
+    }
+}
+
+/// Walker's alias method to sample from a discrete distribution
+struct RandomSampler {
+    probs: Vec<f64>,
+    aliases: Vec<usize>,
+}
+
+impl RandomSampler {
+    fn new(weights: &[f64]) -> Self {
+        let n = weights.len();
+        let sum = weights.iter().sum::<f64>();
+        let mut scaled = weights
+            .iter()
+            .map(|w| w * n as f64 / sum)
+            .collect::<Vec<_>>();
+
+        let (mut small, mut large) = (vec![], vec![]);
+        for i in (0..n).rev() {
+            match scaled[i] < 1.0 {
+                true => small.push(i),
+                false => large.push(i),
+            }
+        }
+
+        let mut probs = vec![0.0; n];
+        let mut aliases = vec![0; n];
+        while !small.is_empty() && !large.is_empty() {
+            let (a, g) = (small.pop().unwrap(), large.pop().unwrap());
+            probs[a] = scaled[a];
+            aliases[a] = g;
+            scaled[g] += scaled[a] - 1.0;
+            match scaled[g] < 1.0 {
+                true => small.push(g),
+                false => large.push(g),
+            }
+        }
+        for i in large.into_iter().chain(small) {
+            probs[i] = 1.0;
+        }
+
+        RandomSampler { probs, aliases }
+    }
+
+    fn next(&self, rng: &mut Xoshiro256) -> usize {
+        let r1 = rng.next_double();
+        let r2 = rng.next_double();
+        let i = (self.probs.len() as f64 * r1) as usize;
+        match r2 < self.probs[i] {
+            true => i,
+            false => self.aliases[i],
+        }
+    }
+}
+
+fn shuffled<T>(mut items: Vec<T>, rng: &mut Xoshiro256) -> Vec<T> {
+    let mut result = Vec::with_capacity(items.len());
+    while !items.is_empty() {
+        let index = rng.next_int(0, items.len() as u64 - 1) as usize;
+        result.push(items.remove(index));
+    }
+    result
+}
+
+/// Indexes of the fragments XORed together in part `seq_num`. The first `seq_len` parts carry a
+/// single fragment each, the following ones a random mix so that any subset of them lets the
+/// receiver recover the missed parts
+pub fn choose_fragments(seq_num: u32, seq_len: usize, checksum: u32) -> Vec<usize> {
+    if seq_num as usize <= seq_len {
+        return vec![seq_num as usize - 1];
+    }
+
+    let seed = [seq_num.to_be_bytes(), checksum.to_be_bytes()].concat();
+    let mut rng = Xoshiro256::from_seed(&seed);
+    let weights = (1..=seq_len).map(|i| 1.0 / i as f64).collect::<Vec<_>>();
+    let degree = RandomSampler::new(&weights).next(&mut rng) + 1;
+
+    let mut indexes = shuffled((0..seq_len).collect(), &mut rng);
+    indexes.truncate(degree);
+    indexes
+}
+
+/// Largest fragment length not above `max_len` that splits the message in fragments of the same
+/// length
+fn nominal_fragment_len(message_len: usize, min_len: usize, max_len: usize) -> usize {
+    let max_count = (message_len / min_len).max(1);
+    (1..=max_count)
+        .map(|count| message_len.div_ceil(count))
+        .find(|len| *len <= max_len)
+        .unwrap_or(min_len)
+}
+
+/// Fountain encoder producing the parts of a multi-part UR (BCR-2020-005)
+#[derive(Clone, Debug)]
+pub struct UrEncoder {
+    ur_type: String,
+    message: Vec<u8>,
+    checksum: u32,
+    fragments: Vec<Vec<u8>>,
+}
+
+impl UrEncoder {
+    /// Split the CBOR encoded `message` in fragments of at most `max_fragment_len` bytes
+    pub fn new(ur_type: &str, message: Vec<u8>, max_fragment_len: usize) -> Self {
+        let fragment_len = nominal_fragment_len(message.len(), 10, max_fragment_len.max(10));
+        let mut padded = message.clone();
+        padded.resize(message.len().div_ceil(fragment_len) * fragment_len, 0);
+
+        UrEncoder {
+            ur_type: ur_type.to_string(),
+            checksum: crc32fast::hash(&message),
+            fragments: padded.chunks(fragment_len).map(<[u8]>::to_vec).collect(),
+            message,
+        }
+    }
+
+    pub fn seq_len(&self) -> usize {
+        self.fragments.len()
+    }
+
+    /// The UR of part `seq_num`, starting from 1. Parts after `seq_len` never repeat and can be
+    /// generated indefinitely
+    pub fn part(&self, seq_num: u32) -> String {
+        if self.seq_len() == 1 {
+            return format!("ur:{}/{}", self.ur_type, bytewords_minimal(&self.message));
+        }
+
+        let mut data = vec![0; self.fragments[0].len()];
+        for index in choose_fragments(seq_num, self.seq_len(), self.checksum) {
+            for (d, f) in data.iter_mut().zip(&self.fragments[index]) {
+                *d ^= f;
+            }
+        }
+
+        let mut cbor = vec![];
+        cbor_head(4, 5, &mut cbor);
+        cbor_head(0, seq_num as u64, &mut cbor);
+        cbor_head(0, self.seq_len() as u64, &mut cbor);
+        cbor_head(0, self.message.len() as u64, &mut cbor);
+        cbor_head(0, self.checksum as u64, &mut cbor);
+        cbor.extend(cbor_bytes(&data));
+
+        format!(
+            "ur:{}/{}-{}/{}",
+            self.ur_type,
+            seq_num,
+            self.seq_len(),
+            bytewords_minimal(&cbor)
+        )
+    }
+}
+
+/// A part of a multi-part UR, as carried by the CBOR array of BCR-2020-005
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct FountainPart {
+    seq_num: u32,
+    seq_len: usize,
+    message_len: usize,
+    checksum: u32,
+    data: Vec<u8>,
+}
+
+impl FountainPart {
+    fn from_cbor(mut cbor: &[u8]) -> Result<Self, String> {
+        let data = &mut cbor;
+        if cbor_read_head(data)? != (4, 5) {
+            return Err("expected a CBOR array of five items".to_string());
+        }
+
+        let part = FountainPart {
+            seq_num: cbor_read_uint(data)?
+                .try_into()
+                .map_err(|_| "sequence number too large")?,
+            seq_len: cbor_read_uint(data)?
+                .try_into()
+                .map_err(|_| "sequence length too large")?,
+            message_len: cbor_read_uint(data)?
+                .try_into()
+                .map_err(|_| "message length too large")?,
+            checksum: cbor_read_uint(data)?
+                .try_into()
+                .map_err(|_| "checksum too large")?,
+            data: cbor_read_bytes(data)?,
+        };
+        if part.seq_num == 0 || part.seq_len == 0 || part.data.is_empty() {
+            return Err("empty part".to_string());
+        }
+        if part.message_len > MAX_MESSAGE_LEN {
+            return Err("the message is too large".to_string());
+        }
+        // Every fragment has the same length, so the length of the message fixes their number
+        match part.seq_len.checked_mul(part.data.len()) {
+            Some(len) if len < part.message_len => {
+                Err("the fragments are shorter than the message".to_string())
+            }
+            Some(_) if part.seq_len <= part.message_len.div_ceil(part.data.len()) => Ok(part),
+            _ => Err("too many fragments for the length of the message".to_string()),
+        }
+    }
+}
+
+/// Fountain decoder collecting the parts of a UR, in any order and with any repetition
+#[derive(Clone, Debug, Default)]
+pub struct UrDecoder {
+    ur_type: Option<String>,
+    /// Common fields of every part received so far: sequence length, message length and checksum
+    params: Option<(usize, usize, u32)>,
+    fragments: Vec<Option<Vec<u8>>>,
+    /// Parts that still mix more than one unknown fragment
+    mixed: Vec<(Vec<usize>, Vec<u8>)>,
+    message: Option<Vec<u8>>,
+}
+
+impl UrDecoder {
+    /// Add a part, either `ur:type/seq-len/bytewords` or the single-part form `ur:type/bytewords`
+    pub fn receive(&mut self, ur: &str) -> Result<(), String> {
+        let ur = ur.trim().to_lowercase();
+        let mut components = ur
+            .strip_prefix("ur:")
+            .ok_or("missing the \"ur:\" prefix")?
+            .split('/');
+        let ur_type = components.next().unwrap_or_default().to_string();
+        let (sequence, payload) = match (components.next(), components.next(), components.next()) {
+            (Some(payload), None, _) => (None, payload),
+            (Some(sequence), Some(payload), None) => (Some(sequence), payload),
+            _ => return Err("invalid UR".to_string()),
+        };
+
+        match &self.ur_type {
+            Some(expected) if expected != &ur_type => {
+                return Err(format!(
+                    "expected a \"{}\" UR, got \"{}\"",
+                    expected, ur_type
+                ))
+            }
+            _ => self.ur_type = Some(ur_type),
+        }
+
+        let payload = bytewords_minimal_decode(payload)?;
+        match sequence {
+            None => {
+                self.message = Some(payload);
+                Ok(())
+            }
+            Some(_) => self.receive_part(FountainPart::from_cbor(&payload)?),
+        }
+    }
+
+    fn receive_part(&mut self, part: FountainPart) -> Result<(), String> {
+        let params = (part.seq_len, part.message_len, part.checksum);
+        match self.params {
+            Some(expected) if expected != params => {
+                return Err("the part belongs to a different message".to_string())
+            }
+            Some(_) => {}
+            None => {
+                self.params = Some(params);
+                self.fragments = vec![None; part.seq_len];
+            }
+        }
+        if self.message.is_some() {
+            return Ok(());
+        }
+
+        let indexes = choose_fragments(part.seq_num, part.seq_len, part.checksum);
+        self.mixed.push((indexes, part.data));
+        self.reduce();
+
+        if self.fragments.iter().all(Option::is_some) {
+            let mut message = self
+                .fragments
+                .iter()
+                .flatten()
+                .flatten()
+                .cloned()
+                .collect::<Vec<_>>();
+            message.truncate(part.message_len);
+            if crc32fast::hash(&message) != part.checksum {
+                return Err("invalid checksum of the reassembled message".to_string());
+            }
+            self.message = Some(message);
+        }
+        Ok(())
+    }
+
+    /// Remove the known fragments from the mixed parts, until no part is left with a single
+    /// unknown fragment
+    fn reduce(&mut self) {
+        loop {
+            let fragments = &self.fragments;
+            for (indexes, data) in &mut self.mixed {
+                indexes.retain(|i| match &fragments[*i] {
+                    Some(fragment) => {
+                        data.iter_mut().zip(fragment).for_each(|(d, f)| *d ^= f);
+                        false
+                    }
+                    None => true,
+                });
+            }
+            self.mixed.retain(|(indexes, _)| !indexes.is_empty());
+
+            match self
+                .mixed
+                .iter()
+                .position(|(indexes, _)| indexes.len() == 1)
+            {
+                Some(pos) => {
+                    let (indexes, data) = self.mixed.remove(pos);
+                    self.fragments[indexes[0]] = Some(data);
+                }
+                None => break,
+            }
+        }
+    }
+
+    pub fn ur_type(&self) -> Option<&str> {
+        self.ur_type.as_deref()
+    }
+
+    /// Number of fragments the message is split into, once the first part has been received
+    pub fn seq_len(&self) -> Option<usize> {
+        self.params.map(|(seq_len, _, _)| seq_len)
+    }
+
+    /// Fragments that haven't been recovered yet, as the sequence numbers of the parts that carry
+    /// them alone
+    pub fn missing(&self) -> Vec<usize> {
+        match self.message {
+            Some(_) => vec![],
+            None => self
+                .fragments
+                .iter()
+                .enumerate()
+                .filter(|(_, f)| f.is_none())
+                .map(|(i, _)| i + 1)
+                .collect(),
+        }
+    }
+
+    /// The reassembled message, once every fragment has been received
+    pub fn message(&self) -> Option<&[u8]> {
+        self.message.as_deref()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bytewords() {
+        assert_eq!(
+            bytewords_minimal(&[0x00, 0x01, 0x02, 0x80, 0xff]),
+            "aeadaolazmjendeoti"
+        );
+
+        let mut minimal = BYTEWORDS
+            .iter()
+            .map(|w| (&w[..1], &w[3..]))
+            .collect::<Vec<_>>();
+        minimal.sort_unstable();
+        minimal.dedup();
+        assert_eq!(minimal.len(), 256);
+    }
+
+    #[test]
+    fn test_xoshiro() {
+        let mut rng = Xoshiro256::from_seed(b"Wolf");
+        let numbers = (0..10).map(|_| rng.next() % 100).collect::<Vec<_>>();
+        assert_eq!(numbers, vec![42, 81, 85, 8, 82, 84, 76, 73, 70, 88]);
+
+        let mut rng = Xoshiro256::from_seed(b"Wolf");
+        assert_eq!(
+            shuffled((1..=10).collect(), &mut rng),
+            vec![6, 4, 9, 3, 10, 5, 7, 8, 1, 2]
+        );
+    }
+
+    #[test]
+    fn test_encoder() {
+        let mut rng = Xoshiro256::from_seed(b"Wolf");
+        let message = (0..256)
+            .map(|_| rng.next_int(0, 255) as u8)
+            .collect::<Vec<_>>();
+        let encoder = UrEncoder::new("bytes", cbor_bytes(&message), 30);
+
+        assert_eq!(encoder.seq_len(), 9);
+        assert_eq!(encoder.part(1), "ur:bytes/1-9/lpadascfadaxcywenbpljkhdcahkadaemejtswhhylkepmykhhtsytsnoyoyaxaedsuttydmmhhpktpmsrjtdkgslpgh");
+        assert_eq!(choose_fragments(5, 9, encoder.checksum), vec![4]);
+        assert!(encoder
+            .part(10)
+            .starts_with("ur:bytes/10-9/lpbkascfadaxcywenbpljkhdca"));
+
+        let single = UrEncoder::new(PSBT_UR_TYPE, cbor_bytes(&[0xde, 0xad]), 100);
+        assert_eq!(single.seq_len(), 1);
+        assert_eq!(
+            single.part(1),
+            format!("ur:crypto-psbt/{}", bytewords_minimal(&[0x42, 0xde, 0xad]))
+        );
+    }
+
+    #[test]
+    fn test_decoder() {
+        assert_eq!(
+            bytewords_minimal_decode("AEADAOLAZMJENDEOTI").unwrap(),
+            vec![0x00, 0x01, 0x02, 0x80, 0xff]
+        );
+        assert_eq!(
+            bytewords_minimal_decode("aeadaolazmjendeota").unwrap_err(),
+            "invalid checksum"
+        );
+        assert_eq!(
+            cbor_read_bytes(&mut &cbor_bytes(&[1; 300])[..]).unwrap(),
+            vec![1; 300]
+        );
+
+        let message = (0..=255).cycle().take(1000).collect::<Vec<u8>>();
+        let encoder = UrEncoder::new(PSBT_UR_TYPE, cbor_bytes(&message), 100);
+        let seq_len = encoder.seq_len() as u32;
+        assert!(seq_len > 2);
+
+        // Skip two of the simple parts and recover them from the mixed ones
+        let mut decoder = UrDecoder::default();
+        for seq_num in (3..=seq_len).chain(seq_len + 1..) {
+            decoder
+                .receive(&encoder.part(seq_num).to_uppercase())
+                .unwrap();
+            if decoder.message().is_some() {
+                break;
+            }
+            assert!(seq_num < 10 * seq_len);
+        }
+        assert!(decoder.missing().is_empty());
+        assert_eq!(
+            cbor_read_bytes(&mut decoder.message().unwrap()).unwrap(),
+            message
+        );
+
+        let mut decoder = UrDecoder::default();
+        decoder.receive(&encoder.part(2)).unwrap();
+        assert_eq!(decoder.seq_len(), Some(seq_len as usize));
+        assert_eq!(decoder.missing().len(), seq_len as usize - 1);
+        assert!(!decoder.missing().contains(&2));
+        assert!(decoder
+            .receive(&UrEncoder::new(PSBT_UR_TYPE, cbor_bytes(&[0; 500]), 100).part(1))
+            .is_err());
+        assert!(decoder.receive("ur:bytes/aeadaolazmjendeoti").is_err());
+
+        // A crafted sequence length must not make us allocate or overflow
+        let crafted = |seq_len: u64, message_len: u64| {
+            let mut cbor = vec![];
+            cbor_head(4, 5, &mut cbor);
+            cbor_head(0, 1, &mut cbor);
+            cbor_head(0, seq_len, &mut cbor);
+            cbor_head(0, message_len, &mut cbor);
+            cbor_head(0, 0, &mut cbor);
+            cbor.extend(cbor_bytes(&[0; 10]));
+            format!(
+                "ur:{}/1-{}/{}",
+                PSBT_UR_TYPE,
+                seq_len,
+                bytewords_minimal(&cbor)
+            )
+        };
+        for (seq_len, message_len) in [
+            (u32::MAX as u64, 100),
+            (u64::MAX, 100),
+            (u64::MAX / 2, u64::MAX),
+            (10, 1 << 40),
+            (3, 100),
+        ] {
+            assert!(UrDecoder::default()
+                .receive(&crafted(seq_len, message_len))
+                .is_err());
+        }
+        assert!(UrDecoder::default().receive(&crafted(10, 100)).is_ok());
+
+        let single = UrEncoder::new(PSBT_UR_TYPE, cbor_bytes(&[0xde, 0xad]), 100);
+        let mut decoder = UrDecoder::default();
+        decoder.receive(&single.part(1)).unwrap();
+        assert_eq!(decoder.message(), Some(&[0x42, 0xde, 0xad][..]));
+        assert_eq!(decoder.ur_type(), Some(PSBT_UR_TYPE));
+    }
+}
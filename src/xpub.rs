@@ -0,0 +1,164 @@
+#[allow(unused_imports)]
+use log::*;
+
+use yew::prelude::*;
+
+use bitcoin::bip32::Xpub;
+use bitcoin::Network;
+
+use crate::export::copy_to_clipboard;
+use crate::network::NetworkKind;
+use crate::slip132::{
+    decode_extended_key, encode_extended_key, for_network, KeyFormat, ALL_FORMATS,
+};
+
+pub struct XpubConverter {
+    link: ComponentLink<Self>,
+    props: XpubConverterProps,
+
+    open: bool,
+    text: String,
+}
+
+#[derive(Clone, Properties)]
+pub struct XpubConverterProps {
+    pub network: Network,
+}
+
+pub enum XpubConverterMsg {
+    Open,
+    Close,
+    SetText(String),
+}
+
+impl XpubConverter {
+    fn view_key(&self, xpub: &Xpub, format: KeyFormat) -> Html {
+        let kind = NetworkKind::of(self.props.network);
+        let normalized = for_network(xpub, self.props.network);
+        // The same script type as the pasted key, on the selected network
+        let equivalent = format.for_network(kind);
+        let details = [
+            ("Fingerprint", xpub.fingerprint().to_string()),
+            ("Depth", xpub.depth.to_string()),
+            ("Parent fingerprint", xpub.parent_fingerprint.to_string()),
+            ("Child number", xpub.child_number.to_string()),
+        ];
+
+        html! {
+            <>
+                <p class="small">
+                    { format!("A {} for {} ({})", format.name(), format.network().name(), format.script_type()) }
+                </p>
+                {
+                    match format.network() == kind {
+                        true => html! {},
+                        false => html! {
+                            <div class="alert alert-warning py-2 small">
+                                { format!("The key is for {} but {} is selected, the conversions below are for {}", format.network().name(), self.props.network, kind.name()) }
+                            </div>
+                        },
+                    }
+                }
+                <table class="table table-sm small">
+                    <tbody>
+                        {
+                            for details.iter().map(|(label, value)| html! {
+                                <tr><th class="fw-normal text-muted">{ label }</th><td class="font-monospace">{ value }</td></tr>
+                            })
+                        }
+                    </tbody>
+                </table>
+                <h6>{ "Formats" }</h6>
+                {
+                    for ALL_FORMATS.iter().filter(|f| f.network() == kind).map(|f| {
+                        let encoded = encode_extended_key(&normalized, *f);
+                        let copied = encoded.clone();
+                        html! {
+                            <div class="d-flex align-items-start mb-2">
+                                <span class=classes!("badge", "border", "me-2", if *f == equivalent { "bg-primary" } else { "bg-light text-dark" }) title=f.script_type()>{ f.name() }</span>
+                                <span class="font-monospace small text-break flex-grow-1">{ encoded }</span>
+                                <button type="button" class="btn btn-sm btn-link text-secondary p-0 ms-2" title="Copy" onclick=Callback::from(move |_| copy_to_clipboard(&copied))>
+                                    <i class="bi bi-clipboard"></i>
+                                </button>
+                            </div>
+                        }
+                    })
+                }
+            </>
+        }
+    }
+}
+
+impl Component for XpubConverter {
+    type Message = XpubConverterMsg;
+    type Properties = XpubConverterProps;
+
+    fn create(props: Self::Properties, link: ComponentLink<Self>) -> Self {
+        XpubConverter {
+            link,
+            props,
+
+            open: false,
+            text: String::new(),
+        }
+    }
+
+    fn change(&mut self, props: Self::Properties) -> ShouldRender {
+        self.props = props;
+        true
+    }
+
+    fn update(&mut self, msg: Self::Message) -> ShouldRender {
+        match msg {
+            XpubConverterMsg::Open => self.open = true,
+            XpubConverterMsg::Close => {
+                self.open = false;
+                self.text.clear();
+            }
+            XpubConverterMsg::SetText(text) => self.text = text,
+        }
+
+        true
+    }
+
+    fn view(&self) -> Html {
+        let button = html! {
+            <button type="button" class="btn btn-outline-secondary me-2" onclick=self.link.callback(|_| XpubConverterMsg::Open)>
+                <i class="bi bi-key"></i>{ " Xpub converter" }
+            </button>
+        };
+        if !self.open {
+            return button;
+        }
+
+        let text = self.text.trim();
+        let (result, error) = match decode_extended_key(text) {
+            _ if text.is_empty() => (html! {}, None),
+            Ok((xpub, format)) => (self.view_key(&xpub, format), None),
+            Err(e) => (html! {}, Some(e.to_string())),
+        };
+
+        html! {
+            <>
+            { button }
+            <div class="modal d-block" tabindex="-1" style="background: rgba(0, 0, 0, .5)">
+                <div class="modal-dialog modal-dialog-centered modal-lg">
+                    <div class="modal-content">
+                        <div class="modal-header">
+                            <h5 class="modal-title">{ "Extended public key converter" }</h5>
+                            <button type="button" class="btn-close" aria-label="Close" onclick=self.link.callback(|_| XpubConverterMsg::Close)></button>
+                        </div>
+                        <div class="modal-body">
+                            <div class="mb-3">
+                                <textarea class=classes!("form-control", "font-monospace", error.as_ref().map(|_| "is-invalid")) rows="2" placeholder="xpub, ypub, zpub, Ypub, Zpub, tpub, upub, vpub, ..." spellcheck="false" value=self.text.clone() oninput=self.link.callback(|e: InputData| XpubConverterMsg::SetText(e.value))></textarea>
+                                { error.map(|e| html! { <div class="invalid-feedback">{ e }</div> }).unwrap_or_default() }
+                            </div>
+                            { result }
+                        </div>
+                    </div>
+                </div>
+            </div>
+            </>
+        }
+    }
+}